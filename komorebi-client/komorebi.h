@@ -0,0 +1,36 @@
+#ifndef KOMOREBI_H
+#define KOMOREBI_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Called from a background thread with each notification as a JSON string */
+typedef void (*komorebi_subscription_callback)(const char *notification, void *user_data);
+
+/* Send a JSON encoded SocketMessage. Returns 0 on success and -1 on failure. */
+int komorebi_send_message(const char *message);
+
+/* Send a JSON encoded SocketMessage and return the JSON encoded SocketResponse, or NULL on failure,
+   including when komorebi does not respond within 10 seconds.
+   The returned string must be freed with komorebi_free_string. */
+char *komorebi_query(const char *message);
+
+/* Return the JSON encoded SocketResponse for a state query, or NULL on failure.
+   The returned string must be freed with komorebi_free_string. */
+char *komorebi_query_state(void);
+
+/* Free a string returned by komorebi_query or komorebi_query_state */
+void komorebi_free_string(char *string);
+
+/* Subscribe to notifications on the named pipe `name`. Returns 0 on success and -1 on failure. */
+int komorebi_subscribe(const char *name, komorebi_subscription_callback callback, void *user_data);
+
+/* Unsubscribe the named pipe `name`. Returns 0 on success and -1 on failure. */
+int komorebi_unsubscribe(const char *name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KOMOREBI_H */
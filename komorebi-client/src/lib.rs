@@ -0,0 +1,257 @@
+#![warn(clippy::all, clippy::nursery, clippy::pedantic)]
+#![allow(clippy::missing_errors_doc)]
+
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::os::raw::c_char;
+use std::os::raw::c_void;
+use std::path::PathBuf;
+use std::ptr;
+use std::str::FromStr;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+use miow::pipe::NamedPipe;
+use uds_windows::UnixListener;
+use uds_windows::UnixStream;
+
+pub use komorebi_core::SocketMessage;
+pub use komorebi_core::SocketResponse;
+
+// Returned by ConnectNamedPipe when a client connected between CreateNamedPipe and the call
+const ERROR_PIPE_CONNECTED: i32 = 535;
+
+const QUERY_TIMEOUT_SECONDS: u64 = 10;
+const ACCEPT_POLL_INTERVAL_MILLISECONDS: u64 = 5;
+
+static QUERY_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A function that receives each notification sent to a subscriber as a JSON string, along with
+/// the `user_data` pointer that was given when subscribing
+pub type SubscriptionCallback = extern "C" fn(notification: *const c_char, user_data: *mut c_void);
+
+fn socket_path(name: &str) -> Result<PathBuf> {
+    let mut socket = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
+    socket.push(name);
+    Ok(socket)
+}
+
+/// Send a message to komorebi without waiting for a response
+pub fn send_message(message: &SocketMessage) -> Result<()> {
+    let mut stream = UnixStream::connect(socket_path("komorebi.sock")?)?;
+    Ok(stream.write_all(&*message.as_bytes()?)?)
+}
+
+/// Send a message to komorebi and wait for the JSON encoded `SocketResponse`, giving up if there
+/// is no response within `QUERY_TIMEOUT_SECONDS`
+pub fn send_query(message: &SocketMessage) -> Result<String> {
+    // Every query gets a socket of its own, so that queries from different threads, processes
+    // and komorebic itself can never receive each other's responses
+    let name = format!(
+        "komorebi-client-{}-{}.sock",
+        std::process::id(),
+        QUERY_COUNTER.fetch_add(1, Ordering::SeqCst)
+    );
+
+    let socket = socket_path(&name)?;
+
+    match std::fs::remove_file(&socket) {
+        Ok(_) => {}
+        Err(error) => match error.kind() {
+            ErrorKind::NotFound => {}
+            _ => {
+                return Err(error.into());
+            }
+        },
+    };
+
+    let listener = UnixListener::bind(&socket)?;
+    let response = send_message(&SocketMessage::WithResponseTo(
+        name,
+        Box::new(message.clone()),
+    ))
+    .and_then(|()| read_response(&listener));
+
+    if let Err(error) = std::fs::remove_file(&socket) {
+        if error.kind() != ErrorKind::NotFound {
+            return Err(error.into());
+        }
+    }
+
+    response
+}
+
+fn read_response(listener: &UnixListener) -> Result<String> {
+    let timeout = Duration::from_secs(QUERY_TIMEOUT_SECONDS);
+    let started = Instant::now();
+
+    // There is no accept timeout, so the listener is polled until komorebi connects
+    listener.set_nonblocking(true)?;
+    let stream = loop {
+        match listener.accept() {
+            Ok((stream, _)) => break stream,
+            Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                if started.elapsed() >= timeout {
+                    return Err(anyhow!("komorebi did not respond to the query"));
+                }
+
+                std::thread::sleep(Duration::from_millis(ACCEPT_POLL_INTERVAL_MILLISECONDS));
+            }
+            Err(error) => return Err(error.into()),
+        }
+    };
+
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Option::from(timeout))?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_to_string(&mut response)?;
+
+    Ok(response)
+}
+
+/// Create the named pipe `name`, register it with komorebi as a subscriber and call `callback`
+/// with every notification on a background thread until komorebi disconnects the pipe
+pub fn subscribe<F>(name: &str, callback: F) -> Result<()>
+where
+    F: Fn(&str) + Send + 'static,
+{
+    let pipe = NamedPipe::new(format!(r"\\.\pipe\{}", name))?;
+    send_message(&SocketMessage::AddSubscriber(name.to_string()))?;
+
+    std::thread::spawn(move || {
+        if let Err(error) = pipe.connect() {
+            if error.raw_os_error() != Some(ERROR_PIPE_CONNECTED) {
+                return;
+            }
+        }
+
+        for line in BufReader::new(pipe).lines() {
+            match line {
+                Ok(notification) => callback(&notification),
+                Err(_) => break,
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Ask komorebi to stop sending notifications to the subscriber `name`
+pub fn unsubscribe(name: &str) -> Result<()> {
+    send_message(&SocketMessage::RemoveSubscriber(name.to_string()))
+}
+
+struct UserData(*mut c_void);
+
+// The caller of komorebi_subscribe is responsible for user_data being usable from another thread
+unsafe impl Send for UserData {}
+
+unsafe fn borrow_str<'a>(string: *const c_char) -> Result<&'a str> {
+    if string.is_null() {
+        return Err(anyhow!("received a null pointer"));
+    }
+
+    Ok(CStr::from_ptr(string).to_str()?)
+}
+
+fn into_raw_string(string: String) -> *mut c_char {
+    CString::new(string).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+const fn status<T>(result: &Result<T>) -> i32 {
+    match result {
+        Ok(_) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Send a JSON encoded `SocketMessage` to komorebi. Returns 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// `message` must be a valid pointer to a null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn komorebi_send_message(message: *const c_char) -> i32 {
+    status(
+        &borrow_str(message).and_then(|message| send_message(&SocketMessage::from_str(message)?)),
+    )
+}
+
+/// Send a JSON encoded `SocketMessage` to komorebi and return the JSON encoded `SocketResponse`,
+/// or null on failure. The returned string must be freed with `komorebi_free_string`.
+///
+/// # Safety
+///
+/// `message` must be a valid pointer to a null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn komorebi_query(message: *const c_char) -> *mut c_char {
+    borrow_str(message)
+        .and_then(|message| send_query(&SocketMessage::from_str(message)?))
+        .map_or(ptr::null_mut(), into_raw_string)
+}
+
+/// Return the JSON encoded `SocketResponse` for a state query, or null on failure. The returned
+/// string must be freed with `komorebi_free_string`.
+#[no_mangle]
+pub extern "C" fn komorebi_query_state() -> *mut c_char {
+    send_query(&SocketMessage::State).map_or(ptr::null_mut(), into_raw_string)
+}
+
+/// Free a string returned by `komorebi_query` or `komorebi_query_state`
+///
+/// # Safety
+///
+/// `string` must be null or a pointer returned by this library that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn komorebi_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Subscribe to komorebi notifications on the named pipe `name`. `callback` is called from a
+/// background thread with each notification and `user_data`. Returns 0 on success and -1 on
+/// failure.
+///
+/// # Safety
+///
+/// `name` must be a valid pointer to a null-terminated UTF-8 string, and `user_data` must remain
+/// valid and safe to use from another thread for as long as the subscription is active.
+#[no_mangle]
+pub unsafe extern "C" fn komorebi_subscribe(
+    name: *const c_char,
+    callback: SubscriptionCallback,
+    user_data: *mut c_void,
+) -> i32 {
+    let user_data = UserData(user_data);
+
+    status(&borrow_str(name).and_then(|name| {
+        subscribe(name, move |notification| {
+            let user_data = &user_data;
+            if let Ok(notification) = CString::new(notification) {
+                callback(notification.as_ptr(), user_data.0);
+            }
+        })
+    }))
+}
+
+/// Unsubscribe the named pipe `name` from komorebi notifications. Returns 0 on success and -1 on
+/// failure.
+///
+/// # Safety
+///
+/// `name` must be a valid pointer to a null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn komorebi_unsubscribe(name: *const c_char) -> i32 {
+    status(&borrow_str(name).and_then(unsubscribe))
+}
@@ -21,6 +21,7 @@ pub trait Arrangement {
         container_padding: Option<i32>,
         layout_flip: Option<Axis>,
         resize_dimensions: &[Option<Rect>],
+        toggled_splits: &[usize],
     ) -> Vec<Rect>;
 }
 
@@ -33,6 +34,7 @@ impl Arrangement for DefaultLayout {
         container_padding: Option<i32>,
         layout_flip: Option<Axis>,
         resize_dimensions: &[Option<Rect>],
+        toggled_splits: &[usize],
     ) -> Vec<Rect> {
         let len = usize::from(len);
         let mut dimensions = match self {
@@ -42,6 +44,7 @@ impl Arrangement for DefaultLayout {
                 area,
                 layout_flip,
                 calculate_resize_adjustments(resize_dimensions),
+                toggled_splits,
             ),
             DefaultLayout::Columns => columns(area, len),
             DefaultLayout::Rows => rows(area, len),
@@ -226,12 +229,16 @@ impl Arrangement for CustomLayout {
         container_padding: Option<i32>,
         _layout_flip: Option<Axis>,
         _resize_dimensions: &[Option<Rect>],
+        _toggled_splits: &[usize],
     ) -> Vec<Rect> {
         let mut dimensions = vec![];
+        // The column of each container, so that any padding set on the column can be applied
+        let mut column_idxs = vec![];
         let container_count = len.get();
 
         if container_count <= self.len() {
-            let mut layouts = columns(area, container_count);
+            let mut layouts = self.column_areas(area, container_count);
+            column_idxs.extend(0..layouts.len());
             dimensions.append(&mut layouts);
         } else {
             let count_map = self.column_container_counts();
@@ -256,86 +263,48 @@ impl Arrangement for CustomLayout {
                 Option::from(1)
             };
 
-            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-            let primary_right = self.primary_width_percentage().map_or_else(
-                || area.right / self.len() as i32,
-                |percentage| (area.right / 100) * percentage as i32,
-            );
-
-            for (idx, column) in self.iter().enumerate() {
-                // If we are offsetting a tertiary column for which the threshold
-                // has not yet been met, this loop should not run for that final
-                // tertiary column
-                if idx < self.len() - offset.unwrap_or(0) {
-                    let column_area = if idx == 0 {
-                        Self::column_area_with_last(self.len(), area, primary_right, None, offset)
-                    } else {
-                        Self::column_area_with_last(
-                            self.len(),
-                            area,
-                            primary_right,
-                            Option::from(dimensions[self.first_container_idx(idx - 1)]),
-                            offset,
-                        )
-                    };
+            // If we are offsetting a tertiary column for which the threshold
+            // has not yet been met, there is no area for that final tertiary column
+            let column_areas = self.column_areas(area, self.len() - offset.unwrap_or(0));
 
-                    match column {
-                        Column::Primary(Option::Some(_)) => {
-                            let main_column_area = if idx == 0 {
-                                Self::main_column_area(area, primary_right, None)
-                            } else {
-                                Self::main_column_area(
-                                    area,
-                                    primary_right,
-                                    Option::from(dimensions[self.first_container_idx(idx - 1)]),
-                                )
-                            };
-
-                            dimensions.push(main_column_area);
+            for (idx, (column, column_area)) in self.iter().zip(column_areas).enumerate() {
+                match column {
+                    Column::Primary(_) | Column::Secondary(None) => {
+                        dimensions.push(column_area);
+                    }
+                    Column::Secondary(Some(split)) => match split {
+                        ColumnSplitWithCapacity::Horizontal(capacity) => {
+                            let mut rows = rows(&column_area, *capacity);
+                            dimensions.append(&mut rows);
                         }
-                        Column::Primary(None) | Column::Secondary(None) => {
-                            dimensions.push(column_area);
+                        ColumnSplitWithCapacity::Vertical(capacity) => {
+                            let mut columns = columns(&column_area, *capacity);
+                            dimensions.append(&mut columns);
                         }
-                        Column::Secondary(Some(split)) => match split {
-                            ColumnSplitWithCapacity::Horizontal(capacity) => {
-                                let mut rows = rows(&column_area, *capacity);
+                    },
+                    Column::Tertiary(split) => {
+                        let remaining = container_count - tertiary_trigger_threshold;
+
+                        match split {
+                            ColumnSplit::Horizontal => {
+                                let mut rows = rows(&column_area, remaining);
                                 dimensions.append(&mut rows);
                             }
-                            ColumnSplitWithCapacity::Vertical(capacity) => {
-                                let mut columns = columns(&column_area, *capacity);
+                            ColumnSplit::Vertical => {
+                                let mut columns = columns(&column_area, remaining);
                                 dimensions.append(&mut columns);
                             }
-                        },
-                        Column::Tertiary(split) => {
-                            let column_area = Self::column_area_with_last(
-                                self.len(),
-                                area,
-                                primary_right,
-                                Option::from(dimensions[self.first_container_idx(idx - 1)]),
-                                offset,
-                            );
-
-                            let remaining = container_count - tertiary_trigger_threshold;
-
-                            match split {
-                                ColumnSplit::Horizontal => {
-                                    let mut rows = rows(&column_area, remaining);
-                                    dimensions.append(&mut rows);
-                                }
-                                ColumnSplit::Vertical => {
-                                    let mut columns = columns(&column_area, remaining);
-                                    dimensions.append(&mut columns);
-                                }
-                            }
                         }
                     }
                 }
+
+                column_idxs.resize(dimensions.len(), idx);
             }
         }
 
-        dimensions
-            .iter_mut()
-            .for_each(|l| l.add_padding(container_padding));
+        for (l, idx) in dimensions.iter_mut().zip(column_idxs) {
+            l.add_padding(self.column_padding(idx).or(container_padding));
+        }
 
         dimensions
     }
@@ -350,7 +319,7 @@ pub enum Axis {
 }
 
 #[must_use]
-fn columns(area: &Rect, len: usize) -> Vec<Rect> {
+pub fn columns(area: &Rect, len: usize) -> Vec<Rect> {
     #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
     let right = area.right / len as i32;
     let mut left = 0;
@@ -371,7 +340,7 @@ fn columns(area: &Rect, len: usize) -> Vec<Rect> {
 }
 
 #[must_use]
-fn rows(area: &Rect, len: usize) -> Vec<Rect> {
+pub fn rows(area: &Rect, len: usize) -> Vec<Rect> {
     #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
     let bottom = area.bottom / len as i32;
     let mut top = 0;
@@ -480,6 +449,7 @@ fn recursive_fibonacci(
     area: &Rect,
     layout_flip: Option<Axis>,
     resize_adjustments: Vec<Option<Rect>>,
+    toggled_splits: &[usize],
 ) -> Vec<Rect> {
     let mut a = *area;
 
@@ -530,6 +500,10 @@ fn recursive_fibonacci(
         alt_y = resized.top + half_resized_height;
     }
 
+    // Odd splits stack containers on top of each other and even splits place them side by side,
+    // unless the orientation of that particular split has been toggled
+    let stacked_split = (idx % 2 != 0) != toggled_splits.contains(&idx);
+
     #[allow(clippy::if_not_else)]
     if count == 0 {
         vec![]
@@ -540,7 +514,7 @@ fn recursive_fibonacci(
             right: resized.right,
             bottom: resized.bottom,
         }]
-    } else if idx % 2 != 0 {
+    } else if stacked_split {
         let mut res = vec![Rect {
             left: resized.left,
             top: main_y,
@@ -558,6 +532,7 @@ fn recursive_fibonacci(
             },
             layout_flip,
             resize_adjustments,
+            toggled_splits,
         ));
         res
     } else {
@@ -578,6 +553,7 @@ fn recursive_fibonacci(
             },
             layout_flip,
             resize_adjustments,
+            toggled_splits,
         ));
         res
     }
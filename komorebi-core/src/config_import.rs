@@ -0,0 +1,286 @@
+use std::path::Path;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+use serde::Deserialize;
+
+use crate::ApplicationIdentifier;
+use crate::SocketMessage;
+
+/// A window rule from another tiling window manager's configuration that komorebi can express
+#[derive(Clone, Debug)]
+pub enum ImportedRule {
+    /// Windows matching this rule should never be tiled
+    Float(ApplicationIdentifier, String),
+    /// Windows matching this rule should be sent to the workspace at this index
+    Workspace(ApplicationIdentifier, String, usize),
+}
+
+impl ImportedRule {
+    #[must_use]
+    pub fn socket_message(&self, monitor_idx: usize) -> SocketMessage {
+        match self {
+            Self::Float(identifier, id) => SocketMessage::FloatRule(identifier.clone(), id.clone()),
+            Self::Workspace(identifier, id, workspace_idx) => SocketMessage::WorkspaceRule(
+                identifier.clone(),
+                id.clone(),
+                monitor_idx,
+                *workspace_idx,
+            ),
+        }
+    }
+
+    /// The equivalent komorebic command, so that imported rules can be added to a configuration
+    #[must_use]
+    pub fn komorebic_command(&self, monitor_idx: usize) -> String {
+        match self {
+            Self::Float(identifier, id) => {
+                format!("komorebic.exe float-rule {} \"{}\"", identifier, id)
+            }
+            Self::Workspace(identifier, id, workspace_idx) => format!(
+                "komorebic.exe workspace-rule {} \"{}\" {} {}",
+                identifier, id, monitor_idx, workspace_idx
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ImportedConfig {
+    pub rules: Vec<ImportedRule>,
+    /// Descriptions of rules which could not be converted
+    pub skipped: Vec<String>,
+}
+
+impl ImportedConfig {
+    /// Convert the window rules in a GlazeWM (.yaml, .yml) or workspacer (.cs, .csx) configuration
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+
+        match path.extension().and_then(std::ffi::OsStr::to_str) {
+            Some("yaml" | "yml") => Self::from_glazewm(&contents),
+            Some("cs" | "csx") => Ok(Self::from_workspacer(&contents)),
+            _ => Err(anyhow!(
+                "unsupported configuration format, expected a GlazeWM .yaml or workspacer .cs file"
+            )),
+        }
+    }
+
+    pub fn from_glazewm(contents: &str) -> Result<Self> {
+        let config: GlazeWmConfig = serde_yaml::from_str(contents)?;
+        let workspaces: Vec<String> = config
+            .workspaces
+            .into_iter()
+            .map(|workspace| workspace.name)
+            .collect();
+
+        let mut imported = Self::default();
+
+        for rule in config.window_rules {
+            let commands = rule
+                .command
+                .iter()
+                .chain(rule.commands.iter().flatten())
+                .cloned()
+                .collect::<Vec<_>>();
+
+            let mut matchers = vec![];
+            for (identifier, value) in [
+                (ApplicationIdentifier::Exe, &rule.match_process_name),
+                (ApplicationIdentifier::Class, &rule.match_class_name),
+                (ApplicationIdentifier::Title, &rule.match_title),
+            ] {
+                if let Some(value) = value {
+                    match glazewm_literals(value) {
+                        Some(literals) => {
+                            for literal in literals {
+                                // GlazeWM process names do not include the extension
+                                let id = match identifier {
+                                    ApplicationIdentifier::Exe => format!("{}.exe", literal),
+                                    _ => literal,
+                                };
+
+                                matchers.push((identifier.clone(), id));
+                            }
+                        }
+                        None => imported.skipped.push(format!(
+                            "{} is a regular expression which cannot be converted",
+                            value
+                        )),
+                    }
+                }
+            }
+
+            for command in commands {
+                // Commands are case insensitive, but workspace names are not
+                let command = command.trim();
+                let workspace_prefix = "move to workspace ";
+                let workspace_name = command
+                    .get(..workspace_prefix.len())
+                    .filter(|verb| verb.eq_ignore_ascii_case(workspace_prefix))
+                    .map(|_| &command[workspace_prefix.len()..]);
+
+                if command.eq_ignore_ascii_case("ignore")
+                    || command.eq_ignore_ascii_case("set floating")
+                {
+                    for (identifier, id) in &matchers {
+                        imported
+                            .rules
+                            .push(ImportedRule::Float(identifier.clone(), id.clone()));
+                    }
+                } else if let Some(name) = workspace_name {
+                    match workspaces.iter().position(|workspace| workspace == name) {
+                        Some(workspace_idx) => {
+                            for (identifier, id) in &matchers {
+                                imported.rules.push(ImportedRule::Workspace(
+                                    identifier.clone(),
+                                    id.clone(),
+                                    workspace_idx,
+                                ));
+                            }
+                        }
+                        None => imported
+                            .skipped
+                            .push(format!("there is no workspace named '{}'", name)),
+                    }
+                } else {
+                    imported
+                        .skipped
+                        .push(format!("'{}' has no komorebi equivalent", command));
+                }
+            }
+        }
+
+        Ok(imported)
+    }
+
+    #[must_use]
+    pub fn from_workspacer(contents: &str) -> Self {
+        let mut imported = Self::default();
+        let mut workspaces = vec![];
+        let mut routes = vec![];
+
+        for statement in contents.split(';').map(str::trim) {
+            if statement.contains("CreateWorkspace") {
+                workspaces.extend(string_literals(statement));
+            } else if statement.contains("AddFilter") {
+                match workspacer_condition(statement) {
+                    // A filter returning false for a window stops workspacer from managing it
+                    Some((identifier, id, true)) => {
+                        imported.rules.push(ImportedRule::Float(identifier, id));
+                    }
+                    _ => imported.skipped.push(statement.to_string()),
+                }
+            } else if statement.contains("AddRoute") {
+                routes.push(statement);
+            }
+        }
+
+        for statement in routes {
+            let workspace = statement
+                .split_once("WorkspaceContainer[")
+                .and_then(|(_, rest)| string_literals(rest).into_iter().next());
+
+            let workspace_idx = workspace
+                .and_then(|workspace| workspaces.iter().position(|name| *name == workspace));
+
+            match (workspacer_condition(statement), workspace_idx) {
+                (Some((identifier, id, false)), Some(workspace_idx)) => {
+                    imported
+                        .rules
+                        .push(ImportedRule::Workspace(identifier, id, workspace_idx));
+                }
+                _ => imported.skipped.push(statement.to_string()),
+            }
+        }
+
+        imported
+    }
+}
+
+#[derive(Deserialize)]
+struct GlazeWmConfig {
+    #[serde(default)]
+    workspaces: Vec<GlazeWmWorkspace>,
+    #[serde(default)]
+    window_rules: Vec<GlazeWmWindowRule>,
+}
+
+#[derive(Deserialize)]
+struct GlazeWmWorkspace {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GlazeWmWindowRule {
+    command: Option<String>,
+    commands: Option<Vec<String>>,
+    match_process_name: Option<String>,
+    match_class_name: Option<String>,
+    match_title: Option<String>,
+}
+
+// GlazeWM values are either exact strings or /regular expressions/; only regular expressions
+// which are an alternation of literal strings (ie. /Taskmgr|ScreenClippingHost/) can be converted
+fn glazewm_literals(value: &str) -> Option<Vec<String>> {
+    let pattern = match value.strip_prefix('/').and_then(|v| v.strip_suffix('/')) {
+        None => return Some(vec![value.to_string()]),
+        Some(pattern) => pattern,
+    };
+
+    let is_literal = |c: char| !"\\^$.*+?()[]{}".contains(c);
+    if !pattern.chars().all(is_literal) {
+        return None;
+    }
+
+    Some(pattern.split('|').map(ToString::to_string).collect())
+}
+
+// Finds a window.Property.Equals("value") or window.Property == "value" comparison, returning
+// whether it was negated
+fn workspacer_condition(statement: &str) -> Option<(ApplicationIdentifier, String, bool)> {
+    for (property, identifier, suffix) in [
+        ("ProcessFileName", ApplicationIdentifier::Exe, ""),
+        ("ProcessName", ApplicationIdentifier::Exe, ".exe"),
+        ("Class", ApplicationIdentifier::Class, ""),
+        ("Title", ApplicationIdentifier::Title, ""),
+    ] {
+        let accessor = format!(".{}", property);
+        let (before, after) = match statement.split_once(&accessor) {
+            None => continue,
+            Some(split) => split,
+        };
+
+        let after = after.trim_start();
+        let (rest, negated_comparison) = if let Some(rest) = after.strip_prefix(".Equals(") {
+            (rest, false)
+        } else if let Some(rest) = after.strip_prefix("==") {
+            (rest, false)
+        } else if let Some(rest) = after.strip_prefix("!=") {
+            (rest, true)
+        } else {
+            continue;
+        };
+
+        let value = string_literals(rest).into_iter().next()?;
+
+        // The lambda parameter can have any name, so look for a ! in front of whatever it is
+        let negated_access = before
+            .rsplit(|c: char| !(c.is_alphanumeric() || c == '_' || c == '!'))
+            .next()
+            .map_or(false, |token| token.starts_with('!'));
+
+        let negated = negated_comparison != negated_access;
+        return Option::from((identifier, format!("{}{}", value, suffix), negated));
+    }
+
+    None
+}
+
+fn string_literals(text: &str) -> Vec<String> {
+    text.split('"')
+        .skip(1)
+        .step_by(2)
+        .map(ToString::to_string)
+        .collect()
+}
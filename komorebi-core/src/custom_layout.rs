@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 use std::fs::File;
+use std::fs::OpenOptions;
 use std::io::BufReader;
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::path::Path;
 use std::path::PathBuf;
 
 use color_eyre::eyre::anyhow;
@@ -13,19 +15,83 @@ use serde::Serialize;
 use crate::Rect;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct CustomLayout(Vec<Column>);
+#[serde(from = "Vec<ColumnDefinition>", into = "Vec<ColumnDefinition>")]
+pub struct CustomLayout {
+    columns: Vec<Column>,
+    widths: Vec<Option<ColumnWidth>>,
+    paddings: Vec<Option<i32>>,
+    gaps: Vec<Option<i32>>,
+}
 
 impl Deref for CustomLayout {
     type Target = Vec<Column>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.columns
     }
 }
 
 impl DerefMut for CustomLayout {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.columns
+    }
+}
+
+/// A column as it is written in a custom layout file, where any column can optionally be given a
+/// fixed width, its own container padding and a gap to the next column alongside its configuration
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ColumnDefinition {
+    #[serde(flatten)]
+    pub column: Column,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<ColumnWidth>,
+    /// Replaces the container padding of the workspace for the containers in this column
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub padding: Option<i32>,
+    /// Pixels of empty space between this column and the next one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap: Option<i32>,
+}
+
+impl From<Vec<ColumnDefinition>> for CustomLayout {
+    fn from(definitions: Vec<ColumnDefinition>) -> Self {
+        let mut layout = Self {
+            columns: vec![],
+            widths: vec![],
+            paddings: vec![],
+            gaps: vec![],
+        };
+
+        for definition in definitions {
+            layout.columns.push(definition.column);
+            layout.widths.push(definition.width);
+            layout.paddings.push(definition.padding);
+            layout.gaps.push(definition.gap);
+        }
+
+        layout
+    }
+}
+
+impl From<CustomLayout> for Vec<ColumnDefinition> {
+    fn from(layout: CustomLayout) -> Self {
+        let widths = layout.widths.into_iter().chain(std::iter::repeat(None));
+        let paddings = layout.paddings.into_iter().chain(std::iter::repeat(None));
+        let gaps = layout.gaps.into_iter().chain(std::iter::repeat(None));
+
+        layout
+            .columns
+            .into_iter()
+            .zip(widths)
+            .zip(paddings)
+            .zip(gaps)
+            .map(|(((column, width), padding), gap)| ColumnDefinition {
+                column,
+                width,
+                padding,
+                gap,
+            })
+            .collect()
     }
 }
 
@@ -52,6 +118,115 @@ impl CustomLayout {
         Ok(layout)
     }
 
+    /// Build a layout from the rects of the containers on a workspace, which have to be arranged in
+    /// columns of one or more containers stacked on top of each other. The leftmost column with a
+    /// single container becomes the primary column, and every column except the last is given the
+    /// percentage of the total width that it currently takes up.
+    pub fn from_rects(rects: &[Rect]) -> Result<Self> {
+        let mut columns: Vec<(i32, i32, usize)> = vec![];
+        for rect in rects {
+            let right = rect.left + rect.right;
+            match columns
+                .iter_mut()
+                .find(|(left, r, _)| *left == rect.left && *r == right)
+            {
+                Some((_, _, count)) => *count += 1,
+                None => columns.push((rect.left, right, 1)),
+            }
+        }
+
+        columns.sort_by_key(|(left, _, _)| *left);
+
+        if columns.len() < 2 {
+            return Err(anyhow!(
+                "a custom layout needs at least two columns of containers"
+            ));
+        }
+
+        if columns.windows(2).any(|pair| pair[0].1 > pair[1].0) {
+            return Err(anyhow!(
+                "the containers on this workspace are not arranged in columns"
+            ));
+        }
+
+        let primary_idx = columns[..columns.len() - 1]
+            .iter()
+            .position(|(_, _, count)| *count == 1)
+            .ok_or_else(|| anyhow!("a custom layout needs a column with a single container"))?;
+
+        let start = columns[0].0;
+        let total = columns[columns.len() - 1].1 - start;
+        if total <= 0 {
+            return Err(anyhow!("the containers on this workspace have no width"));
+        }
+
+        let mut definitions = vec![];
+        for (idx, (left, _, count)) in columns.iter().enumerate() {
+            if idx == columns.len() - 1 {
+                definitions.push(ColumnDefinition {
+                    column: Column::Tertiary(ColumnSplit::Horizontal),
+                    width: None,
+                    padding: None,
+                    gap: None,
+                });
+
+                continue;
+            }
+
+            // Measuring to the next column includes the padding between the two
+            let width = columns[idx + 1].0 - left;
+            #[allow(clippy::cast_sign_loss)]
+            let percentage = ColumnWidth::WidthPercentage((width * 100 / total) as usize);
+
+            definitions.push(if idx == primary_idx {
+                ColumnDefinition {
+                    column: Column::Primary(Option::from(percentage)),
+                    width: None,
+                    padding: None,
+                    gap: None,
+                }
+            } else if *count == 1 {
+                ColumnDefinition {
+                    column: Column::Secondary(None),
+                    width: Option::from(percentage),
+                    padding: None,
+                    gap: None,
+                }
+            } else {
+                ColumnDefinition {
+                    column: Column::Secondary(Option::from(ColumnSplitWithCapacity::Horizontal(
+                        *count,
+                    ))),
+                    width: Option::from(percentage),
+                    padding: None,
+                    gap: None,
+                }
+            });
+        }
+
+        Ok(Self::from(definitions))
+    }
+
+    /// Write this layout as json if `path` has a json extension, and as yaml otherwise
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open(path)?;
+
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "json")
+        {
+            serde_json::to_writer_pretty(&file, self)?;
+        } else {
+            serde_yaml::to_writer(&file, self)?;
+        }
+
+        Ok(())
+    }
+
     #[must_use]
     pub fn column_with_idx(&self, idx: usize) -> (usize, Option<&Column>) {
         let column_idx = self.column_for_container_idx(idx);
@@ -190,62 +365,112 @@ impl CustomLayout {
         self.len() - 1
     }
 
+    /// The width of a column, either given explicitly or through the configuration of the primary
+    /// column
     #[must_use]
-    pub fn column_area(&self, work_area: &Rect, idx: usize, offset: Option<usize>) -> Rect {
-        let divisor = offset.map_or_else(|| self.len(), |offset| self.len() - offset);
+    pub fn column_width(&self, idx: usize) -> Option<ColumnWidth> {
+        if let Some(Some(width)) = self.widths.get(idx) {
+            return Option::from(*width);
+        }
 
-        #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
-        let equal_width = work_area.right / divisor as i32;
-        let mut left = work_area.left;
-        let right = equal_width;
+        match self.get(idx) {
+            Some(Column::Primary(width)) => *width,
+            _ => None,
+        }
+    }
 
-        for _ in 0..idx {
-            left += right;
+    /// A copy of this layout with the padding and gap of every column passed through `scale`, for
+    /// paddings and gaps which are given in logical pixels
+    #[must_use]
+    pub fn with_scaled_spacing(&self, scale: impl Fn(i32) -> i32) -> Self {
+        let mut layout = self.clone();
+        for spacing in layout.paddings.iter_mut().chain(layout.gaps.iter_mut()) {
+            *spacing = spacing.map(&scale);
         }
 
-        Rect {
-            left,
-            top: work_area.top,
-            right,
-            bottom: work_area.bottom,
+        layout
+    }
+
+    /// The container padding of a column, if it replaces the container padding of the workspace
+    #[must_use]
+    pub fn column_padding(&self, idx: usize) -> Option<i32> {
+        self.paddings.get(idx).copied().flatten()
+    }
+
+    /// The gap between a column and the next one, where the last of `count` columns has no gap
+    fn column_gap(&self, idx: usize, count: usize) -> i32 {
+        if idx + 1 >= count {
+            return 0;
         }
+
+        self.gaps.get(idx).copied().flatten().unwrap_or(0).max(0)
     }
 
+    /// The areas of the first `count` columns. Columns with a fixed width get exactly that width,
+    /// and whatever is left over after any gaps is shared equally between the remaining flexible
+    /// columns. If every column has a fixed width, the last of them absorbs the remainder instead.
     #[must_use]
-    pub fn column_area_with_last(
-        len: usize,
-        work_area: &Rect,
-        primary_right: i32,
-        last_column: Option<Rect>,
-        offset: Option<usize>,
-    ) -> Rect {
-        let divisor = offset.map_or_else(|| len - 1, |offset| len - offset - 1);
+    pub fn column_areas(&self, work_area: &Rect, count: usize) -> Vec<Rect> {
+        let fixed = (0..count)
+            .map(|idx| {
+                self.column_width(idx)
+                    .map(|width| width.pixels(work_area.right))
+            })
+            .collect::<Vec<_>>();
+
+        let gaps = (0..count)
+            .map(|idx| self.column_gap(idx, count))
+            .sum::<i32>();
+
+        let flexible = fixed.iter().filter(|width| width.is_none()).count();
+        let remainder = (work_area.right - gaps - fixed.iter().flatten().sum::<i32>()).max(0);
 
         #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
-        let equal_width = (work_area.right - primary_right) / divisor as i32;
-        let left = last_column.map_or(work_area.left, |last| last.left + last.right);
-        let right = equal_width;
+        let flexible_width = if flexible == 0 {
+            0
+        } else {
+            remainder / flexible as i32
+        };
 
-        Rect {
-            left,
-            top: work_area.top,
-            right,
-            bottom: work_area.bottom,
+        let mut areas = vec![];
+        let mut left = work_area.left;
+
+        for (idx, width) in fixed.iter().enumerate() {
+            let mut right = width.unwrap_or(flexible_width);
+            if flexible == 0 && idx == count - 1 {
+                right += remainder;
+            }
+
+            areas.push(Rect {
+                left,
+                top: work_area.top,
+                right,
+                bottom: work_area.bottom,
+            });
+
+            left += right + self.column_gap(idx, count);
         }
+
+        areas
     }
 
     #[must_use]
-    pub fn main_column_area(
-        work_area: &Rect,
-        primary_right: i32,
-        last_column: Option<Rect>,
-    ) -> Rect {
-        let left = last_column.map_or(work_area.left, |last| last.left + last.right);
+    pub fn column_area(&self, work_area: &Rect, idx: usize, offset: Option<usize>) -> Rect {
+        let divisor = offset.map_or_else(|| self.len(), |offset| self.len() - offset);
+
+        #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+        let equal_width = work_area.right / divisor as i32;
+        let mut left = work_area.left;
+        let right = equal_width;
+
+        for _ in 0..idx {
+            left += right;
+        }
 
         Rect {
             left,
             top: work_area.top,
-            right: primary_right,
+            right,
             bottom: work_area.bottom,
         }
     }
@@ -262,6 +487,21 @@ pub enum Column {
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum ColumnWidth {
     WidthPercentage(usize),
+    WidthPixels(usize),
+}
+
+impl ColumnWidth {
+    /// The width in pixels, which can never be more than the total width available
+    #[must_use]
+    pub fn pixels(self, total: i32) -> i32 {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let pixels = match self {
+            Self::WidthPercentage(percentage) => (total / 100) * percentage as i32,
+            Self::WidthPixels(pixels) => pixels as i32,
+        };
+
+        pixels.min(total)
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
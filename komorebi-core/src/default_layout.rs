@@ -8,7 +8,9 @@ use crate::OperationDirection;
 use crate::Rect;
 use crate::Sizing;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, Display, EnumString, ArgEnum)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Display, EnumString, ArgEnum,
+)]
 #[strum(serialize_all = "snake_case")]
 pub enum DefaultLayout {
     BSP,
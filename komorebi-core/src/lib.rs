@@ -10,27 +10,48 @@ use serde::Deserialize;
 use serde::Serialize;
 use strum::Display;
 use strum::EnumString;
+use strum::EnumVariantNames;
+use strum::VariantNames;
 
 pub use arrangement::Arrangement;
 pub use arrangement::Axis;
+pub use config_import::ImportedConfig;
+pub use config_import::ImportedRule;
 pub use custom_layout::CustomLayout;
 pub use cycle_direction::CycleDirection;
 pub use default_layout::DefaultLayout;
 pub use direction::Direction;
 pub use layout::Layout;
+pub use notification::NotificationEvent;
+pub use notification::NotificationWindow;
 pub use operation_direction::OperationDirection;
 pub use rect::Rect;
+pub use saved_layout::SavedLayout;
+pub use size_constraints::SizeConstraint;
+pub use size_constraints::SizeConstraints;
+pub use workspace_snapshot::ApplicationPlaceholder;
+pub use workspace_snapshot::SnapshotContainer;
+pub use workspace_snapshot::WorkspaceSnapshot;
 
 pub mod arrangement;
+pub mod config_import;
 pub mod custom_layout;
 pub mod cycle_direction;
 pub mod default_layout;
 pub mod direction;
 pub mod layout;
+pub mod notification;
 pub mod operation_direction;
 pub mod rect;
+pub mod saved_layout;
+pub mod size_constraints;
+pub mod workspace_snapshot;
 
-#[derive(Clone, Debug, Serialize, Deserialize, Display)]
+/// The version of the socket protocol, which is incremented whenever the meaning or the arguments
+/// of an existing `SocketMessage` variant change
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Display, EnumVariantNames)]
 #[serde(tag = "type", content = "content")]
 pub enum SocketMessage {
     // Window / Container Commands
@@ -40,69 +61,172 @@ pub enum SocketMessage {
     CycleMoveWindow(CycleDirection),
     StackWindow(OperationDirection),
     ResizeWindowEdge(OperationDirection, Sizing),
+    CycleSplitRatio(CycleDirection),
     ResizeWindowAxis(Axis, Sizing),
     UnstackWindow,
     CycleStack(CycleDirection),
+    NestContainer(Axis),
+    UnnestContainer,
+    DissolveContainer,
+    NextWindowDirection(OperationDirection),
+    FocusContainerById(String),
+    FocusWindowByHwnd(isize),
+    ShowContainerHints,
+    FocusHint(String),
+    MoveContainerById(String, usize, usize),
     MoveContainerToMonitorNumber(usize),
     MoveContainerToWorkspaceNumber(usize),
+    MoveContainerToWorkspaceNumberAndFollow(usize, bool),
     SendContainerToMonitorNumber(usize),
     SendContainerToWorkspaceNumber(usize),
     MoveWorkspaceToMonitorNumber(usize),
+    MoveWorkspaceToIndex(usize),
     Promote,
+    BalanceWorkspace,
+    FocusPrimaryZone,
     ToggleFloat,
+    TileFloat(OperationDirection),
     ToggleMonocle,
+    ToggleMonoclePip,
+    RaiseWindow,
+    LowerWindow,
+    ToggleWindowOnTop,
+    ToggleFocusMode,
     ToggleMaximize,
+    ToggleContainerMaximize,
     ToggleWindowContainerBehaviour,
     WindowHidingBehaviour(HidingBehaviour),
+    OnEmptyWorkspace(EmptyWorkspaceBehaviour),
+    HidingBehaviourRule(ApplicationIdentifier, String, HidingBehaviour),
     // Current Workspace Commands
     ManageFocusedWindow,
     UnmanageFocusedWindow,
     AdjustContainerPadding(Sizing, i32),
     AdjustWorkspacePadding(Sizing, i32),
+    AdjustInnerGap(Sizing, i32),
+    AdjustOuterGap(Sizing, i32),
     ChangeLayout(DefaultLayout),
     ChangeLayoutCustom(PathBuf),
+    CycleLayout(CycleDirection),
     FlipLayout(Axis),
+    ToggleSplitOrientation,
     // Monitor and Workspace Commands
     EnsureWorkspaces(usize, usize),
+    MonitorGroup(usize, usize, i32),
+    RemoveMonitorGroup(usize),
+    ToggleWorkspaceSync,
+    AddExclusionZone(usize, Rect),
+    ClearExclusionZones(usize),
+    MonitorWorkAreaSize(usize, Rect),
+    ClearMonitorWorkAreaSize(usize),
+    MonitorWorkspaceSync(usize, bool),
     NewWorkspace,
     ToggleTiling,
     Stop,
+    CancelPendingOperations,
+    Restart,
     TogglePause,
     Retile,
+    Reconcile,
     QuickSave,
     QuickLoad,
     Save(PathBuf),
     Load(PathBuf),
+    SaveWorkspaceSnapshot(PathBuf),
+    GenerateCustomLayoutFromWorkspace(PathBuf),
+    LoadWorkspaceSnapshot(PathBuf),
+    SpawnIntoWorkspace(String, usize, usize),
     CycleFocusMonitor(CycleDirection),
     CycleFocusWorkspace(CycleDirection),
     FocusMonitorNumber(usize),
     FocusWorkspaceNumber(usize),
     FocusMonitorWorkspaceNumber(usize, usize),
+    FocusWorkspaceBack,
+    FocusWorkspaceForward,
+    PeekWorkspace(usize),
+    EndPeek,
     ContainerPadding(usize, usize, i32),
+    ContainerPaddingScale(usize, usize, Vec<i32>),
+    WorkspaceResizeDelta(usize, usize, i32),
     WorkspacePadding(usize, usize, i32),
+    InnerGap(usize, usize, i32),
+    OuterGap(usize, usize, i32),
     WorkspaceTiling(usize, usize, bool),
+    WorkspaceTaskbarHidden(usize, usize, bool),
+    WorkspaceFocusFollowsMouse(usize, usize, bool),
+    WorkspaceLogicalPadding(usize, usize, bool),
     WorkspaceName(usize, usize, String),
     WorkspaceLayout(usize, usize, DefaultLayout),
     WorkspaceLayoutCustom(usize, usize, PathBuf),
+    WorkspaceLayoutRotation(usize, usize, Vec<DefaultLayout>),
+    WorkspaceLayoutCycleInterval(usize, usize, u64, Vec<DefaultLayout>),
+    WorkspaceMultiStack(usize, usize, usize),
+    CloneWorkspaceLayoutToMonitor(usize, usize, usize),
+    WorkspaceWallpaper(usize, usize, PathBuf),
+    SetContainerResizeDimensions(usize, usize, usize, Rect),
     // Configuration
     ReloadConfiguration,
     WatchConfiguration(bool),
     InvisibleBorders(Rect),
     WorkAreaOffset(Rect),
     ResizeDelta(i32),
+    FocusModeWidth(u8),
+    ThrottleRetiles(bool),
+    RetileFpsCap(u32),
+    RetileBatchSize(usize),
+    MessageRateLimit(u32, u32),
     WorkspaceRule(ApplicationIdentifier, String, usize, usize),
+    ApplyWorkspaceRule(ApplicationIdentifier, String, usize, usize),
+    WorkspaceRuleOnce(ApplicationIdentifier, String, usize, usize),
+    BoundWorkspaceRule(ApplicationIdentifier, String, String),
+    ApplyWorkspaceRules,
+    WorkspaceRulesOnTitleChange(bool),
     FloatRule(ApplicationIdentifier, String),
+    FloatRuleOnce(ApplicationIdentifier, String),
     ManageRule(ApplicationIdentifier, String),
+    FollowRule(ApplicationIdentifier, String),
+    SwallowRule(ApplicationIdentifier, String),
+    AppGroupRule(ApplicationIdentifier, String),
     IdentifyTrayApplication(ApplicationIdentifier, String),
     IdentifyBorderOverflow(ApplicationIdentifier, String),
+    IdentifyNoBorderOverflow(ApplicationIdentifier, String),
+    AddClassBlocklistPattern(String),
+    RemoveClassBlocklistPattern(String),
+    SizeConstraintRule(ApplicationIdentifier, String, SizeConstraint, i32),
+    AddRuleGroup(String),
+    EndRuleGroup,
+    ToggleRuleGroup(String),
     State,
     Query(StateQuery),
+    QueryHealth,
+    QueryWindowList,
+    QueryMruWindowList,
+    LearningMode(u64),
+    QueryRuleSuggestions,
+    QueryConfigurationDiff,
+    ProtocolVersion,
+    WithResponse(Box<SocketMessage>),
+    WithResponseTo(String, Box<SocketMessage>),
+    DefineAlias(String, Vec<SocketMessage>),
+    InvokeAlias(String),
     FocusFollowsMouse(FocusFollowsMouseImplementation, bool),
     ToggleFocusFollowsMouse(FocusFollowsMouseImplementation),
     MouseFollowsFocus(bool),
     ToggleMouseFollowsFocus,
+    MouseFollowsFocusPlacement(CursorPlacement, i32),
+    StackIndicator(bool),
+    FloatElevatedWindows(bool),
     AddSubscriber(String),
     RemoveSubscriber(String),
+    WebSocketServer(u16, String),
+    WebSocketAllowOrigin(String),
+    MirrorListen(u16, String),
+    MirrorConnect(String, String),
+    PeerMessage(Box<SocketMessage>),
+    QueryPeerState,
+    SetLogLevel(String),
+    PerformanceCounters(bool),
+    ResetPerformanceCounters,
 }
 
 impl SocketMessage {
@@ -119,6 +243,67 @@ impl FromStr for SocketMessage {
     }
 }
 
+/// The response to a `ProtocolVersion` handshake, so that clients can check which messages a
+/// daemon supports before sending them
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProtocolInfo {
+    pub version: u32,
+    pub messages: Vec<String>,
+}
+
+impl Default for ProtocolInfo {
+    fn default() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            messages: SocketMessage::VARIANTS
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl ProtocolInfo {
+    #[must_use]
+    pub fn supports(&self, message: &SocketMessage) -> bool {
+        self.messages.contains(&message.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Display)]
+#[serde(rename_all = "snake_case")]
+pub enum SocketResponseStatus {
+    Ok,
+    Error,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SocketResponse {
+    pub status: SocketResponseStatus,
+    pub data: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+impl SocketResponse {
+    #[must_use]
+    pub const fn ok(data: Option<serde_json::Value>) -> Self {
+        Self {
+            status: SocketResponseStatus::Ok,
+            data,
+            error: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn error(error: String) -> Self {
+        Self {
+            status: SocketResponseStatus::Error,
+            data: None,
+            error: Some(error),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Display, EnumString, ArgEnum)]
 #[strum(serialize_all = "snake_case")]
 pub enum StateQuery {
@@ -126,6 +311,11 @@ pub enum StateQuery {
     FocusedWorkspaceIndex,
     FocusedContainerIndex,
     FocusedWindowIndex,
+    /// One of tile, stack:<size>, nested:<size>, float, maximized, monocle or empty
+    FocusedContainerKind,
+    /// Whether the focused workspace has a monocle container or a maximized window or container
+    FocusedWorkspaceHasMaximizedWindow,
+    Version,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Display, EnumString, ArgEnum)]
@@ -134,6 +324,8 @@ pub enum ApplicationIdentifier {
     Exe,
     Class,
     Title,
+    /// Matches any window whose process command line contains the identifier
+    CommandLine,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Display, EnumString, ArgEnum)]
@@ -150,7 +342,32 @@ pub enum WindowContainerBehaviour {
     Append,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Display, EnumString, ArgEnum)]
+/// Where focus goes when the last window on the focused workspace is closed
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Display, EnumString, ArgEnum)]
+#[strum(serialize_all = "snake_case")]
+pub enum EmptyWorkspaceBehaviour {
+    /// Stay on the empty workspace
+    Stay,
+    /// Go back to the previously focused workspace
+    Previous,
+    /// Go to the nearest workspace on the same monitor which still has windows
+    Nearest,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Display, EnumString, ArgEnum)]
+#[strum(serialize_all = "snake_case")]
+pub enum CursorPlacement {
+    /// Centre the cursor in the focused window
+    Center,
+    /// Place the cursor at the offset from the top-left corner of the focused window
+    TopLeft,
+    /// Move the cursor the shortest distance needed to be inside the focused window
+    NearestEdge,
+    /// Put the cursor back where it last was in the focused window, or centre it
+    LastPosition,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Display, EnumString, ArgEnum)]
 #[strum(serialize_all = "snake_case")]
 pub enum HidingBehaviour {
     Hide,
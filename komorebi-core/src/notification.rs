@@ -0,0 +1,97 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::Layout;
+use crate::SocketMessage;
+
+/// Window properties can no longer be read once a window has been destroyed, so they are
+/// optional here and consumers should always fall back to the `hwnd`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NotificationWindow {
+    pub hwnd: isize,
+    pub title: Option<String>,
+    pub exe: Option<String>,
+    pub class: Option<String>,
+    /// Whether the window belongs to an elevated process that komorebi is unable to move because
+    /// it is not running elevated itself
+    #[serde(default)]
+    pub elevation_mismatch: bool,
+    /// The path to an .ico file of the window's icon
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "content")]
+pub enum NotificationEvent {
+    FocusChange {
+        window: NotificationWindow,
+        monitor_idx: usize,
+        workspace_idx: usize,
+    },
+    WorkspaceFocus {
+        monitor_idx: usize,
+        workspace_idx: usize,
+    },
+    MonitorFocus {
+        monitor_idx: usize,
+    },
+    WindowManaged {
+        window: NotificationWindow,
+        monitor_idx: usize,
+        workspace_idx: usize,
+    },
+    WindowUnmanaged {
+        window: NotificationWindow,
+    },
+    WindowMoveResizeStart {
+        window: NotificationWindow,
+    },
+    WindowMoveResizeEnd {
+        window: NotificationWindow,
+        monitor_idx: usize,
+        workspace_idx: usize,
+    },
+    WindowTitleChange {
+        window: NotificationWindow,
+        monitor_idx: usize,
+        workspace_idx: usize,
+    },
+    /// A window has flashed its taskbar button to request attention
+    WindowUrgent {
+        window: NotificationWindow,
+        monitor_idx: usize,
+        workspace_idx: usize,
+    },
+    LayoutChange {
+        monitor_idx: usize,
+        workspace_idx: usize,
+        layout: Layout,
+    },
+    PauseToggle {
+        paused: bool,
+    },
+    OrphanRemoved {
+        hwnd: isize,
+        monitor_idx: usize,
+        workspace_idx: usize,
+    },
+    /// A workspace has entered or left monocle mode, or gained or lost a maximized window
+    MaximizedStateChange {
+        monitor_idx: usize,
+        workspace_idx: usize,
+        monocle: bool,
+        maximized: bool,
+    },
+    /// A window which was already managed has been shown again, such as after being restored
+    /// from the taskbar
+    WindowShown {
+        window: NotificationWindow,
+        monitor_idx: usize,
+        workspace_idx: usize,
+    },
+    /// The resolution or scaling of a display has changed
+    DisplayChange,
+    /// Any handled command which does not have a more specific notification event
+    Command(SocketMessage),
+}
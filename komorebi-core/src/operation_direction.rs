@@ -8,6 +8,7 @@ use strum::EnumString;
 
 use crate::direction::Direction;
 use crate::Axis;
+use crate::Rect;
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, Display, EnumString, ArgEnum)]
 #[strum(serialize_all = "snake_case")]
@@ -60,4 +61,54 @@ impl OperationDirection {
     ) -> Option<usize> {
         layout.index_in_direction(self.flip(layout_flip), idx, len.get())
     }
+
+    /// The index of the rect in `candidates` which is nearest to `from` in this direction
+    ///
+    /// Candidates must have their centroid beyond the centroid of `from` in this direction and
+    /// overlap it on the perpendicular axis. They are ranked by the gap between the facing edges
+    /// (overlapping edges count as no gap); ties are broken by the closest centroid and then by
+    /// the lowest index, so the same candidate is picked every time
+    #[must_use]
+    pub fn nearest(self, from: &Rect, candidates: &[Rect]) -> Option<usize> {
+        // Centroids are doubled so that they don't have to be rounded
+        let centroid = |rect: &Rect| {
+            (
+                i64::from(rect.left) * 2 + i64::from(rect.right),
+                i64::from(rect.top) * 2 + i64::from(rect.bottom),
+            )
+        };
+
+        let overlaps = |start: i32, length: i32, other_start: i32, other_length: i32| {
+            start < other_start + other_length && other_start < start + length
+        };
+
+        let (from_x, from_y) = centroid(from);
+
+        candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, rect)| {
+                let (x, y) = centroid(rect);
+
+                let (is_ahead, gap) = match self {
+                    Self::Left => (x < from_x, from.left - (rect.left + rect.right)),
+                    Self::Right => (x > from_x, rect.left - (from.left + from.right)),
+                    Self::Up => (y < from_y, from.top - (rect.top + rect.bottom)),
+                    Self::Down => (y > from_y, rect.top - (from.top + from.bottom)),
+                };
+
+                let is_aligned = match self {
+                    Self::Left | Self::Right => {
+                        overlaps(from.top, from.bottom, rect.top, rect.bottom)
+                    }
+                    Self::Up | Self::Down => overlaps(from.left, from.right, rect.left, rect.right),
+                };
+
+                let distance = (x - from_x).pow(2) + (y - from_y).pow(2);
+
+                (is_ahead && is_aligned).then(|| (gap.max(0), distance, idx))
+            })
+            .min()
+            .map(|(_, _, idx)| idx)
+    }
 }
@@ -31,6 +31,71 @@ impl Rect {
         }
     }
 
+    /// Shrink by half of `gap` on each side, so that two adjacent rects which have both been
+    /// shrunk end up exactly `gap` apart instead of twice that distance
+    pub fn add_inner_gap(&mut self, gap: i32) {
+        let before = gap / 2;
+
+        self.left += before;
+        self.top += before;
+        self.right -= gap;
+        self.bottom -= gap;
+    }
+
+    /// The inverse of `add_inner_gap`, used on a work area so that the outermost rects of a
+    /// layout calculated within it still line up with its edges after `add_inner_gap`
+    pub fn remove_inner_gap(&mut self, gap: i32) {
+        let before = gap / 2;
+
+        self.left -= before;
+        self.top -= before;
+        self.right += gap;
+        self.bottom += gap;
+    }
+
+    /// Remove `zone` by moving whichever edge keeps the largest area; a rect cannot have a hole
+    /// cut out of it, so a zone in the middle of this rect takes the whole band that it spans
+    pub fn subtract(&mut self, zone: &Self) {
+        let right = self.left + self.right;
+        let bottom = self.top + self.bottom;
+        let zone_right = zone.left + zone.right;
+        let zone_bottom = zone.top + zone.bottom;
+
+        if zone.left >= right
+            || zone_right <= self.left
+            || zone.top >= bottom
+            || zone_bottom <= self.top
+        {
+            return;
+        }
+
+        let remaining = [
+            i64::from(right - zone_right) * i64::from(self.bottom),
+            i64::from(zone.left - self.left) * i64::from(self.bottom),
+            i64::from(bottom - zone_bottom) * i64::from(self.right),
+            i64::from(zone.top - self.top) * i64::from(self.right),
+        ];
+
+        let side = remaining
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, area)| **area)
+            .map_or(0, |(side, _)| side);
+
+        match side {
+            0 => {
+                self.left = zone_right;
+                self.right = (right - zone_right).max(0);
+            }
+            1 => self.right = (zone.left - self.left).max(0),
+            2 => {
+                self.top = zone_bottom;
+                self.bottom = (bottom - zone_bottom).max(0);
+            }
+            _ => self.bottom = (zone.top - self.top).max(0),
+        }
+    }
+
     #[must_use]
     pub const fn contains_point(&self, point: (i32, i32)) -> bool {
         point.0 >= self.left
@@ -0,0 +1,72 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufReader;
+use std::path::Path;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::Rect;
+
+/// The current version of the saved layout format. This must be incremented whenever the format
+/// changes, along with a migration from the previous version in `SavedLayout::migrate`.
+pub const SAVED_LAYOUT_VERSION: u32 = 1;
+
+/// The resize dimensions of a workspace, as written by the quicksave and save commands
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedLayout {
+    pub version: u32,
+    pub resize_dimensions: Vec<Option<Rect>>,
+}
+
+impl SavedLayout {
+    #[must_use]
+    pub const fn new(resize_dimensions: Vec<Option<Rect>>) -> Self {
+        Self {
+            version: SAVED_LAYOUT_VERSION,
+            resize_dimensions,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open(path)?;
+
+        Ok(serde_json::to_writer_pretty(&file, self)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).map_err(|_| anyhow!("no saved layout found at {}", path.display()))?;
+
+        let value: serde_json::Value = serde_json::from_reader(BufReader::new(file))?;
+        Self::migrate(value).map_err(|error| anyhow!("{}: {}", path.display(), error))
+    }
+
+    fn migrate(value: serde_json::Value) -> Result<Self> {
+        // Files written before the format was versioned are a bare array of resize dimensions
+        if value.is_array() {
+            return Ok(Self::new(serde_json::from_value(value)?));
+        }
+
+        let version = value
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or_else(|| anyhow!("the saved layout does not have a valid version"))?;
+
+        if version > u64::from(SAVED_LAYOUT_VERSION) {
+            return Err(anyhow!(
+                "the saved layout is version {}, but this version of komorebi only supports up to version {}",
+                version,
+                SAVED_LAYOUT_VERSION
+            ));
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+}
@@ -0,0 +1,208 @@
+use clap::ArgEnum;
+use serde::Deserialize;
+use serde::Serialize;
+use strum::Display;
+use strum::EnumString;
+
+use crate::Rect;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Display, EnumString, ArgEnum)]
+#[strum(serialize_all = "snake_case")]
+pub enum SizeConstraint {
+    MinWidth,
+    MinHeight,
+    MaxWidth,
+    MaxHeight,
+    WidthIncrement,
+    HeightIncrement,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SizeConstraints {
+    pub min_width: Option<i32>,
+    pub min_height: Option<i32>,
+    pub max_width: Option<i32>,
+    pub max_height: Option<i32>,
+    pub width_increment: Option<i32>,
+    pub height_increment: Option<i32>,
+}
+
+impl SizeConstraints {
+    pub fn set(&mut self, constraint: SizeConstraint, value: i32) {
+        let value = Option::from(value).filter(|value| *value > 0);
+
+        match constraint {
+            SizeConstraint::MinWidth => self.min_width = value,
+            SizeConstraint::MinHeight => self.min_height = value,
+            SizeConstraint::MaxWidth => self.max_width = value,
+            SizeConstraint::MaxHeight => self.max_height = value,
+            SizeConstraint::WidthIncrement => self.width_increment = value,
+            SizeConstraint::HeightIncrement => self.height_increment = value,
+        }
+    }
+
+    #[must_use]
+    pub fn constrain(&self, rect: &Rect) -> Rect {
+        Rect {
+            left: rect.left,
+            top: rect.top,
+            right: constrain_dimension(
+                rect.right,
+                self.min_width,
+                self.max_width,
+                self.width_increment,
+            ),
+            bottom: constrain_dimension(
+                rect.bottom,
+                self.min_height,
+                self.max_height,
+                self.height_increment,
+            ),
+        }
+    }
+
+    /// Constrain the layout at `idx`, giving any space that it no longer uses to (or taking any
+    /// additional space that it needs from) its nearest neighbours on the horizontal and
+    /// vertical axes.
+    pub fn apply(&self, layouts: &mut [Rect], idx: usize) {
+        let original = match layouts.get(idx) {
+            None => return,
+            Some(original) => *original,
+        };
+
+        let mut constrained = self.constrain(&original);
+
+        let width_delta = constrained.right - original.right;
+        if width_delta != 0 {
+            let after = neighbours_after(layouts, idx, &original, Axis::Horizontal);
+            if after.is_empty() {
+                // There is nothing to the right, so we keep the right edge where it is instead
+                let before = neighbours_before(layouts, idx, &original, Axis::Horizontal);
+                constrained.left -= width_delta;
+                for i in before {
+                    layouts[i].right -= width_delta;
+                }
+            } else {
+                for i in after {
+                    layouts[i].left += width_delta;
+                    layouts[i].right -= width_delta;
+                }
+            }
+        }
+
+        let height_delta = constrained.bottom - original.bottom;
+        if height_delta != 0 {
+            let after = neighbours_after(layouts, idx, &original, Axis::Vertical);
+            if after.is_empty() {
+                // There is nothing below, so we keep the bottom edge where it is instead
+                let before = neighbours_before(layouts, idx, &original, Axis::Vertical);
+                constrained.top -= height_delta;
+                for i in before {
+                    layouts[i].bottom -= height_delta;
+                }
+            } else {
+                for i in after {
+                    layouts[i].top += height_delta;
+                    layouts[i].bottom -= height_delta;
+                }
+            }
+        }
+
+        layouts[idx] = constrained;
+    }
+}
+
+fn constrain_dimension(
+    value: i32,
+    min: Option<i32>,
+    max: Option<i32>,
+    increment: Option<i32>,
+) -> i32 {
+    let mut value = value;
+
+    if let Some(increment) = increment {
+        value -= value % increment;
+    }
+
+    if let Some(max) = max {
+        value = value.min(max);
+    }
+
+    if let Some(min) = min {
+        value = value.max(min);
+    }
+
+    value
+}
+
+#[derive(Clone, Copy)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+const fn overlaps(a: &Rect, b: &Rect, axis: Axis) -> bool {
+    match axis {
+        // Neighbours on the horizontal axis need to share part of the vertical span and vice versa
+        Axis::Horizontal => a.top < b.top + b.bottom && b.top < a.top + a.bottom,
+        Axis::Vertical => a.left < b.left + b.right && b.left < a.left + a.right,
+    }
+}
+
+fn neighbours_after(layouts: &[Rect], idx: usize, rect: &Rect, axis: Axis) -> Vec<usize> {
+    let edge = match axis {
+        Axis::Horizontal => rect.left + rect.right,
+        Axis::Vertical => rect.top + rect.bottom,
+    };
+
+    let gaps = layouts
+        .iter()
+        .enumerate()
+        .filter(|(i, other)| *i != idx && overlaps(rect, other, axis))
+        .filter_map(|(i, other)| {
+            let start = match axis {
+                Axis::Horizontal => other.left,
+                Axis::Vertical => other.top,
+            };
+
+            (start >= edge).then(|| (i, start - edge))
+        })
+        .collect::<Vec<_>>();
+
+    nearest(&gaps)
+}
+
+fn neighbours_before(layouts: &[Rect], idx: usize, rect: &Rect, axis: Axis) -> Vec<usize> {
+    let edge = match axis {
+        Axis::Horizontal => rect.left,
+        Axis::Vertical => rect.top,
+    };
+
+    let gaps = layouts
+        .iter()
+        .enumerate()
+        .filter(|(i, other)| *i != idx && overlaps(rect, other, axis))
+        .filter_map(|(i, other)| {
+            let end = match axis {
+                Axis::Horizontal => other.left + other.right,
+                Axis::Vertical => other.top + other.bottom,
+            };
+
+            (end <= edge).then(|| (i, edge - end))
+        })
+        .collect::<Vec<_>>();
+
+    nearest(&gaps)
+}
+
+fn nearest(gaps: &[(usize, i32)]) -> Vec<usize> {
+    gaps.iter()
+        .map(|(_, gap)| *gap)
+        .min()
+        .map_or_else(Vec::new, |min| {
+            gaps.iter()
+                .filter(|(_, gap)| *gap == min)
+                .map(|(i, _)| *i)
+                .collect()
+        })
+}
@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufReader;
+use std::path::Path;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::Axis;
+use crate::Layout;
+use crate::Rect;
+
+/// The current version of the workspace snapshot format
+pub const WORKSPACE_SNAPSHOT_VERSION: u32 = 1;
+
+/// An application which was open in a container when a workspace snapshot was taken
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApplicationPlaceholder {
+    pub exe: String,
+    pub class: String,
+    pub title: Option<String>,
+    /// Full path to the executable, used to launch the application if it is not already open
+    pub path: Option<String>,
+}
+
+impl ApplicationPlaceholder {
+    #[must_use]
+    pub fn matches(&self, exe: &str, class: &str) -> bool {
+        self.exe == exe && self.class == class
+    }
+
+    /// Whether this was saved from a window with the same title, which is used to tell apart
+    /// several windows of the same application
+    #[must_use]
+    pub fn matches_title(&self, title: &str) -> bool {
+        self.title.as_deref() == Some(title)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnapshotContainer {
+    pub applications: Vec<ApplicationPlaceholder>,
+}
+
+/// The layout of a single workspace and the applications in each of its containers, as written by
+/// the save-workspace-snapshot command, which can be used as a template for a project
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorkspaceSnapshot {
+    pub version: u32,
+    pub layout: Layout,
+    pub layout_flip: Option<Axis>,
+    pub resize_dimensions: Vec<Option<Rect>>,
+    pub containers: Vec<SnapshotContainer>,
+}
+
+impl WorkspaceSnapshot {
+    #[must_use]
+    pub const fn new(
+        layout: Layout,
+        layout_flip: Option<Axis>,
+        resize_dimensions: Vec<Option<Rect>>,
+        containers: Vec<SnapshotContainer>,
+    ) -> Self {
+        Self {
+            version: WORKSPACE_SNAPSHOT_VERSION,
+            layout,
+            layout_flip,
+            resize_dimensions,
+            containers,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open(path)?;
+
+        Ok(serde_json::to_writer_pretty(&file, self)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|_| anyhow!("no workspace snapshot found at {}", path.display()))?;
+
+        let snapshot: Self = serde_json::from_reader(BufReader::new(file))
+            .map_err(|error| anyhow!("{}: {}", path.display(), error))?;
+
+        if snapshot.version > WORKSPACE_SNAPSHOT_VERSION {
+            return Err(anyhow!(
+                "the workspace snapshot is version {}, but this version of komorebi only supports up to version {}",
+                snapshot.version,
+                WORKSPACE_SNAPSHOT_VERSION
+            ));
+        }
+
+        Ok(snapshot)
+    }
+}
@@ -0,0 +1,19 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+// Every message is tagged with the generation that was current when it was received, and
+// cancelling moves on to the next generation, which invalidates everything received before it
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+pub fn token() -> usize {
+    GENERATION.load(Ordering::SeqCst)
+}
+
+pub fn cancel() {
+    tracing::info!("cancelling pending operations");
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+pub fn is_cancelled(token: usize) -> bool {
+    GENERATION.load(Ordering::SeqCst) != token
+}
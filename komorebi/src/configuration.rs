@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+
+use komorebi_core::Axis;
+use komorebi_core::DefaultLayout;
+use komorebi_core::EmptyWorkspaceBehaviour;
+use komorebi_core::FocusFollowsMouseImplementation;
+use komorebi_core::Layout;
+use komorebi_core::Rect;
+use komorebi_core::SizeConstraints;
+use komorebi_core::WindowContainerBehaviour;
+
+use crate::monitor;
+use crate::monitor::Monitor;
+use crate::window_manager::WindowManager;
+use crate::workspace::Workspace;
+use crate::BORDER_OVERFLOW_IDENTIFIERS;
+use crate::CLASS_BLOCKLIST;
+use crate::FLOAT_IDENTIFIERS;
+use crate::LAYERED_EXE_WHITELIST;
+use crate::MANAGE_IDENTIFIERS;
+use crate::SIZE_CONSTRAINTS;
+use crate::TRAY_AND_MULTI_WINDOW_IDENTIFIERS;
+use crate::WORKSPACE_RULES;
+
+lazy_static! {
+    static ref DEFAULT_CONFIGURATION: Arc<Mutex<Option<Configuration>>> =
+        Arc::new(Mutex::new(None));
+}
+
+/// The settings which can be changed by a configuration script, without any of the windows
+/// that happen to be managed at the time
+#[derive(Debug, Clone, Serialize)]
+pub struct Configuration {
+    pub resize_delta: i32,
+    pub new_window_behaviour: WindowContainerBehaviour,
+    pub empty_workspace_behaviour: EmptyWorkspaceBehaviour,
+    pub workspace_sync: bool,
+    pub work_area_offset: Option<Rect>,
+    pub focus_follows_mouse: Option<FocusFollowsMouseImplementation>,
+    pub mouse_follows_focus: bool,
+    pub stack_indicator: bool,
+    pub focus_mode_width: u8,
+    pub float_identifiers: Vec<String>,
+    pub manage_identifiers: Vec<String>,
+    pub class_blocklist: Vec<String>,
+    pub layered_exe_whitelist: Vec<String>,
+    pub tray_and_multi_window_identifiers: Vec<String>,
+    pub border_overflow_identifiers: Vec<String>,
+    pub size_constraints: HashMap<String, SizeConstraints>,
+    pub workspace_rules: HashMap<String, (usize, usize)>,
+    /// Keyed by index so that the settings of each monitor are compared individually
+    pub monitors: BTreeMap<usize, MonitorConfiguration>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorConfiguration {
+    pub workspace_sync: bool,
+    pub exclusion_zones: Vec<Rect>,
+    pub work_area_override: Option<Rect>,
+    /// Keyed by index so that the settings of each workspace are compared individually
+    pub workspaces: BTreeMap<usize, WorkspaceConfiguration>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceConfiguration {
+    pub name: Option<String>,
+    pub layout: Layout,
+    pub layout_rotation: Vec<DefaultLayout>,
+    pub layout_cycle_interval: Option<u64>,
+    pub layout_flip: Option<Axis>,
+    pub workspace_padding: Option<i32>,
+    pub container_padding: Option<i32>,
+    pub container_padding_scale: Vec<i32>,
+    pub multi_stack: Option<usize>,
+    pub inner_gap: Option<i32>,
+    pub outer_gap: Option<i32>,
+    pub logical_padding: bool,
+    pub tile: bool,
+    pub focus_follows_mouse: Option<bool>,
+    pub resize_delta: Option<i32>,
+    pub wallpaper: Option<PathBuf>,
+}
+
+impl From<&Workspace> for WorkspaceConfiguration {
+    fn from(workspace: &Workspace) -> Self {
+        Self {
+            name: workspace.name().clone(),
+            layout: workspace.layout().clone(),
+            layout_rotation: workspace.layout_rotation().clone(),
+            layout_cycle_interval: workspace.layout_cycle_interval(),
+            layout_flip: workspace.layout_flip(),
+            workspace_padding: workspace.workspace_padding(),
+            container_padding: workspace.container_padding(),
+            container_padding_scale: workspace.container_padding_scale().clone(),
+            multi_stack: workspace.multi_stack(),
+            inner_gap: workspace.inner_gap(),
+            outer_gap: workspace.outer_gap(),
+            logical_padding: workspace.logical_padding(),
+            tile: *workspace.tile(),
+            focus_follows_mouse: workspace.focus_follows_mouse(),
+            resize_delta: workspace.resize_delta(),
+            wallpaper: workspace.wallpaper().clone(),
+        }
+    }
+}
+
+impl From<&Monitor> for MonitorConfiguration {
+    fn from(monitor: &Monitor) -> Self {
+        Self {
+            workspace_sync: monitor.workspace_sync(),
+            exclusion_zones: monitor.exclusion_zones().clone(),
+            work_area_override: monitor.work_area_override(),
+            workspaces: monitor
+                .workspaces()
+                .iter()
+                .map(WorkspaceConfiguration::from)
+                .enumerate()
+                .collect(),
+        }
+    }
+}
+
+impl From<&WindowManager> for Configuration {
+    fn from(wm: &WindowManager) -> Self {
+        Self {
+            resize_delta: wm.resize_delta,
+            new_window_behaviour: wm.window_container_behaviour,
+            empty_workspace_behaviour: wm.empty_workspace_behaviour,
+            workspace_sync: wm.workspace_sync,
+            work_area_offset: wm.work_area_offset,
+            focus_follows_mouse: wm.focus_follows_mouse.clone(),
+            mouse_follows_focus: wm.mouse_follows_focus,
+            stack_indicator: wm.stack_indicator,
+            focus_mode_width: wm.focus_mode_width,
+            float_identifiers: FLOAT_IDENTIFIERS.lock().clone(),
+            manage_identifiers: MANAGE_IDENTIFIERS.lock().clone(),
+            class_blocklist: CLASS_BLOCKLIST.lock().clone(),
+            layered_exe_whitelist: LAYERED_EXE_WHITELIST.lock().clone(),
+            tray_and_multi_window_identifiers: TRAY_AND_MULTI_WINDOW_IDENTIFIERS.lock().clone(),
+            border_overflow_identifiers: BORDER_OVERFLOW_IDENTIFIERS.lock().clone(),
+            size_constraints: SIZE_CONSTRAINTS.lock().clone(),
+            workspace_rules: WORKSPACE_RULES.lock().clone(),
+            monitors: wm
+                .monitors()
+                .iter()
+                .map(MonitorConfiguration::from)
+                .enumerate()
+                .collect(),
+        }
+    }
+}
+
+/// Remember the built-in defaults, which must be called before any configuration is loaded
+pub fn capture_defaults(wm: &WindowManager) {
+    *DEFAULT_CONFIGURATION.lock() = Option::from(Configuration::from(wm));
+}
+
+/// Every setting which differs from the built-in defaults, with the monitors and workspaces
+/// that exist now compared against freshly created ones
+pub fn diff(wm: &WindowManager) -> Result<Value> {
+    let current = Configuration::from(wm);
+    let mut default = DEFAULT_CONFIGURATION
+        .lock()
+        .clone()
+        .ok_or_else(|| anyhow!("the default configuration has not been captured"))?;
+
+    let default_monitor = MonitorConfiguration::from(&monitor::new(
+        0,
+        Rect::default(),
+        Rect::default(),
+        String::new(),
+    ));
+
+    let default_workspace = WorkspaceConfiguration::from(&Workspace::default());
+
+    default.monitors = current
+        .monitors
+        .iter()
+        .map(|(idx, monitor)| {
+            let mut configuration = default_monitor.clone();
+            configuration.workspaces = monitor
+                .workspaces
+                .keys()
+                .map(|idx| (*idx, default_workspace.clone()))
+                .collect();
+
+            (*idx, configuration)
+        })
+        .collect();
+
+    let changes = diff_values(
+        &serde_json::to_value(default)?,
+        &serde_json::to_value(current)?,
+    );
+
+    Ok(changes.unwrap_or_else(|| Value::Object(Map::new())))
+}
+
+/// Objects are compared key by key, lists of plain values report what was added and removed,
+/// and anything else reports both the default and the current value
+fn diff_values(default: &Value, current: &Value) -> Option<Value> {
+    if default == current {
+        return None;
+    }
+
+    match (default, current) {
+        (Value::Object(default), Value::Object(current)) => {
+            let mut changes = Map::new();
+            for (key, value) in current {
+                if let Some(change) = diff_values(default.get(key).unwrap_or(&Value::Null), value) {
+                    changes.insert(key.clone(), change);
+                }
+            }
+
+            Option::from(Value::Object(changes))
+        }
+        (Value::Array(default), Value::Array(current))
+            if default
+                .iter()
+                .chain(current.iter())
+                .all(|value| !value.is_object() && !value.is_array()) =>
+        {
+            let added = current
+                .iter()
+                .filter(|value| !default.contains(value))
+                .collect::<Vec<_>>();
+
+            let removed = default
+                .iter()
+                .filter(|value| !current.contains(value))
+                .collect::<Vec<_>>();
+
+            Option::from(json!({ "added": added, "removed": removed }))
+        }
+        _ => Option::from(json!({ "default": default, "current": current })),
+    }
+}
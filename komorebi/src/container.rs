@@ -1,18 +1,31 @@
 use std::collections::VecDeque;
 
+use getset::CopyGetters;
 use getset::Getters;
+use getset::Setters;
 use nanoid::nanoid;
 use serde::Serialize;
 
+use komorebi_core::arrangement::columns;
+use komorebi_core::arrangement::rows;
+use komorebi_core::Axis;
+use komorebi_core::OperationDirection;
+use komorebi_core::Rect;
+
 use crate::ring::Ring;
 use crate::window::Window;
 
-#[derive(Debug, Clone, Serialize, Getters)]
+#[derive(Debug, Clone, Serialize, Getters, CopyGetters, Setters)]
 pub struct Container {
-    #[serde(skip_serializing)]
     #[getset(get = "pub")]
     id: String,
     windows: Ring<Window>,
+    /// When set, the windows in this container are tiled along this axis within the container's
+    /// tile instead of being stacked on top of each other. Nesting is only ever one level deep:
+    /// containers hold windows rather than other containers, so there is no tree of splits like
+    /// there is in i3, and nesting an already nested container only changes its axis
+    #[getset(get_copy = "pub", set = "pub")]
+    nested_axis: Option<Axis>,
 }
 
 impl_ring_elements!(Container, Window);
@@ -22,6 +35,7 @@ impl Default for Container {
         Self {
             id: nanoid!(),
             windows: Ring::default(),
+            nested_axis: None,
         }
     }
 }
@@ -35,8 +49,9 @@ impl PartialEq for Container {
 impl Container {
     pub fn load_focused_window(&mut self) {
         let focused_idx = self.focused_window_idx();
+        let nested = self.nested_axis.is_some();
         for (i, window) in self.windows_mut().iter_mut().enumerate() {
-            if i == focused_idx {
+            if nested || i == focused_idx {
                 window.restore();
             } else {
                 window.hide();
@@ -44,6 +59,34 @@ impl Container {
         }
     }
 
+    /// The tiles of each window in a nested container which has been given `area`
+    pub fn nested_layouts(&self, area: &Rect) -> Vec<Rect> {
+        let len = self.windows().len().max(1);
+        match self.nested_axis {
+            Some(Axis::Vertical) => rows(area, len),
+            _ => columns(area, len),
+        }
+    }
+
+    /// The index of the window next to the focused window in `direction` when this container is
+    /// nested and its windows are tiled along the axis of `direction`
+    pub fn nested_idx_for_direction(&self, direction: OperationDirection) -> Option<usize> {
+        let idx = self.focused_window_idx();
+        let next_idx = match (self.nested_axis?, direction) {
+            (Axis::Horizontal, OperationDirection::Left)
+            | (Axis::Vertical, OperationDirection::Up) => idx.checked_sub(1)?,
+            (Axis::Horizontal, OperationDirection::Right)
+            | (Axis::Vertical, OperationDirection::Down) => idx + 1,
+            _ => return None,
+        };
+
+        if next_idx < self.windows().len() {
+            Option::from(next_idx)
+        } else {
+            None
+        }
+    }
+
     pub fn hwnd_from_exe(&self, exe: &str) -> Option<isize> {
         for window in self.windows() {
             if let Ok(window_exe) = window.exe() {
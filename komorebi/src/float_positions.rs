@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+use color_eyre::Result;
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use komorebi_core::Rect;
+
+use crate::temp_file;
+use crate::window::Window;
+use crate::window_manager_event::WindowManagerEvent;
+use crate::windows_api::WindowsApi;
+
+lazy_static! {
+    /// The last position and size of a floating window, keyed by its exe and class
+    static ref FLOATING_WINDOW_CHANNEL: Arc<Mutex<(Sender<WindowManagerEvent>, Receiver<WindowManagerEvent>)>> =
+        Arc::new(Mutex::new(crossbeam_channel::unbounded()));
+    static ref FLOAT_POSITIONS: Arc<Mutex<HashMap<String, Rect>>> =
+        Arc::new(Mutex::new(load().unwrap_or_default()));
+}
+
+// Stored next to the quicksave so that positions survive komorebi being restarted
+fn path() -> PathBuf {
+    temp_file("komorebi.float_positions.json")
+}
+
+fn load() -> Result<HashMap<String, Rect>> {
+    let file = File::open(path())?;
+    Ok(serde_json::from_reader(BufReader::new(file))?)
+}
+
+fn save(positions: &HashMap<String, Rect>) -> Result<()> {
+    let file = OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open(path())?;
+
+    Ok(serde_json::to_writer_pretty(&file, positions)?)
+}
+
+fn key(window: Window) -> Result<String> {
+    Ok(format!("{}|{}", window.exe()?, window.class()?))
+}
+
+/// Remember where a floating window currently is so that the next matching window can be
+/// floated in the same place
+pub fn remember(window: Window) -> Result<()> {
+    let key = key(window)?;
+    let rect = WindowsApi::window_rect(window.hwnd())?;
+
+    let mut positions = FLOAT_POSITIONS.lock();
+    if positions.get(&key) != Option::from(&rect) {
+        positions.insert(key, rect);
+        save(&positions)?;
+    }
+
+    Ok(())
+}
+
+/// Move a floating window to the last remembered position of a window with the same exe and
+/// class, returning false if there is nothing to restore
+pub fn restore(window: Window) -> Result<bool> {
+    let rect = match FLOAT_POSITIONS.lock().get(&key(window)?) {
+        None => return Ok(false),
+        Some(rect) => *rect,
+    };
+
+    WindowsApi::position_window(window.hwnd(), &rect, false)?;
+
+    Ok(true)
+}
+
+/// Hand an event for a window which is not managed to the floating window listener, which
+/// restores or remembers its position if it is floated by a rule
+pub fn send(event: WindowManagerEvent) {
+    if let Err(error) = FLOATING_WINDOW_CHANNEL.lock().0.send(event) {
+        tracing::error!("{}", error);
+    }
+}
+
+// Checking float rules and reading or writing the positions file is too slow to do in the WinEvent
+// hook, so it is done on this thread instead
+#[tracing::instrument]
+pub fn listen_for_floating_windows() {
+    let receiver = FLOATING_WINDOW_CHANNEL.lock().1.clone();
+
+    thread::spawn(move || {
+        for event in receiver.iter() {
+            let result = match event {
+                WindowManagerEvent::Show(_, window) if window.is_floated_by_rule() => {
+                    restore(window).map(|_| ())
+                }
+                WindowManagerEvent::MoveResizeEnd(_, window) if window.is_floated_by_rule() => {
+                    remember(window)
+                }
+                _ => Ok(()),
+            };
+
+            if let Err(error) = result {
+                tracing::warn!("could not update floating window position: {}", error);
+            }
+        }
+    });
+}
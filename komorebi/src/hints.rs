@@ -0,0 +1,146 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use windows::Win32::Foundation::HWND;
+
+use komorebi_core::Rect;
+
+use crate::windows_api::WindowsApi;
+use crate::windows_callbacks;
+use crate::winevent_listener::MessageLoop;
+
+/// How long hints stay on screen, and how long focus-hint will accept their labels
+pub const HINT_TIMEOUT_SECONDS: u64 = 5;
+
+// Labels are made from the home row keys so that they can be typed without looking
+const HINT_ALPHABET: &str = "asdfghjkl";
+const HINT_SIZE: i32 = 48;
+const HINT_CLASS: &str = "komorebi.hint";
+
+// COLORREF values are 0x00BBGGRR
+pub const HINT_BACKGROUND_COLOUR: u32 = 0x0000_D7FF;
+pub const HINT_TEXT_COLOUR: u32 = 0x0000_0000;
+
+/// The label of each hint and the area of the container that it is shown over
+type Hints = Vec<(String, Rect)>;
+
+lazy_static! {
+    static ref HINTS_CHANNEL: Arc<Mutex<(Sender<Hints>, Receiver<Hints>)>> =
+        Arc::new(Mutex::new(crossbeam_channel::unbounded()));
+    /// The label painted by each hint window, keyed by its hwnd
+    pub static ref HINT_LABELS: Arc<Mutex<HashMap<isize, String>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+/// Generate `count` unique labels, using two characters per label when there are more containers
+/// than characters in the alphabet
+pub fn labels(count: usize) -> Vec<String> {
+    let alphabet: Vec<char> = HINT_ALPHABET.chars().collect();
+
+    if count <= alphabet.len() {
+        return alphabet
+            .iter()
+            .take(count)
+            .map(ToString::to_string)
+            .collect();
+    }
+
+    alphabet
+        .iter()
+        .flat_map(|first| {
+            alphabet
+                .iter()
+                .map(move |second| format!("{}{}", first, second))
+        })
+        .take(count)
+        .collect()
+}
+
+/// Show a hint with each label centred on its rect, replacing any hints already on screen
+pub fn show(hints: Hints) {
+    if let Err(error) = HINTS_CHANNEL.lock().0.send(hints) {
+        tracing::error!("could not send hints: {}", error);
+    }
+}
+
+pub fn hide() {
+    show(vec![]);
+}
+
+#[tracing::instrument]
+pub fn listen_for_hints() {
+    thread::spawn(|| {
+        // Windows can only be destroyed by the thread that created them, so the hint windows are
+        // all created, painted and destroyed by this thread
+        if let Err(error) =
+            WindowsApi::register_window_class(HINT_CLASS, Some(windows_callbacks::hint_window_proc))
+        {
+            tracing::error!("could not register hint window class: {}", error);
+            return;
+        }
+
+        let receiver = HINTS_CHANNEL.lock().1.clone();
+        let windows: RefCell<Vec<HWND>> = RefCell::new(vec![]);
+        let shown: Cell<Option<Instant>> = Cell::new(None);
+
+        MessageLoop::start(10, |_msg| {
+            let timed_out = shown.get().map_or(false, |shown| {
+                shown.elapsed() > Duration::from_secs(HINT_TIMEOUT_SECONDS)
+            });
+
+            if timed_out {
+                destroy_hint_windows(&mut windows.borrow_mut());
+                shown.set(None);
+            }
+
+            while let Ok(hints) = receiver.try_recv() {
+                destroy_hint_windows(&mut windows.borrow_mut());
+                shown.set(None);
+
+                if hints.is_empty() {
+                    continue;
+                }
+
+                for (label, rect) in hints {
+                    let hint_rect = Rect {
+                        left: rect.left + (rect.right - HINT_SIZE) / 2,
+                        top: rect.top + (rect.bottom - HINT_SIZE) / 2,
+                        right: HINT_SIZE,
+                        bottom: HINT_SIZE,
+                    };
+
+                    match WindowsApi::create_overlay_window(HINT_CLASS, &hint_rect) {
+                        Ok(hwnd) => {
+                            HINT_LABELS.lock().insert(hwnd.0, label);
+                            WindowsApi::show_window_without_activating(hwnd);
+                            windows.borrow_mut().push(hwnd);
+                        }
+                        Err(error) => tracing::error!("could not create hint window: {}", error),
+                    }
+                }
+
+                shown.set(Option::from(Instant::now()));
+            }
+
+            true
+        });
+    });
+}
+
+fn destroy_hint_windows(windows: &mut Vec<HWND>) {
+    for hwnd in windows.drain(..) {
+        HINT_LABELS.lock().remove(&hwnd.0);
+        if let Err(error) = WindowsApi::destroy_window(hwnd) {
+            tracing::error!("could not destroy hint window: {}", error);
+        }
+    }
+}
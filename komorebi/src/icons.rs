@@ -0,0 +1,161 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+use color_eyre::Result;
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use crate::temp_file;
+use crate::window::Window;
+use crate::windows_api::WindowsApi;
+
+lazy_static! {
+    static ref ICON_CHANNEL: Arc<Mutex<(Sender<Window>, Receiver<Window>)>> =
+        Arc::new(Mutex::new(crossbeam_channel::unbounded()));
+    /// Where the icon of each exe has been written to, or None if it could not be extracted
+    static ref EXE_ICON_PATHS: Arc<Mutex<HashMap<String, Option<String>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    /// The icon of each managed window, keyed by hwnd
+    static ref ICON_PATHS: Arc<Mutex<HashMap<isize, String>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+const ICON_SIZE: i32 = 32;
+
+fn directory() -> PathBuf {
+    temp_file("komorebi.icons")
+}
+
+/// The path to an .ico file of the window's icon, if one has been extracted since the window
+/// became managed, so that bars and pickers don't have to do this themselves
+pub fn path(window: Window) -> Option<String> {
+    ICON_PATHS.lock().get(&window.hwnd).cloned()
+}
+
+/// Extract the icon of a window which has just become managed on another thread, since this
+/// can mean reading the icon out of the exe
+pub fn extract(window: Window) {
+    if ICON_PATHS.lock().contains_key(&window.hwnd) {
+        return;
+    }
+
+    if let Err(error) = ICON_CHANNEL.lock().0.send(window) {
+        tracing::error!("{}", error);
+    }
+}
+
+/// Forget the icon of a window which is no longer managed
+pub fn forget(hwnd: isize) {
+    ICON_PATHS.lock().remove(&hwnd);
+}
+
+#[tracing::instrument]
+pub fn listen_for_icons() {
+    let receiver = ICON_CHANNEL.lock().1.clone();
+
+    thread::spawn(move || {
+        for window in receiver.iter() {
+            let exe_path = match window.path() {
+                Ok(exe_path) => exe_path,
+                Err(_) => continue,
+            };
+
+            // Each icon is only extracted once for every exe
+            let cached = EXE_ICON_PATHS.lock().get(&exe_path).cloned();
+            let path = if let Some(path) = cached {
+                path
+            } else {
+                let path = match write(window, &exe_path) {
+                    Ok(path) => Option::from(path),
+                    Err(error) => {
+                        tracing::debug!("could not extract the icon of {}: {}", exe_path, error);
+                        None
+                    }
+                };
+
+                EXE_ICON_PATHS.lock().insert(exe_path, path.clone());
+                path
+            };
+
+            if let Some(path) = path {
+                ICON_PATHS.lock().insert(window.hwnd, path);
+            }
+        }
+    });
+}
+
+fn write(window: Window, exe_path: &str) -> Result<String> {
+    let (width, height, pixels) = if let Some(icon) = WindowsApi::window_icon(window.hwnd()) {
+        WindowsApi::icon_pixels(icon)?
+    } else {
+        let icon = WindowsApi::exe_icon(exe_path, ICON_SIZE)?;
+        let pixels = WindowsApi::icon_pixels(icon);
+        WindowsApi::destroy_icon(icon)?;
+        pixels?
+    };
+
+    let mut hasher = DefaultHasher::new();
+    exe_path.hash(&mut hasher);
+
+    let mut path = directory();
+    std::fs::create_dir_all(&path)?;
+    path.push(format!("{:x}.ico", hasher.finish()));
+
+    std::fs::write(&path, ico(width, height, &pixels)?)?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// An .ico file containing a single 32-bit image, where the pixels are stored from the bottom up
+/// and are followed by an empty mask since transparency comes from the alpha channel
+fn ico(width: i32, height: i32, pixels: &[u8]) -> Result<Vec<u8>> {
+    let mask_len = usize::try_from((width + 31) / 32 * 4 * height)?;
+    let image_len = u32::try_from(40 + pixels.len() + mask_len)?;
+
+    let mut ico = vec![];
+
+    // ICONDIR: reserved, type (1 for icons) and the number of images
+    ico.extend_from_slice(&0_u16.to_le_bytes());
+    ico.extend_from_slice(&1_u16.to_le_bytes());
+    ico.extend_from_slice(&1_u16.to_le_bytes());
+
+    // ICONDIRENTRY, where a dimension of 0 means 256
+    ico.push(u8::try_from(width % 256)?);
+    ico.push(u8::try_from(height % 256)?);
+    ico.push(0);
+    ico.push(0);
+    ico.extend_from_slice(&1_u16.to_le_bytes());
+    ico.extend_from_slice(&32_u16.to_le_bytes());
+    ico.extend_from_slice(&image_len.to_le_bytes());
+    ico.extend_from_slice(&22_u32.to_le_bytes());
+
+    // BITMAPINFOHEADER, where the height covers both the colour image and the mask
+    ico.extend_from_slice(&40_u32.to_le_bytes());
+    ico.extend_from_slice(&width.to_le_bytes());
+    ico.extend_from_slice(&(height * 2).to_le_bytes());
+    ico.extend_from_slice(&1_u16.to_le_bytes());
+    ico.extend_from_slice(&32_u16.to_le_bytes());
+    ico.extend_from_slice(&0_u32.to_le_bytes());
+    ico.extend_from_slice(&(image_len - 40).to_le_bytes());
+    ico.extend_from_slice(&[0; 16]);
+
+    // Icons without an alpha channel would otherwise be entirely transparent
+    if pixels.chunks_exact(4).all(|pixel| pixel[3] == 0) {
+        for pixel in pixels.chunks_exact(4) {
+            ico.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 0xFF]);
+        }
+    } else {
+        ico.extend_from_slice(pixels);
+    }
+
+    ico.resize(ico.len() + mask_len, 0);
+
+    Ok(ico)
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use crate::session;
+use crate::window_manager::WindowManager;
+
+const POLL_INTERVAL_SECONDS: u64 = 1;
+
+#[tracing::instrument]
+pub fn listen_for_layout_cycles(wm: Arc<Mutex<WindowManager>>) {
+    thread::spawn(move || {
+        tracing::info!("watching for scheduled layout cycles");
+
+        loop {
+            thread::sleep(Duration::from_secs(POLL_INTERVAL_SECONDS));
+
+            let mut wm = wm.lock();
+            if wm.is_paused || session::is_suspended() {
+                continue;
+            }
+
+            if let Err(error) = wm.cycle_scheduled_layouts() {
+                tracing::error!("{}", error);
+            }
+        }
+    });
+}
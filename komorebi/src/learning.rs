@@ -0,0 +1,93 @@
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::window::Window;
+
+lazy_static! {
+    static ref LEARNING_UNTIL: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+    static ref RULE_SUGGESTIONS: Arc<Mutex<Vec<RuleSuggestion>>> = Arc::new(Mutex::new(vec![]));
+}
+
+/// Why a rule is being suggested for an application
+#[derive(Debug, Clone, Copy, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionReason {
+    /// komorebi refused to manage the window when it was shown
+    Refused,
+    /// The window was managed but was floated by hand
+    FloatedManually,
+    /// The window was not managed but was managed by hand
+    ManagedManually,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleSuggestion {
+    pub exe: String,
+    pub class: String,
+    pub title: String,
+    pub reason: SuggestionReason,
+    /// The komorebic command which would make this the default behaviour for the application
+    pub rule: String,
+}
+
+/// Record rule suggestions for the next `duration`, discarding any from a previous session
+pub fn start(duration: Duration) {
+    *LEARNING_UNTIL.lock() = Option::from(Instant::now() + duration);
+    RULE_SUGGESTIONS.lock().clear();
+}
+
+pub fn is_learning() -> bool {
+    LEARNING_UNTIL
+        .lock()
+        .map_or(false, |until| Instant::now() < until)
+}
+
+pub fn suggestions() -> Vec<RuleSuggestion> {
+    RULE_SUGGESTIONS.lock().clone()
+}
+
+/// Suggest a rule for the application of `window` if learning mode is on, ignoring windows
+/// without a title since they are almost always hidden helper windows
+pub fn record(window: Window, reason: SuggestionReason) {
+    // Windows which are floated by a rule are not managed on purpose
+    if !is_learning() || window.is_floated_by_rule() {
+        return;
+    }
+
+    let (exe, class, title) = match (window.exe(), window.class(), window.title()) {
+        (Ok(exe), Ok(class), Ok(title)) if !title.is_empty() => (exe, class, title),
+        _ => return,
+    };
+
+    let mut suggestions = RULE_SUGGESTIONS.lock();
+    if suggestions
+        .iter()
+        .any(|s| s.exe == exe && s.class == class && s.reason == reason)
+    {
+        return;
+    }
+
+    let rule = match reason {
+        SuggestionReason::Refused | SuggestionReason::ManagedManually => {
+            format!("komorebic.exe manage-rule class \"{}\"", class)
+        }
+        SuggestionReason::FloatedManually => {
+            format!("komorebic.exe float-rule class \"{}\"", class)
+        }
+    };
+
+    tracing::info!("suggesting rule for {} ({}): {}", exe, class, rule);
+
+    suggestions.push(RuleSuggestion {
+        exe,
+        class,
+        title,
+        reason,
+        rule,
+    });
+}
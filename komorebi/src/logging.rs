@@ -0,0 +1,157 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use tracing_subscriber::reload::Handle;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::Registry;
+
+/// Log files are rotated once they grow beyond this size
+const MAX_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+/// The number of rotated log files (komorebi.log.1, komorebi.log.2, ...) that are kept
+const MAX_ROTATED_LOG_FILES: usize = 3;
+
+/// Subsystem names that can be used in place of module paths in log level directives
+const SUBSYSTEMS: [(&str, &[&str]); 4] = [
+    (
+        "events",
+        &[
+            "komorebi::process_event",
+            "komorebi::winevent",
+            "komorebi::winevent_listener",
+            "komorebi::windows_callbacks",
+        ],
+    ),
+    (
+        "layout",
+        &[
+            "komorebi::workspace",
+            "komorebi::monitor",
+            "komorebi::container",
+            "komorebi_core::arrangement",
+        ],
+    ),
+    ("socket", &["komorebi::process_command"]),
+    ("ffm", &["komorebi::process_movement"]),
+];
+
+lazy_static! {
+    static ref LOG_FILTER: Mutex<Option<Handle<EnvFilter, Registry>>> = Mutex::new(None);
+}
+
+pub fn set_filter_handle(handle: Handle<EnvFilter, Registry>) {
+    *LOG_FILTER.lock() = Option::from(handle);
+}
+
+/// Replace the active log filter with `directives`, which use the same syntax as `RUST_LOG`
+/// (ie. `info,events=debug,ffm=trace`), with the addition of the subsystem names in `SUBSYSTEMS`
+pub fn set_log_level(directives: &str) -> Result<()> {
+    let filter = EnvFilter::try_new(expand_subsystems(directives))?;
+
+    LOG_FILTER
+        .lock()
+        .as_ref()
+        .ok_or_else(|| anyhow!("logging has not been initialised"))?
+        .reload(filter)?;
+
+    Ok(())
+}
+
+fn expand_subsystems(directives: &str) -> String {
+    directives
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .flat_map(|directive| {
+            let (target, level) = directive
+                .split_once('=')
+                .map_or((directive, None), |(target, level)| (target, Some(level)));
+
+            SUBSYSTEMS
+                .iter()
+                .find(|(subsystem, _)| subsystem.eq_ignore_ascii_case(target))
+                .map_or_else(
+                    || vec![directive.to_string()],
+                    |(_, modules)| {
+                        modules
+                            .iter()
+                            .map(|module| {
+                                level.map_or_else(
+                                    || (*module).to_string(),
+                                    |level| format!("{}={}", module, level),
+                                )
+                            })
+                            .collect()
+                    },
+                )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A log file writer which rotates the file when it grows beyond `MAX_LOG_FILE_SIZE`
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn new(directory: &Path, name: &str) -> Result<Self> {
+        let path = directory.join(name);
+        let file = Self::open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self { path, file, size })
+    }
+
+    fn open(path: &Path) -> std::io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    fn rotated_path(&self, idx: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", idx));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+
+        for idx in (1..MAX_ROTATED_LOG_FILES).rev() {
+            let from = self.rotated_path(idx);
+            if from.is_file() {
+                std::fs::rename(from, self.rotated_path(idx + 1))?;
+            }
+        }
+
+        std::fs::rename(&self.path, self.rotated_path(1))?;
+        self.file = Self::open(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.size + buf.len() as u64 > MAX_LOG_FILE_SIZE && self.size > 0 {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
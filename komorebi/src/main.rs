@@ -4,14 +4,15 @@
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
+use std::path::PathBuf;
 use std::process::Command;
 use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicI32;
 use std::sync::atomic::AtomicU32;
+use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-#[cfg(feature = "deadlock_detection")]
 use std::thread;
-#[cfg(feature = "deadlock_detection")]
 use std::time::Duration;
 
 use clap::Parser;
@@ -24,20 +25,40 @@ use lazy_static::lazy_static;
 use parking_lot::deadlock;
 use parking_lot::Mutex;
 use serde::Serialize;
+use sysinfo::Pid;
 use sysinfo::SystemExt;
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::reload;
 use tracing_subscriber::EnvFilter;
 use which::which;
 use winreg::enums::HKEY_CURRENT_USER;
 use winreg::RegKey;
 
+use komorebi_core::ApplicationIdentifier;
+use komorebi_core::CursorPlacement;
 use komorebi_core::HidingBehaviour;
+use komorebi_core::NotificationEvent;
+use komorebi_core::Rect;
+use komorebi_core::SizeConstraints;
 use komorebi_core::SocketMessage;
 
+use crate::float_positions::listen_for_floating_windows;
+use crate::hints::listen_for_hints;
+use crate::icons::listen_for_icons;
+use crate::layout_cycle::listen_for_layout_cycles;
+use crate::logging::RotatingFile;
+use crate::mirror::listen_for_mirror_peers;
 use crate::process_command::listen_for_commands;
 use crate::process_event::listen_for_events;
 use crate::process_movement::listen_for_movements;
+use crate::session::listen_for_session_changes;
+use crate::urgency::listen_for_urgent_windows;
+use crate::wallpaper::listen_for_wallpaper_changes;
+use crate::watchdog::listen_for_orphans;
+use crate::watchdog::listen_for_stale_hooks;
+use crate::websocket::listen_for_websocket_connections;
+use crate::window::MorphSignature;
 use crate::window_manager::State;
 use crate::window_manager::WindowManager;
 use crate::window_manager_event::WindowManagerEvent;
@@ -46,13 +67,34 @@ use crate::windows_api::WindowsApi;
 #[macro_use]
 mod ring;
 
+mod cancellation;
+mod configuration;
 mod container;
+mod float_positions;
+mod hints;
+mod icons;
+mod layout_cycle;
+mod learning;
+mod logging;
+mod mirror;
 mod monitor;
+mod operation_guard;
+mod performance;
+mod process_cache;
 mod process_command;
 mod process_event;
 mod process_movement;
+mod rate_limit;
+mod restart;
+mod rule_groups;
+mod secret;
+mod session;
 mod set_window_position;
 mod styles;
+mod urgency;
+mod wallpaper;
+mod watchdog;
+mod websocket;
 mod window;
 mod window_manager;
 mod window_manager_event;
@@ -61,6 +103,13 @@ mod windows_callbacks;
 mod winevent;
 mod winevent_listener;
 mod workspace;
+mod workspace_history;
+
+/// A substring of the command line of a process, and the (monitor, workspace) which its windows
+/// are sent to
+type CommandLineWorkspaceRule = (String, (usize, usize));
+/// A workspace rule which is removed once it has sent a window to its (monitor, workspace)
+type WorkspaceRuleOnce = (ApplicationIdentifier, String, (usize, usize));
 
 lazy_static! {
     static ref HIDDEN_HWNDS: Arc<Mutex<Vec<isize>>> = Arc::new(Mutex::new(vec![]));
@@ -81,14 +130,54 @@ lazy_static! {
     ]));
     static ref WORKSPACE_RULES: Arc<Mutex<HashMap<String, (usize, usize)>>> =
         Arc::new(Mutex::new(HashMap::new()));
+    // Command line rules match on substrings, so they are checked in the order they were added
+    static ref COMMAND_LINE_WORKSPACE_RULES: Arc<Mutex<Vec<CommandLineWorkspaceRule>>> =
+        Arc::new(Mutex::new(vec![]));
+    static ref COMMAND_LINE_FLOAT_IDENTIFIERS: Arc<Mutex<Vec<String>>> =
+        Arc::new(Mutex::new(vec![]));
+    // One-shot rules are removed as soon as they have been applied to a window
+    static ref FLOAT_RULES_ONCE: Arc<Mutex<Vec<(ApplicationIdentifier, String)>>> =
+        Arc::new(Mutex::new(vec![]));
+    static ref WORKSPACE_RULES_ONCE: Arc<Mutex<Vec<WorkspaceRuleOnce>>> =
+        Arc::new(Mutex::new(vec![]));
+    // Workspace rules bound to a workspace name or id, which are resolved to indices whenever
+    // workspaces are renamed or reordered
+    static ref WORKSPACE_RULE_BINDINGS: Arc<Mutex<Vec<(ApplicationIdentifier, String, String)>>> =
+        Arc::new(Mutex::new(vec![]));
+    // Windows floated by a one-shot rule stay floating after the rule has been removed
+    static ref FLOATED_ONCE_HWNDS: Arc<Mutex<Vec<isize>>> = Arc::new(Mutex::new(vec![]));
     static ref MANAGE_IDENTIFIERS: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
-    static ref FLOAT_IDENTIFIERS: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![
+    static ref FOLLOW_IDENTIFIERS: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+    static ref SWALLOW_IDENTIFIERS: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+    static ref APP_GROUP_IDENTIFIERS: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+    static ref FLOAT_IDENTIFIERS: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+    // Window classes (which can contain * and ? wildcards) of system windows that are never managed
+    static ref CLASS_BLOCKLIST: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![
+        // Tooltips and menus
+        "tooltips_class32".to_string(),
+        "#32768".to_string(),
+        // IME windows
+        "IME".to_string(),
+        "MSCTFIME UI".to_string(),
+        "Shell_InputSwitchTopLevelWindow".to_string(),
+        // The task switcher
+        "MultitaskingViewFrame".to_string(),
+        "TaskSwitcherWnd".to_string(),
+        "XamlExplorerHostIslandWindow".to_string(),
+        // Taskbars and shell flyouts
+        "Shell_*TrayWnd".to_string(),
+        "NotifyIconOverflowWindow".to_string(),
+        "TopLevelWindowForOverflowXamlIsland".to_string(),
+        "Windows.UI.Core.CoreWindow".to_string(),
         // mstsc.exe creates these on Windows 11 when a WSL process is launched
         // https://github.com/LGUG2Z/komorebi/issues/74
         "OPContainerClass".to_string(),
-        "IHWindowClass".to_string()
+        "IHWindowClass".to_string(),
     ]));
     static ref BORDER_OVERFLOW_IDENTIFIERS: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+    static ref NO_BORDER_OVERFLOW_IDENTIFIERS: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+    static ref SIZE_CONSTRAINTS: Arc<Mutex<HashMap<String, SizeConstraints>>> =
+        Arc::new(Mutex::new(HashMap::new()));
     static ref WSL2_UI_PROCESSES: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![
         "X410.exe".to_string(),
         "mstsc.exe".to_string(),
@@ -98,10 +187,33 @@ lazy_static! {
         Arc::new(Mutex::new(HashMap::new()));
     static ref HIDING_BEHAVIOUR: Arc<Mutex<HidingBehaviour>> =
         Arc::new(Mutex::new(HidingBehaviour::Minimize));
+    static ref MOUSE_FOLLOWS_FOCUS_PLACEMENT: Arc<Mutex<CursorPlacement>> =
+        Arc::new(Mutex::new(CursorPlacement::Center));
+    /// The last cursor position in each window, relative to its top-left corner
+    static ref LAST_CURSOR_POSITIONS: Arc<Mutex<HashMap<isize, (i32, i32)>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    /// The size of each monitor by hmonitor, which is kept up to date as monitors are reconciled
+    /// so that checking for fullscreen windows doesn't have to query the monitor on every event
+    static ref MONITOR_SIZES: Arc<Mutex<HashMap<isize, Rect>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    static ref WINDOW_SIGNATURES: Arc<Mutex<HashMap<isize, MorphSignature>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    static ref HIDING_BEHAVIOUR_RULES: Arc<Mutex<HashMap<String, HidingBehaviour>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    static ref ALIASES: Arc<Mutex<HashMap<String, Vec<SocketMessage>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
 }
 
 pub static CUSTOM_FFM: AtomicBool = AtomicBool::new(false);
 pub static SESSION_ID: AtomicU32 = AtomicU32::new(0);
+pub static MOUSE_FOLLOWS_FOCUS_OFFSET: AtomicI32 = AtomicI32::new(0);
+pub static WORKSPACE_RULES_ON_TITLE_CHANGE: AtomicBool = AtomicBool::new(false);
+pub static THROTTLE_RETILES: AtomicBool = AtomicBool::new(false);
+pub static RETILE_FPS_CAP: AtomicU32 = AtomicU32::new(0);
+pub static RETILE_PENDING: AtomicBool = AtomicBool::new(false);
+pub static RETILE_BATCH_SIZE: AtomicUsize = AtomicUsize::new(8);
+pub static KOMOREBI_ELEVATED: AtomicBool = AtomicBool::new(false);
+pub static FLOAT_ELEVATED_WINDOWS: AtomicBool = AtomicBool::new(false);
 
 fn setup() -> Result<(WorkerGuard, WorkerGuard)> {
     if std::env::var("RUST_LIB_BACKTRACE").is_err() {
@@ -115,15 +227,18 @@ fn setup() -> Result<(WorkerGuard, WorkerGuard)> {
     }
 
     let home = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
-    let appender = tracing_appender::rolling::never(home, "komorebi.log");
-    let color_appender = tracing_appender::rolling::never(std::env::temp_dir(), "komorebi.log");
+    let appender = RotatingFile::new(&home, "komorebi.log")?;
+    let color_appender = RotatingFile::new(&std::env::temp_dir(), "komorebi.log")?;
     let (non_blocking, guard) = tracing_appender::non_blocking(appender);
     let (color_non_blocking, color_guard) = tracing_appender::non_blocking(color_appender);
 
+    let (filter, filter_handle) = reload::Layer::new(EnvFilter::from_default_env());
+    logging::set_filter_handle(filter_handle);
+
     tracing::subscriber::set_global_default(
-        tracing_subscriber::fmt::Subscriber::builder()
-            .with_env_filter(EnvFilter::from_default_env())
-            .finish()
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(tracing_subscriber::fmt::Layer::default())
             .with(
                 tracing_subscriber::fmt::Layer::default()
                     .with_writer(non_blocking)
@@ -167,6 +282,15 @@ fn setup() -> Result<(WorkerGuard, WorkerGuard)> {
     Ok((guard, color_guard))
 }
 
+/// A file in the temporary directory, where state which has to survive komorebi being restarted
+/// is kept
+#[must_use]
+pub fn temp_file(name: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(name);
+    path
+}
+
 pub fn load_configuration() -> Result<()> {
     let home = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
 
@@ -205,6 +329,51 @@ pub fn load_configuration() -> Result<()> {
     Ok(())
 }
 
+pub fn load_display_profile(display_set: &str) -> Result<()> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
+
+    let mut profile_v1 = home.clone();
+    profile_v1.push("komorebi.profiles");
+    profile_v1.push(format!("{}.ahk", display_set));
+
+    let mut profile_v2 = home;
+    profile_v2.push("komorebi.profiles");
+    profile_v2.push(format!("{}.ahk2", display_set));
+
+    if profile_v1.exists() && which("autohotkey.exe").is_ok() {
+        tracing::info!(
+            "loading display profile: {}",
+            profile_v1
+                .as_os_str()
+                .to_str()
+                .ok_or_else(|| anyhow!("cannot convert path to string"))?
+        );
+
+        Command::new("autohotkey.exe")
+            .arg(profile_v1.as_os_str())
+            .output()?;
+    } else if profile_v2.exists() && which("AutoHotkey64.exe").is_ok() {
+        tracing::info!(
+            "loading display profile: {}",
+            profile_v2
+                .as_os_str()
+                .to_str()
+                .ok_or_else(|| anyhow!("cannot convert path to string"))?
+        );
+
+        Command::new("AutoHotkey64.exe")
+            .arg(profile_v2.as_os_str())
+            .output()?;
+    } else {
+        tracing::info!(
+            "there is no display profile for display set: {}",
+            display_set
+        );
+    };
+
+    Ok(())
+}
+
 pub fn current_virtual_desktop() -> Option<Vec<u8>> {
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
 
@@ -248,17 +417,21 @@ pub fn current_virtual_desktop() -> Option<Vec<u8>> {
     current
 }
 
-#[derive(Debug, Serialize)]
-#[serde(untagged)]
-pub enum NotificationEvent {
-    WindowManager(WindowManagerEvent),
-    Socket(SocketMessage),
-}
-
 #[derive(Debug, Serialize)]
 pub struct Notification {
     pub event: NotificationEvent,
     pub state: State,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<StackIndicator>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StackIndicator {
+    pub size: usize,
+    pub focused_window_idx: usize,
+    pub label: String,
+    /// The titles of the windows in the stack, in order, for rendering as tab labels
+    pub titles: Vec<String>,
 }
 
 pub fn notify_subscribers(notification: &str) -> Result<()> {
@@ -292,6 +465,9 @@ pub fn notify_subscribers(notification: &str) -> Result<()> {
         subscriptions.remove(&subscriber);
     }
 
+    websocket::broadcast(notification);
+    mirror::publish(notification);
+
     Ok(())
 }
 
@@ -324,6 +500,12 @@ struct Opts {
     /// Allow the use of komorebi's custom focus-follows-mouse implementation
     #[clap(long = "ffm")]
     focus_follows_mouse: bool,
+    /// Restore the window arrangement handed over by the restart command
+    #[clap(long, hide = true)]
+    restore_state: Option<PathBuf>,
+    /// Wait for the process with this id to exit before starting
+    #[clap(long, hide = true)]
+    await_process: Option<u32>,
 }
 
 #[tracing::instrument]
@@ -331,14 +513,35 @@ fn main() -> Result<()> {
     let opts: Opts = Opts::parse();
     CUSTOM_FFM.store(opts.focus_follows_mouse, Ordering::SeqCst);
 
-    let arg_count = std::env::args().count();
-    let has_valid_args = arg_count == 1 || (arg_count == 2 && CUSTOM_FFM.load(Ordering::SeqCst));
+    let mut expected_arg_count = 1;
+    if opts.focus_follows_mouse {
+        expected_arg_count += 1;
+    }
+
+    if opts.restore_state.is_some() {
+        expected_arg_count += 2;
+    }
+
+    if opts.await_process.is_some() {
+        expected_arg_count += 2;
+    }
+
+    let has_valid_args = std::env::args().count() == expected_arg_count;
 
     if has_valid_args {
         let session_id = WindowsApi::process_id_to_session_id()?;
         SESSION_ID.store(session_id, Ordering::SeqCst);
 
         let mut system = sysinfo::System::new_all();
+        // The restart command starts this process before the previous one has exited
+        if let Some(process_id) = opts.await_process {
+            if let Ok(pid) = Pid::try_from(process_id) {
+                while system.refresh_process(pid) {
+                    thread::sleep(Duration::from_millis(50));
+                }
+            }
+        }
+
         system.refresh_processes();
 
         if system.process_by_name("komorebi.exe").len() > 1 {
@@ -355,6 +558,9 @@ fn main() -> Result<()> {
         let process_id = WindowsApi::current_process_id();
         WindowsApi::allow_set_foreground_window(process_id)?;
 
+        let elevated = WindowsApi::is_process_elevated(process_id).unwrap_or_default();
+        KOMOREBI_ELEVATED.store(elevated, Ordering::SeqCst);
+
         let (outgoing, incoming): (Sender<WindowManagerEvent>, Receiver<WindowManagerEvent>) =
             crossbeam_channel::unbounded();
 
@@ -366,8 +572,30 @@ fn main() -> Result<()> {
         )))?));
 
         wm.lock().init()?;
+        configuration::capture_defaults(&wm.lock());
+
+        if let Some(path) = &opts.restore_state {
+            if let Err(error) = wm.lock().apply_restart_state(path) {
+                tracing::error!(
+                    "could not restore the state handed over on restart: {}",
+                    error
+                );
+            }
+        }
+
         listen_for_commands(wm.clone());
         listen_for_events(wm.clone());
+        listen_for_stale_hooks(wm.clone());
+        listen_for_orphans(wm.clone());
+        listen_for_layout_cycles(wm.clone());
+        listen_for_session_changes(wm.clone());
+        listen_for_urgent_windows(wm.clone());
+        listen_for_hints();
+        listen_for_wallpaper_changes();
+        listen_for_floating_windows();
+        listen_for_icons();
+        listen_for_websocket_connections(wm.clone());
+        listen_for_mirror_peers(wm.clone());
 
         if CUSTOM_FFM.load(Ordering::SeqCst) {
             listen_for_movements(wm.clone());
@@ -375,6 +603,12 @@ fn main() -> Result<()> {
 
         load_configuration()?;
 
+        // Windows open at startup are adopted before the configuration has sent any workspace
+        // rules, so they are placed again once it has been loaded
+        if let Err(error) = wm.lock().apply_workspace_rules() {
+            tracing::error!("{}", error);
+        }
+
         let (ctrlc_sender, ctrlc_receiver) = crossbeam_channel::bounded(1);
         ctrlc::set_handler(move || {
             ctrlc_sender
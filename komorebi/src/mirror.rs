@@ -0,0 +1,344 @@
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+
+use komorebi_core::SocketMessage;
+
+use crate::rate_limit::RateLimiter;
+use crate::secret;
+use crate::window_manager;
+use crate::window_manager::WindowManager;
+
+const CONNECT_TIMEOUT_SECONDS: u64 = 5;
+
+lazy_static! {
+    static ref MIRROR_TARGET_CHANNEL: Arc<Mutex<(Sender<MirrorTarget>, Receiver<MirrorTarget>)>> =
+        Arc::new(Mutex::new(crossbeam_channel::unbounded()));
+    /// Lines to be written to the connected peer, if there is one
+    static ref PEER: Arc<Mutex<Option<Sender<String>>>> = Arc::new(Mutex::new(None));
+    /// The state most recently published by the connected peer
+    static ref PEER_STATE: Arc<Mutex<Option<serde_json::Value>>> = Arc::new(Mutex::new(None));
+}
+
+/// Where to find a mirroring peer, and the secret which both instances must have been given
+#[derive(Debug)]
+enum MirrorTarget {
+    Listen(u16, String),
+    Connect(String, String),
+}
+
+/// Every line sent between peers is one of these, encoded as JSON
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "content")]
+enum MirrorMessage {
+    /// A random nonce, which must be the first line sent by each instance
+    Challenge(String),
+    /// The HMAC of the challenge sent by the receiving instance, which must be the second line
+    /// sent by each instance
+    Answer(String),
+    /// A notification from the sending instance, including its current state
+    Notification(serde_json::Value),
+    /// A socket message for the receiving instance to handle
+    Command(SocketMessage),
+}
+
+/// Accept a mirroring peer which knows `secret` on `port` on every network interface
+pub fn listen(port: u16, secret: String) -> Result<()> {
+    if secret.is_empty() {
+        return Err(anyhow!("the mirroring secret cannot be empty"));
+    }
+
+    Ok(MIRROR_TARGET_CHANNEL
+        .lock()
+        .0
+        .send(MirrorTarget::Listen(port, secret))?)
+}
+
+/// Connect to a mirroring peer which knows `secret` listening at `address`, given as host:port
+pub fn connect(address: String, secret: String) -> Result<()> {
+    if secret.is_empty() {
+        return Err(anyhow!("the mirroring secret cannot be empty"));
+    }
+
+    Ok(MIRROR_TARGET_CHANNEL
+        .lock()
+        .0
+        .send(MirrorTarget::Connect(address, secret))?)
+}
+
+/// Send a socket message for the connected peer to handle
+pub fn send(message: SocketMessage) -> Result<()> {
+    let line = serde_json::to_string(&MirrorMessage::Command(message))?;
+
+    PEER.lock()
+        .as_ref()
+        .ok_or_else(|| anyhow!("there is no mirroring peer connected"))?
+        .send(line)?;
+
+    Ok(())
+}
+
+/// Forward a notification to the connected peer, if there is one
+pub fn publish(notification: &str) {
+    if let Some(peer) = PEER.lock().as_ref() {
+        let line = serde_json::from_str(notification)
+            .map(MirrorMessage::Notification)
+            .and_then(|message| serde_json::to_string(&message));
+
+        match line {
+            Ok(line) => {
+                if peer.send(line).is_err() {
+                    tracing::debug!("mirroring peer has disconnected");
+                }
+            }
+            Err(error) => tracing::error!("{}", error),
+        }
+    }
+}
+
+pub fn peer_state() -> Option<serde_json::Value> {
+    PEER_STATE.lock().clone()
+}
+
+/// The socket messages which a peer is allowed to send, which only change what is focused
+const fn is_allowed(message: &SocketMessage) -> bool {
+    matches!(
+        message,
+        SocketMessage::FocusWindow(_)
+            | SocketMessage::CycleFocusWindow(_)
+            | SocketMessage::FocusContainerById(_)
+            | SocketMessage::FocusWindowByHwnd(_)
+            | SocketMessage::FocusPrimaryZone
+            | SocketMessage::CycleFocusMonitor(_)
+            | SocketMessage::CycleFocusWorkspace(_)
+            | SocketMessage::FocusMonitorNumber(_)
+            | SocketMessage::FocusWorkspaceNumber(_)
+            | SocketMessage::FocusMonitorWorkspaceNumber(..)
+            | SocketMessage::FocusWorkspaceBack
+            | SocketMessage::FocusWorkspaceForward
+    )
+}
+
+// Answers include the role of the instance giving them, so that a connection cannot pass off the
+// answer to its own challenge as an answer of its own by sending the same challenge back
+fn answer(secret: &str, listening: bool, challenge: &str) -> String {
+    let role = if listening { "listen" } else { "connect" };
+    secret::hmac(
+        secret.as_bytes(),
+        format!("{}:{}", role, challenge).as_bytes(),
+    )
+}
+
+#[tracing::instrument]
+pub fn listen_for_mirror_peers(wm: Arc<Mutex<WindowManager>>) {
+    let receiver = MIRROR_TARGET_CHANNEL.lock().1.clone();
+
+    thread::spawn(move || {
+        // Nothing is opened until a mirror-listen or mirror-connect command has been received
+        for target in receiver {
+            let wm = wm.clone();
+            match target {
+                MirrorTarget::Listen(port, secret) => {
+                    let listener = match TcpListener::bind(("0.0.0.0", port)) {
+                        Ok(listener) => listener,
+                        Err(error) => {
+                            tracing::error!(
+                                "could not listen for a mirroring peer on port {}: {}",
+                                port,
+                                error
+                            );
+                            continue;
+                        }
+                    };
+
+                    tracing::info!("listening for a mirroring peer on port {}", port);
+
+                    // Only one peer is mirrored at a time, so connections are handled in turn
+                    thread::spawn(move || {
+                        for stream in listener.incoming() {
+                            match stream {
+                                Ok(stream) => {
+                                    if let Err(error) = handle_peer(&wm, stream, &secret, true) {
+                                        tracing::error!("{}", error);
+                                    }
+                                }
+                                Err(error) => tracing::error!("{}", error),
+                            }
+                        }
+                    });
+                }
+                MirrorTarget::Connect(address, secret) => {
+                    thread::spawn(move || {
+                        let stream = address
+                            .to_socket_addrs()
+                            .map_err(color_eyre::Report::from)
+                            .and_then(|mut addresses| {
+                                addresses
+                                    .next()
+                                    .ok_or_else(|| anyhow!("there is no address for {}", address))
+                            })
+                            .and_then(|socket_address| {
+                                Ok(TcpStream::connect_timeout(
+                                    &socket_address,
+                                    Duration::from_secs(CONNECT_TIMEOUT_SECONDS),
+                                )?)
+                            });
+
+                        match stream {
+                            Ok(stream) => {
+                                if let Err(error) = handle_peer(&wm, stream, &secret, false) {
+                                    tracing::error!("{}", error);
+                                }
+                            }
+                            Err(error) => {
+                                tracing::error!(
+                                    "could not connect to mirroring peer {}: {}",
+                                    address,
+                                    error
+                                );
+                            }
+                        }
+                    });
+                }
+            }
+        }
+    });
+}
+
+fn handle_peer(
+    wm: &Arc<Mutex<WindowManager>>,
+    stream: TcpStream,
+    secret: &str,
+    listening: bool,
+) -> Result<()> {
+    let peer_address = stream.peer_addr()?;
+
+    let challenge = nanoid::nanoid!();
+    let mut writer = stream.try_clone()?;
+    writeln!(
+        writer,
+        "{}",
+        serde_json::to_string(&MirrorMessage::Challenge(challenge.clone()))?
+    )?;
+
+    // Nothing from the peer is trusted until it has answered our challenge, which it must do
+    // promptly
+    stream.set_read_timeout(Option::from(Duration::from_secs(CONNECT_TIMEOUT_SECONDS)))?;
+    let mut lines = BufReader::new(stream).lines();
+    let mut next_line = || {
+        lines
+            .next()
+            .ok_or_else(|| anyhow!("mirroring peer {} disconnected", peer_address))?
+            .map_err(color_eyre::Report::from)
+    };
+
+    let peer_challenge = match serde_json::from_str::<MirrorMessage>(&next_line()?) {
+        Ok(MirrorMessage::Challenge(peer_challenge)) => peer_challenge,
+        _ => {
+            return Err(anyhow!(
+                "mirroring peer {} did not send a challenge",
+                peer_address
+            ));
+        }
+    };
+
+    writeln!(
+        writer,
+        "{}",
+        serde_json::to_string(&MirrorMessage::Answer(answer(
+            secret,
+            listening,
+            &peer_challenge
+        )))?
+    )?;
+
+    let expected = answer(secret, !listening, &challenge);
+    match serde_json::from_str::<MirrorMessage>(&next_line()?) {
+        Ok(MirrorMessage::Answer(received))
+            if secret::matches(expected.as_bytes(), received.as_bytes()) => {}
+        _ => {
+            return Err(anyhow!(
+                "mirroring peer {} did not answer the challenge with the expected secret",
+                peer_address
+            ));
+        }
+    }
+
+    // The writer is a handle to the same socket, so this lifts the timeout from the reader too
+    writer.set_read_timeout(None)?;
+    tracing::info!("mirroring with peer {}", peer_address);
+
+    let (sender, receiver) = crossbeam_channel::unbounded::<String>();
+
+    // The peer shouldn't have to wait for the next notification to know the current state
+    let state = serde_json::to_value(window_manager::State::from(&*wm.lock()))?;
+    sender.send(serde_json::to_string(&MirrorMessage::Notification(
+        json!({ "state": state }),
+    ))?)?;
+
+    *PEER.lock() = Option::from(sender.clone());
+
+    // The writer stops once the sender has been dropped or replaced by that of a new peer
+    thread::spawn(move || {
+        for line in receiver {
+            if let Err(error) = writeln!(writer, "{}", line) {
+                tracing::error!("could not write to mirroring peer: {}", error);
+                break;
+            }
+        }
+    });
+
+    let mut limiter = RateLimiter::default();
+    for line in lines {
+        match serde_json::from_str::<MirrorMessage>(&line?) {
+            Ok(MirrorMessage::Notification(notification)) => {
+                *PEER_STATE.lock() = notification.get("state").cloned();
+            }
+            Ok(MirrorMessage::Command(_)) if !limiter.allow() => {
+                tracing::warn!("dropping mirroring peer message over the rate limit");
+            }
+            Ok(MirrorMessage::Command(message)) if !is_allowed(&message) => {
+                tracing::warn!("ignoring disallowed command sent by peer: {}", message);
+            }
+            Ok(MirrorMessage::Command(message)) => {
+                if let Err(error) = wm.lock().handle_command(message) {
+                    tracing::error!("{}", error);
+                }
+            }
+            Ok(MirrorMessage::Challenge(_) | MirrorMessage::Answer(_)) => {}
+            Err(error) => {
+                tracing::error!("could not parse message from mirroring peer: {}", error);
+            }
+        }
+    }
+
+    tracing::info!("mirroring peer {} disconnected", peer_address);
+
+    // A newer peer may already have replaced this one, and shouldn't be forgotten
+    let mut peer = PEER.lock();
+    if peer
+        .as_ref()
+        .map_or(false, |peer| peer.same_channel(&sender))
+    {
+        *peer = None;
+        *PEER_STATE.lock() = None;
+    }
+
+    Ok(())
+}
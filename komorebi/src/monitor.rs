@@ -1,5 +1,8 @@
 use std::collections::HashMap;
 use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
 
 use color_eyre::eyre::anyhow;
 use color_eyre::Result;
@@ -12,8 +15,14 @@ use serde::Serialize;
 use komorebi_core::Rect;
 
 use crate::container::Container;
+use crate::process_event;
 use crate::ring::Ring;
 use crate::workspace::Workspace;
+use crate::RETILE_FPS_CAP;
+use crate::THROTTLE_RETILES;
+
+// Used when the refresh rate of a monitor could not be determined
+const DEFAULT_REFRESH_RATE: u32 = 60;
 
 #[derive(Debug, Clone, Serialize, Getters, CopyGetters, MutGetters, Setters)]
 pub struct Monitor {
@@ -23,6 +32,40 @@ pub struct Monitor {
     size: Rect,
     #[getset(get = "pub", set = "pub")]
     work_area_size: Rect,
+    #[getset(get = "pub")]
+    device: String,
+    /// The device interface path of the display, which unlike the device name identifies the
+    /// physical display and the port it is connected to
+    #[getset(get = "pub", set = "pub")]
+    device_id: Option<String>,
+    #[getset(get_copy = "pub", set = "pub")]
+    group: Option<MonitorGroup>,
+    #[getset(get_copy = "pub", set = "pub")]
+    grouped_into: Option<isize>,
+    #[getset(get_copy = "pub", set = "pub")]
+    fullscreen_window: Option<isize>,
+    #[getset(get_copy = "pub", set = "pub")]
+    refresh_rate: Option<u32>,
+    /// Whether the display has been turned off or put to sleep without being unplugged, in
+    /// which case its last known geometry is kept and no layouts are applied until it wakes
+    #[getset(get_copy = "pub", set = "pub")]
+    asleep: bool,
+    /// Whether this monitor switches workspaces along with the others while workspace sync is on
+    #[getset(get_copy = "pub", set = "pub")]
+    workspace_sync: bool,
+    /// Areas reserved for other applications, relative to the top left corner of the monitor,
+    /// which are removed from the tiling area
+    #[getset(get = "pub", get_mut = "pub")]
+    exclusion_zones: Vec<Rect>,
+    /// Used instead of the work area reported by the OS, for docks and bars which do not
+    /// register themselves as an appbar
+    #[getset(get_copy = "pub", set = "pub")]
+    work_area_override: Option<Rect>,
+    #[serde(skip_serializing)]
+    last_update: Option<Instant>,
+    #[serde(skip_serializing)]
+    #[getset(get_copy = "pub")]
+    pending_update: bool,
     workspaces: Ring<Workspace>,
     #[serde(skip_serializing)]
     #[getset(get_mut = "pub")]
@@ -31,7 +74,76 @@ pub struct Monitor {
 
 impl_ring_elements!(Monitor, Workspace);
 
-pub fn new(id: isize, size: Rect, work_area_size: Rect) -> Monitor {
+/// Two side-by-side monitors which are tiled as a single logical work area. Layouts are
+/// calculated across a virtual work area which includes the physical gap between the two
+/// displays, and then mapped back to real coordinates so that windows spanning the seam line up.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct MonitorGroup {
+    pub secondary: isize,
+    pub bezel_gap: i32,
+    pub work_area_size: Rect,
+    seam: i32,
+    secondary_left: i32,
+}
+
+impl MonitorGroup {
+    pub fn new(secondary: isize, bezel_gap: i32, primary: &Rect, other: &Rect) -> Result<Self> {
+        let (left, right) = if primary.left <= other.left {
+            (primary, other)
+        } else {
+            (other, primary)
+        };
+
+        if left.left + left.right > right.left {
+            return Err(anyhow!(
+                "monitor groups can only be created from side-by-side monitors"
+            ));
+        }
+
+        let bezel_gap = bezel_gap.max(0);
+        let top = left.top.max(right.top);
+        let bottom = (left.top + left.bottom).min(right.top + right.bottom);
+
+        Ok(Self {
+            secondary,
+            bezel_gap,
+            work_area_size: Rect {
+                left: left.left,
+                top,
+                right: left.right + bezel_gap + right.right,
+                bottom: bottom - top,
+            },
+            seam: left.left + left.right,
+            secondary_left: right.left,
+        })
+    }
+
+    const fn real_x(&self, x: i32) -> i32 {
+        if x <= self.seam {
+            x
+        } else if x < self.seam + self.bezel_gap {
+            // Anything that would land behind the bezel is pulled back to the seam
+            self.seam
+        } else {
+            x - self.seam - self.bezel_gap + self.secondary_left
+        }
+    }
+
+    #[must_use]
+    pub const fn compensate(&self, rect: &Rect) -> Rect {
+        let left = self.real_x(rect.left);
+        let right = self.real_x(rect.left + rect.right);
+
+        Rect {
+            left,
+            top: rect.top,
+            right: right - left,
+            bottom: rect.bottom,
+        }
+    }
+}
+
+pub fn new(id: isize, size: Rect, work_area_size: Rect, device: String) -> Monitor {
     let mut workspaces = Ring::default();
     workspaces.elements_mut().push_back(Workspace::default());
 
@@ -39,6 +151,18 @@ pub fn new(id: isize, size: Rect, work_area_size: Rect) -> Monitor {
         id,
         size,
         work_area_size,
+        device,
+        device_id: None,
+        group: None,
+        grouped_into: None,
+        fullscreen_window: None,
+        refresh_rate: None,
+        asleep: false,
+        workspace_sync: true,
+        exclusion_zones: vec![],
+        work_area_override: None,
+        last_update: None,
+        pending_update: false,
         workspaces,
         workspace_names: HashMap::default(),
     }
@@ -85,7 +209,7 @@ impl Monitor {
     pub fn ensure_workspace_count(&mut self, ensure_count: usize) {
         if self.workspaces().len() < ensure_count {
             self.workspaces_mut()
-                .resize(ensure_count, Workspace::default());
+                .resize_with(ensure_count, Workspace::default);
         }
     }
 
@@ -113,7 +237,7 @@ impl Monitor {
 
         let target_workspace = match workspaces.get_mut(target_workspace_idx) {
             None => {
-                workspaces.resize(target_workspace_idx + 1, Workspace::default());
+                workspaces.resize_with(target_workspace_idx + 1, Workspace::default);
                 workspaces.get_mut(target_workspace_idx).unwrap()
             }
             Some(workspace) => workspace,
@@ -136,7 +260,7 @@ impl Monitor {
             let workspaces = self.workspaces_mut();
 
             if workspaces.get(idx).is_none() {
-                workspaces.resize(idx + 1, Workspace::default());
+                workspaces.resize_with(idx + 1, Workspace::default);
             }
 
             self.workspaces.focus(idx);
@@ -156,6 +280,45 @@ impl Monitor {
         Ok(())
     }
 
+    /// The area that layouts on this monitor should be calculated across, which spans both
+    /// displays if this monitor is the primary monitor of a group
+    pub fn tiling_area(&self) -> Rect {
+        let mut area = self.group.map_or_else(
+            || {
+                if let Some(work_area) = self.work_area_override {
+                    work_area
+                } else if self.taskbar_hidden() {
+                    self.size
+                } else {
+                    self.work_area_size
+                }
+            },
+            |group| group.work_area_size,
+        );
+
+        for zone in &self.exclusion_zones {
+            area.subtract(&Rect {
+                left: self.size.left + zone.left,
+                top: self.size.top + zone.top,
+                ..*zone
+            });
+        }
+
+        area
+    }
+
+    /// Whether the focused workspace hides the taskbar on this monitor
+    pub fn taskbar_hidden(&self) -> bool {
+        self.focused_workspace()
+            .map_or(false, Workspace::taskbar_hidden)
+    }
+
+    /// Whether the focused workspace still has windows to position from a time-sliced update
+    pub fn has_pending_positions(&self) -> bool {
+        self.focused_workspace()
+            .map_or(false, Workspace::has_pending_positions)
+    }
+
     pub fn new_workspace_idx(&self) -> usize {
         self.workspaces().len()
     }
@@ -165,12 +328,52 @@ impl Monitor {
         offset: Option<Rect>,
         invisible_borders: &Rect,
     ) -> Result<()> {
-        let work_area = *self.work_area_size();
+        if self.fullscreen_window.is_some() {
+            tracing::trace!("not updating workspace while a fullscreen window is active");
+            return Ok(());
+        }
+
+        if self.asleep {
+            tracing::trace!("not updating workspace while the monitor is asleep");
+            return Ok(());
+        }
+
+        // A throttled update is deferred until the next frame, when it will be flushed by the
+        // event loop, so that bursts of events only result in one layout pass per frame
+        if let (Some(interval), Some(last_update)) = (self.frame_interval(), self.last_update) {
+            if last_update.elapsed() < interval {
+                tracing::trace!("throttling workspace update until the next frame");
+                self.pending_update = true;
+                process_event::defer_retile();
+                return Ok(());
+            }
+        }
+
+        let work_area = self.tiling_area();
+        let group = self.group();
 
         self.focused_workspace_mut()
             .ok_or_else(|| anyhow!("there is no workspace"))?
-            .update(&work_area, offset, invisible_borders)?;
+            .update(&work_area, offset, invisible_borders, group)?;
+
+        self.last_update = Option::from(Instant::now());
+        self.pending_update = false;
 
         Ok(())
     }
+
+    /// The minimum time between two updates of this monitor, if updates are being throttled
+    fn frame_interval(&self) -> Option<Duration> {
+        if !THROTTLE_RETILES.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        let refresh_rate = self.refresh_rate.unwrap_or(DEFAULT_REFRESH_RATE);
+        let fps = match RETILE_FPS_CAP.load(Ordering::SeqCst) {
+            0 => refresh_rate,
+            cap => refresh_rate.min(cap),
+        };
+
+        Option::from(Duration::from_secs(1) / fps.max(1))
+    }
 }
@@ -0,0 +1,51 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+/// Focus events from windows which were restored or moved by a retile keep arriving for a short
+/// while after the retile pass itself has completed
+pub const OPERATION_SETTLE_MILLISECONDS: u64 = 200;
+
+static OPERATIONS_IN_PROGRESS: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref LAST_OPERATION_COMPLETED: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+}
+
+/// Held for the duration of a retile pass so that focus-follows-mouse and mouse-follows-focus
+/// do not react to the transient focus events of the windows being repositioned
+pub struct OperationGuard;
+
+impl OperationGuard {
+    #[must_use]
+    pub fn start() -> Self {
+        OPERATIONS_IN_PROGRESS.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        if OPERATIONS_IN_PROGRESS.fetch_sub(1, Ordering::SeqCst) == 1 {
+            *LAST_OPERATION_COMPLETED.lock() = Option::from(Instant::now());
+        }
+    }
+}
+
+pub fn in_progress() -> bool {
+    OPERATIONS_IN_PROGRESS.load(Ordering::SeqCst) > 0
+}
+
+/// Whether an operation is in progress or completed too recently for its focus events to have
+/// settled
+pub fn settling() -> bool {
+    in_progress()
+        || LAST_OPERATION_COMPLETED.lock().map_or(false, |completed| {
+            completed.elapsed() < Duration::from_millis(OPERATION_SETTLE_MILLISECONDS)
+        })
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LatencyCounter {
+    pub count: u64,
+    pub average_microseconds: u64,
+    pub max_microseconds: u64,
+    #[serde(skip)]
+    total_microseconds: u64,
+}
+
+impl LatencyCounter {
+    pub fn record(&mut self, duration: Duration) {
+        let microseconds = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+
+        self.count += 1;
+        self.total_microseconds = self.total_microseconds.saturating_add(microseconds);
+        self.average_microseconds = self.total_microseconds / self.count;
+        self.max_microseconds = self.max_microseconds.max(microseconds);
+    }
+}
+
+/// Timings which are collected while performance counters are enabled, so that slow layout
+/// passes can be traced back to the applications whose events triggered them
+#[derive(Debug)]
+pub struct PerformanceCounters {
+    pub enabled: bool,
+    since: Instant,
+    event_to_retile_latency: LatencyCounter,
+    retile_duration: LatencyCounter,
+    command_latency: LatencyCounter,
+    event_latency_by_exe: HashMap<String, LatencyCounter>,
+}
+
+impl Default for PerformanceCounters {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            since: Instant::now(),
+            event_to_retile_latency: LatencyCounter::default(),
+            retile_duration: LatencyCounter::default(),
+            command_latency: LatencyCounter::default(),
+            event_latency_by_exe: HashMap::new(),
+        }
+    }
+}
+
+/// The serializable view of `PerformanceCounters` which is included in the State JSON
+#[derive(Debug, Clone, Serialize)]
+pub struct PerformanceReport {
+    pub seconds_elapsed: u64,
+    pub events_per_second: f64,
+    pub event_to_retile_latency: LatencyCounter,
+    pub retile_duration: LatencyCounter,
+    pub command_latency: LatencyCounter,
+    pub event_latency_by_exe: HashMap<String, LatencyCounter>,
+}
+
+impl PerformanceCounters {
+    pub fn reset(&mut self) {
+        *self = Self {
+            enabled: self.enabled,
+            ..Self::default()
+        };
+    }
+
+    /// `received` is the point at which the event was taken off the event channel, so the
+    /// recorded latency includes any time spent waiting for the window manager lock
+    pub fn record_event(&mut self, exe: Option<String>, received: Instant) {
+        let latency = received.elapsed();
+        self.event_to_retile_latency.record(latency);
+
+        if let Some(exe) = exe {
+            self.event_latency_by_exe
+                .entry(exe)
+                .or_default()
+                .record(latency);
+        }
+    }
+
+    /// `queued` is the point at which the command was read from the socket, so the recorded
+    /// latency includes any time spent waiting behind other commands
+    pub fn record_command(&mut self, queued: Instant) {
+        if self.enabled {
+            self.command_latency.record(queued.elapsed());
+        }
+    }
+
+    pub fn record_retile(&mut self, started: Instant) {
+        if self.enabled {
+            self.retile_duration.record(started.elapsed());
+        }
+    }
+
+    pub fn report(&self) -> Option<PerformanceReport> {
+        if !self.enabled {
+            return None;
+        }
+
+        let elapsed = self.since.elapsed();
+
+        #[allow(clippy::cast_precision_loss)]
+        let events_per_second = if elapsed.as_secs_f64() > 0.0 {
+            self.event_to_retile_latency.count as f64 / elapsed.as_secs_f64()
+        } else {
+            0.0
+        };
+
+        Option::from(PerformanceReport {
+            seconds_elapsed: elapsed.as_secs(),
+            events_per_second,
+            event_to_retile_latency: self.event_to_retile_latency.clone(),
+            retile_duration: self.retile_duration.clone(),
+            command_latency: self.command_latency.clone(),
+            event_latency_by_exe: self.event_latency_by_exe.clone(),
+        })
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use sysinfo::Pid;
+use sysinfo::ProcessExt;
+use sysinfo::SystemExt;
+
+use crate::windows_api::WindowsApi;
+
+lazy_static! {
+    /// The command line of every process which has been looked up, by process id
+    static ref COMMAND_LINES: Arc<Mutex<HashMap<u32, String>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    /// Whether every process which has been looked up is elevated, by process id
+    static ref ELEVATED_PROCESSES: Arc<Mutex<HashMap<u32, bool>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+/// The command line that a process was launched with, with arguments separated by spaces, which
+/// is only looked up the first time it is needed since it never changes
+pub fn command_line(process_id: u32) -> Result<String> {
+    if let Some(command_line) = COMMAND_LINES.lock().get(&process_id) {
+        return Ok(command_line.clone());
+    }
+
+    let pid = Pid::try_from(process_id).map_err(|_| anyhow!("there is no process with this id"))?;
+
+    // Only the process being looked up is refreshed, rather than every running process
+    let mut system = sysinfo::System::new();
+    system.refresh_process(pid);
+
+    let command_line = system
+        .process(pid)
+        .map(|process| process.cmd().join(" "))
+        .ok_or_else(|| anyhow!("there is no process with this id"))?;
+
+    COMMAND_LINES
+        .lock()
+        .insert(process_id, command_line.clone());
+
+    Ok(command_line)
+}
+
+/// Whether a process is running with an elevated (administrator) token, which is only looked up
+/// the first time it is needed since it never changes
+pub fn is_elevated(process_id: u32) -> Result<bool> {
+    if let Some(elevated) = ELEVATED_PROCESSES.lock().get(&process_id) {
+        return Ok(*elevated);
+    }
+
+    let elevated = WindowsApi::is_process_elevated(process_id)?;
+    ELEVATED_PROCESSES.lock().insert(process_id, elevated);
+
+    Ok(elevated)
+}
+
+/// Forget the processes which have exited, so that nothing is remembered for a new process which
+/// is given the same id
+pub fn prune() {
+    COMMAND_LINES
+        .lock()
+        .retain(|process_id, _| WindowsApi::process_exists(*process_id));
+
+    ELEVATED_PROCESSES
+        .lock()
+        .retain(|process_id, _| WindowsApi::process_exists(*process_id));
+}
@@ -1,16 +1,20 @@
-use std::fs::File;
-use std::fs::OpenOptions;
+use std::collections::VecDeque;
+use std::ffi::OsStr;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::Write;
 use std::num::NonZeroUsize;
+use std::path::Path;
 use std::str::FromStr;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 
 use color_eyre::eyre::anyhow;
 use color_eyre::Result;
+use crossbeam_channel::Sender;
 use miow::pipe::connect;
 use parking_lot::Mutex;
 use uds_windows::UnixStream;
@@ -20,53 +24,207 @@ use komorebi_core::Axis;
 use komorebi_core::FocusFollowsMouseImplementation;
 use komorebi_core::Layout;
 use komorebi_core::OperationDirection;
-use komorebi_core::Rect;
+use komorebi_core::ProtocolInfo;
+use komorebi_core::SavedLayout;
 use komorebi_core::Sizing;
 use komorebi_core::SocketMessage;
+use komorebi_core::SocketResponse;
 use komorebi_core::StateQuery;
 use komorebi_core::WindowContainerBehaviour;
 
+use crate::cancellation;
+use crate::configuration;
 use crate::current_virtual_desktop;
+use crate::learning;
+use crate::logging;
+use crate::mirror;
 use crate::notify_subscribers;
+use crate::rate_limit;
+use crate::rate_limit::RateLimiter;
+use crate::rate_limit::COALESCED_MESSAGES;
+use crate::rate_limit::MESSAGE_BURST_LIMIT;
+use crate::rate_limit::MESSAGE_RATE_LIMIT;
+use crate::rule_groups;
+use crate::temp_file;
+use crate::websocket;
 use crate::window_manager;
 use crate::window_manager::WindowManager;
 use crate::windows_api::WindowsApi;
 use crate::Notification;
-use crate::NotificationEvent;
+use crate::ALIASES;
+use crate::APP_GROUP_IDENTIFIERS;
 use crate::BORDER_OVERFLOW_IDENTIFIERS;
+use crate::CLASS_BLOCKLIST;
+use crate::COMMAND_LINE_FLOAT_IDENTIFIERS;
+use crate::COMMAND_LINE_WORKSPACE_RULES;
 use crate::CUSTOM_FFM;
+use crate::FLOAT_ELEVATED_WINDOWS;
 use crate::FLOAT_IDENTIFIERS;
+use crate::FLOAT_RULES_ONCE;
+use crate::FOLLOW_IDENTIFIERS;
 use crate::HIDING_BEHAVIOUR;
+use crate::HIDING_BEHAVIOUR_RULES;
 use crate::MANAGE_IDENTIFIERS;
+use crate::MOUSE_FOLLOWS_FOCUS_OFFSET;
+use crate::MOUSE_FOLLOWS_FOCUS_PLACEMENT;
+use crate::NO_BORDER_OVERFLOW_IDENTIFIERS;
+use crate::RETILE_BATCH_SIZE;
+use crate::RETILE_FPS_CAP;
+use crate::SIZE_CONSTRAINTS;
 use crate::SUBSCRIPTION_PIPES;
+use crate::SWALLOW_IDENTIFIERS;
+use crate::THROTTLE_RETILES;
 use crate::TRAY_AND_MULTI_WINDOW_IDENTIFIERS;
 use crate::WORKSPACE_RULES;
+use crate::WORKSPACE_RULES_ONCE;
+use crate::WORKSPACE_RULES_ON_TITLE_CHANGE;
+use crate::WORKSPACE_RULE_BINDINGS;
+
+// The socket that komorebic listens on for responses to WithResponse messages
+const COMMAND_RESPONSE_SOCKET: &str = "komorebic.sock";
 
 #[tracing::instrument]
 pub fn listen_for_commands(wm: Arc<Mutex<WindowManager>>) {
-    let listener = wm
-        .lock()
-        .command_listener
-        .try_clone()
-        .expect("could not clone unix listener");
+    let (listener, priority_listener) = {
+        let wm = wm.lock();
+        (
+            wm.command_listener
+                .try_clone()
+                .expect("could not clone unix listener"),
+            wm.priority_command_listener
+                .try_clone()
+                .expect("could not clone unix listener"),
+        )
+    };
+
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    let (priority_sender, priority_receiver) = crossbeam_channel::unbounded();
+
+    // Messages are queued as soon as they are received, without waiting for the window manager
+    // lock, so that CancelPendingOperations can take effect while earlier messages are still
+    // being processed
+    for (listener, sender) in [(listener, sender), (priority_listener, priority_sender)] {
+        thread::spawn(move || {
+            tracing::info!("listening");
+            let mut limiter = RateLimiter::default();
+            for client in listener.incoming() {
+                match client {
+                    Ok(stream) => match queue_commands(stream, &sender, &mut limiter) {
+                        Ok(()) => {}
+                        Err(error) => tracing::error!("{}", error),
+                    },
+                    Err(error) => {
+                        tracing::error!("{}", error);
+                        break;
+                    }
+                }
+            }
+        });
+    }
 
     thread::spawn(move || {
-        tracing::info!("listening");
-        for client in listener.incoming() {
-            match client {
-                Ok(stream) => match wm.lock().read_commands(stream) {
-                    Ok(()) => {}
-                    Err(error) => tracing::error!("{}", error),
-                },
-                Err(error) => {
-                    tracing::error!("{}", error);
-                    break;
+        let handle = |token, queued, message| {
+            let mut wm = wm.lock();
+            match wm.read_command(token, message) {
+                Ok(()) => {}
+                Err(error) => tracing::error!("{}", error),
+            }
+
+            wm.performance_counters.record_command(queued);
+        };
+
+        let mut queued = VecDeque::new();
+        loop {
+            // Priority messages jump ahead of everything that has already been queued, so that
+            // commands like TogglePause and Stop never wait behind a large batch
+            for (token, queued, message) in priority_receiver.try_iter() {
+                handle(token, queued, message);
+            }
+
+            queued.extend(receiver.try_iter());
+
+            if let Some((token, queued_at, message)) = queued.pop_front() {
+                if queued.front().map_or(false, |(_, _, next)| {
+                    rate_limit::is_redundant(&message, next)
+                }) {
+                    tracing::trace!("coalescing redundant message: {}", message);
+                    COALESCED_MESSAGES.fetch_add(1, Ordering::SeqCst);
+                    continue;
+                }
+
+                handle(token, queued_at, message);
+            } else {
+                crossbeam_channel::select! {
+                    recv(priority_receiver) -> next => match next {
+                        Ok((token, queued_at, message)) => handle(token, queued_at, message),
+                        Err(_) => break,
+                    },
+                    recv(receiver) -> next => match next {
+                        Ok(next) => queued.push_back(next),
+                        Err(_) => break,
+                    },
                 }
             }
         }
     });
 }
 
+fn queue_commands(
+    stream: UnixStream,
+    sender: &Sender<(usize, Instant, SocketMessage)>,
+    limiter: &mut RateLimiter,
+) -> Result<()> {
+    let stream = BufReader::new(stream);
+    for line in stream.lines() {
+        let message = SocketMessage::from_str(&line?)?;
+
+        let cancel = match &message {
+            SocketMessage::CancelPendingOperations => true,
+            SocketMessage::WithResponse(message) | SocketMessage::WithResponseTo(_, message) => {
+                matches!(**message, SocketMessage::CancelPendingOperations)
+            }
+            _ => false,
+        };
+
+        if cancel {
+            cancellation::cancel();
+
+            if let Some(socket) = response_socket(&message) {
+                write_response_to(socket, &serde_json::to_string(&SocketResponse::ok(None))?)?;
+            }
+        } else if !limiter.allow() {
+            tracing::warn!("dropping message over the rate limit: {}", message);
+
+            if let Some(socket) = response_socket(&message) {
+                let response = SocketResponse::error("the rate limit was exceeded".to_string());
+                write_response_to(socket, &serde_json::to_string(&response)?)?;
+            }
+        } else {
+            // Latency is measured from here so that it includes the time spent in the queue
+            sender.send((cancellation::token(), Instant::now(), message))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn add_workspace_rule(
+    identifier: &ApplicationIdentifier,
+    id: String,
+    monitor_idx: usize,
+    workspace_idx: usize,
+) {
+    if matches!(identifier, ApplicationIdentifier::CommandLine) {
+        let mut rules = COMMAND_LINE_WORKSPACE_RULES.lock();
+        rules.retain(|(existing, _)| *existing != id);
+        rules.push((id, (monitor_idx, workspace_idx)));
+    } else {
+        WORKSPACE_RULES
+            .lock()
+            .insert(id, (monitor_idx, workspace_idx));
+    }
+}
+
 impl WindowManager {
     #[tracing::instrument(skip(self))]
     pub fn process_command(&mut self, message: SocketMessage) -> Result<()> {
@@ -82,8 +240,17 @@ impl WindowManager {
             }
         }
 
+        // Any other command which could change the state ends a peek first, so that it always
+        // operates on the real state
+        if !matches!(message, SocketMessage::PeekWorkspace(_)) && !is_read_only(&message) {
+            self.end_peek()?;
+        }
+
         match message {
+            SocketMessage::CancelPendingOperations => cancellation::cancel(),
             SocketMessage::Promote => self.promote_container_to_front()?,
+            SocketMessage::FocusPrimaryZone => self.focus_primary_zone()?,
+            SocketMessage::BalanceWorkspace => self.balance_workspace()?,
             SocketMessage::FocusWindow(direction) => {
                 self.focus_container_in_direction(direction)?;
             }
@@ -101,35 +268,119 @@ impl WindowManager {
             SocketMessage::CycleStack(direction) => {
                 self.cycle_container_window_in_direction(direction)?;
             }
+            SocketMessage::NestContainer(axis) => self.nest_container(axis)?,
+            SocketMessage::UnnestContainer => self.unnest_container()?,
+            SocketMessage::DissolveContainer => self.dissolve_container()?,
+            SocketMessage::NextWindowDirection(direction) => {
+                self.next_window_direction = Option::from(direction);
+            }
             SocketMessage::ToggleFloat => self.toggle_float()?,
+            SocketMessage::TileFloat(direction) => self.tile_float(direction)?,
             SocketMessage::ToggleMonocle => self.toggle_monocle()?,
+            SocketMessage::ToggleMonoclePip => self.toggle_monocle_pip()?,
+            SocketMessage::RaiseWindow => self.raise_window()?,
+            SocketMessage::LowerWindow => self.lower_window()?,
+            SocketMessage::ToggleWindowOnTop => self.toggle_window_on_top()?,
+            SocketMessage::ToggleFocusMode => self.toggle_focus_mode()?,
             SocketMessage::ToggleMaximize => self.toggle_maximize()?,
+            SocketMessage::ToggleContainerMaximize => self.toggle_container_maximize()?,
             SocketMessage::ContainerPadding(monitor_idx, workspace_idx, size) => {
                 self.set_container_padding(monitor_idx, workspace_idx, size)?;
             }
+            SocketMessage::ContainerPaddingScale(monitor_idx, workspace_idx, scale) => {
+                self.set_container_padding_scale(monitor_idx, workspace_idx, scale)?;
+            }
+            SocketMessage::WorkspaceResizeDelta(monitor_idx, workspace_idx, delta) => {
+                self.set_workspace_resize_delta(monitor_idx, workspace_idx, delta)?;
+            }
             SocketMessage::WorkspacePadding(monitor_idx, workspace_idx, size) => {
                 self.set_workspace_padding(monitor_idx, workspace_idx, size)?;
             }
-            SocketMessage::WorkspaceRule(_, id, monitor_idx, workspace_idx) => {
+            SocketMessage::InnerGap(monitor_idx, workspace_idx, size) => {
+                self.set_inner_gap(monitor_idx, workspace_idx, size)?;
+            }
+            SocketMessage::OuterGap(monitor_idx, workspace_idx, size) => {
+                self.set_outer_gap(monitor_idx, workspace_idx, size)?;
+            }
+            SocketMessage::WorkspaceRule(identifier, id, monitor_idx, workspace_idx) => {
+                add_workspace_rule(&identifier, id, monitor_idx, workspace_idx);
+                self.enforce_workspace_rules()?;
+            }
+            SocketMessage::ApplyWorkspaceRule(identifier, id, monitor_idx, workspace_idx) => {
+                add_workspace_rule(&identifier, id, monitor_idx, workspace_idx);
+                self.apply_workspace_rules()?;
+            }
+            SocketMessage::WorkspaceRuleOnce(identifier, id, monitor_idx, workspace_idx) => {
+                WORKSPACE_RULES_ONCE
+                    .lock()
+                    .push((identifier, id, (monitor_idx, workspace_idx)));
+            }
+            SocketMessage::BoundWorkspaceRule(identifier, id, target) => {
                 {
-                    let mut workspace_rules = WORKSPACE_RULES.lock();
-                    workspace_rules.insert(id, (monitor_idx, workspace_idx));
+                    let mut bindings = WORKSPACE_RULE_BINDINGS.lock();
+                    bindings.retain(|(_, existing, _)| *existing != id);
+                    bindings.push((identifier, id, target));
                 }
 
+                self.resolve_workspace_rule_bindings();
                 self.enforce_workspace_rules()?;
             }
+            SocketMessage::ApplyWorkspaceRules => self.apply_workspace_rules()?,
+            SocketMessage::WorkspaceRulesOnTitleChange(enable) => {
+                WORKSPACE_RULES_ON_TITLE_CHANGE.store(enable, Ordering::SeqCst);
+            }
             SocketMessage::ManageRule(_, id) => {
                 let mut manage_identifiers = MANAGE_IDENTIFIERS.lock();
                 if !manage_identifiers.contains(&id) {
                     manage_identifiers.push(id);
                 }
             }
+            SocketMessage::FollowRule(_, id) => {
+                let mut follow_identifiers = FOLLOW_IDENTIFIERS.lock();
+                if !follow_identifiers.contains(&id) {
+                    follow_identifiers.push(id);
+                }
+            }
+            SocketMessage::SwallowRule(_, id) => {
+                let mut swallow_identifiers = SWALLOW_IDENTIFIERS.lock();
+                if !swallow_identifiers.contains(&id) {
+                    swallow_identifiers.push(id);
+                }
+            }
+            SocketMessage::AppGroupRule(_, id) => {
+                {
+                    let mut app_group_identifiers = APP_GROUP_IDENTIFIERS.lock();
+                    if !app_group_identifiers.contains(&id) {
+                        app_group_identifiers.push(id);
+                    }
+                }
+
+                for monitor in self.monitors_mut() {
+                    let focused_workspace_idx = monitor.focused_workspace_idx();
+                    for (i, workspace) in monitor.workspaces_mut().iter_mut().enumerate() {
+                        workspace.consolidate_app_groups(i == focused_workspace_idx)?;
+                    }
+                }
+
+                self.update_focused_workspace(false)?;
+            }
+            SocketMessage::FloatRuleOnce(identifier, id) => {
+                FLOAT_RULES_ONCE.lock().push((identifier, id));
+            }
             SocketMessage::FloatRule(identifier, id) => {
-                let mut float_identifiers = FLOAT_IDENTIFIERS.lock();
+                let mut float_identifiers =
+                    if matches!(identifier, ApplicationIdentifier::CommandLine) {
+                        COMMAND_LINE_FLOAT_IDENTIFIERS.lock()
+                    } else {
+                        FLOAT_IDENTIFIERS.lock()
+                    };
+
                 if !float_identifiers.contains(&id) {
                     float_identifiers.push(id.clone());
                 }
 
+                drop(float_identifiers);
+
                 let invisible_borders = self.invisible_borders;
                 let offset = self.work_area_offset;
 
@@ -158,6 +409,11 @@ impl WindowManager {
                                         hwnds_to_purge.push((i, window.hwnd));
                                     }
                                 }
+                                ApplicationIdentifier::CommandLine => {
+                                    if window.command_line()?.contains(&id) {
+                                        hwnds_to_purge.push((i, window.hwnd));
+                                    }
+                                }
                             }
                         }
                     }
@@ -183,9 +439,18 @@ impl WindowManager {
             SocketMessage::AdjustWorkspacePadding(sizing, adjustment) => {
                 self.adjust_workspace_padding(sizing, adjustment)?;
             }
+            SocketMessage::AdjustInnerGap(sizing, adjustment) => {
+                self.adjust_inner_gap(sizing, adjustment)?;
+            }
+            SocketMessage::AdjustOuterGap(sizing, adjustment) => {
+                self.adjust_outer_gap(sizing, adjustment)?;
+            }
             SocketMessage::MoveContainerToWorkspaceNumber(workspace_idx) => {
                 self.move_container_to_workspace(workspace_idx, true)?;
             }
+            SocketMessage::MoveContainerToWorkspaceNumberAndFollow(workspace_idx, follow) => {
+                self.move_container_to_workspace(workspace_idx, follow)?;
+            }
             SocketMessage::MoveContainerToMonitorNumber(monitor_idx) => {
                 self.move_container_to_monitor(monitor_idx, true)?;
             }
@@ -198,6 +463,9 @@ impl WindowManager {
             SocketMessage::MoveWorkspaceToMonitorNumber(monitor_idx) => {
                 self.move_workspace_to_monitor(monitor_idx)?;
             }
+            SocketMessage::MoveWorkspaceToIndex(workspace_idx) => {
+                self.move_workspace_to_index(workspace_idx)?;
+            }
             SocketMessage::TogglePause => {
                 if self.is_paused {
                     tracing::info!("resuming");
@@ -226,18 +494,76 @@ impl WindowManager {
                 self.update_focused_workspace(self.mouse_follows_focus)?;
             }
             SocketMessage::Retile => self.retile_all(false)?,
+            SocketMessage::Reconcile => self.reconcile()?,
             SocketMessage::FlipLayout(layout_flip) => self.flip_layout(layout_flip)?,
+            SocketMessage::ToggleSplitOrientation => self.toggle_split_orientation()?,
             SocketMessage::ChangeLayout(layout) => self.change_workspace_layout_default(layout)?,
             SocketMessage::ChangeLayoutCustom(path) => self.change_workspace_custom_layout(path)?,
+            SocketMessage::CycleLayout(direction) => self.cycle_workspace_layout(direction)?,
             SocketMessage::WorkspaceLayoutCustom(monitor_idx, workspace_idx, path) => {
                 self.set_workspace_layout_custom(monitor_idx, workspace_idx, path)?;
             }
+            SocketMessage::WorkspaceWallpaper(monitor_idx, workspace_idx, path) => {
+                self.set_workspace_wallpaper(monitor_idx, workspace_idx, path)?;
+            }
+            SocketMessage::SetContainerResizeDimensions(
+                monitor_idx,
+                workspace_idx,
+                container_idx,
+                rect,
+            ) => {
+                self.set_container_resize_dimensions(
+                    monitor_idx,
+                    workspace_idx,
+                    container_idx,
+                    rect,
+                )?;
+            }
             SocketMessage::WorkspaceTiling(monitor_idx, workspace_idx, tile) => {
                 self.set_workspace_tiling(monitor_idx, workspace_idx, tile)?;
             }
+            SocketMessage::WorkspaceTaskbarHidden(monitor_idx, workspace_idx, hidden) => {
+                self.set_workspace_taskbar_hidden(monitor_idx, workspace_idx, hidden)?;
+            }
+            SocketMessage::WorkspaceFocusFollowsMouse(monitor_idx, workspace_idx, enable) => {
+                self.set_workspace_focus_follows_mouse(monitor_idx, workspace_idx, enable)?;
+            }
+            SocketMessage::WorkspaceLogicalPadding(monitor_idx, workspace_idx, enable) => {
+                self.set_workspace_logical_padding(monitor_idx, workspace_idx, enable)?;
+            }
             SocketMessage::WorkspaceLayout(monitor_idx, workspace_idx, layout) => {
                 self.set_workspace_layout_default(monitor_idx, workspace_idx, layout)?;
             }
+            SocketMessage::WorkspaceLayoutRotation(monitor_idx, workspace_idx, layouts) => {
+                self.set_workspace_layout_rotation(monitor_idx, workspace_idx, layouts)?;
+            }
+            SocketMessage::WorkspaceLayoutCycleInterval(
+                monitor_idx,
+                workspace_idx,
+                interval,
+                layouts,
+            ) => {
+                self.set_workspace_layout_cycle_interval(
+                    monitor_idx,
+                    workspace_idx,
+                    interval,
+                    layouts,
+                )?;
+            }
+            SocketMessage::WorkspaceMultiStack(monitor_idx, workspace_idx, regions) => {
+                self.set_workspace_multi_stack(monitor_idx, workspace_idx, regions)?;
+            }
+            SocketMessage::CloneWorkspaceLayoutToMonitor(
+                monitor_idx,
+                workspace_idx,
+                target_monitor_idx,
+            ) => {
+                self.clone_workspace_layout_to_monitor(
+                    monitor_idx,
+                    workspace_idx,
+                    target_monitor_idx,
+                )?;
+            }
             SocketMessage::CycleFocusWorkspace(direction) => {
                 // This is to ensure that even on an empty workspace on a secondary monitor, the
                 // secondary monitor where the cursor is focused will be used as the target for
@@ -274,10 +600,24 @@ impl WindowManager {
                 self.focus_monitor(monitor_idx)?;
                 self.focus_workspace(workspace_idx)?;
             }
+            SocketMessage::PeekWorkspace(workspace_idx) => self.peek_workspace(workspace_idx)?,
+            // The peek has already been ended before matching on the message
+            SocketMessage::EndPeek => {}
             SocketMessage::FocusMonitorWorkspaceNumber(monitor_idx, workspace_idx) => {
                 self.focus_monitor(monitor_idx)?;
                 self.focus_workspace(workspace_idx)?;
             }
+            SocketMessage::FocusWorkspaceBack => self.focus_workspace_back()?,
+            SocketMessage::FocusWorkspaceForward => self.focus_workspace_forward()?,
+            SocketMessage::FocusContainerById(id) => {
+                self.focus_container_by_id(&id)?;
+            }
+            SocketMessage::FocusWindowByHwnd(hwnd) => self.focus_window_by_hwnd(hwnd)?,
+            SocketMessage::ShowContainerHints => self.show_container_hints()?,
+            SocketMessage::FocusHint(label) => self.focus_hint(&label)?,
+            SocketMessage::MoveContainerById(id, monitor_idx, workspace_idx) => {
+                self.move_container_by_id(&id, monitor_idx, workspace_idx)?;
+            }
             SocketMessage::Stop => {
                 tracing::info!(
                     "received stop command, restoring all hidden windows and terminating process"
@@ -290,9 +630,35 @@ impl WindowManager {
 
                 std::process::exit(0)
             }
+            SocketMessage::Restart => {
+                tracing::info!("received restart command, handing over to a new process");
+                self.restart()?;
+            }
             SocketMessage::EnsureWorkspaces(monitor_idx, workspace_count) => {
                 self.ensure_workspaces_for_monitor(monitor_idx, workspace_count)?;
             }
+            SocketMessage::MonitorGroup(primary_idx, secondary_idx, bezel_gap) => {
+                self.set_monitor_group(primary_idx, secondary_idx, bezel_gap)?;
+            }
+            SocketMessage::RemoveMonitorGroup(monitor_idx) => {
+                self.remove_monitor_group(monitor_idx)?;
+            }
+            SocketMessage::ToggleWorkspaceSync => self.toggle_workspace_sync(),
+            SocketMessage::AddExclusionZone(monitor_idx, zone) => {
+                self.add_exclusion_zone(monitor_idx, zone)?;
+            }
+            SocketMessage::ClearExclusionZones(monitor_idx) => {
+                self.clear_exclusion_zones(monitor_idx)?;
+            }
+            SocketMessage::MonitorWorkAreaSize(monitor_idx, work_area) => {
+                self.set_work_area_override(monitor_idx, Option::from(work_area))?;
+            }
+            SocketMessage::ClearMonitorWorkAreaSize(monitor_idx) => {
+                self.set_work_area_override(monitor_idx, None)?;
+            }
+            SocketMessage::MonitorWorkspaceSync(monitor_idx, enable) => {
+                self.set_monitor_workspace_sync(monitor_idx, enable)?;
+            }
             SocketMessage::NewWorkspace => {
                 self.new_workspace()?;
             }
@@ -306,42 +672,96 @@ impl WindowManager {
                     Err(error) => error.to_string(),
                 };
 
-                let mut socket =
-                    dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
-                socket.push("komorebic.sock");
-                let socket = socket.as_path();
-
-                let mut stream = UnixStream::connect(&socket)?;
-                stream.write_all(state.as_bytes())?;
+                write_response(&state)?;
             }
             SocketMessage::Query(query) => {
-                let response = match query {
-                    StateQuery::FocusedMonitorIndex => self.focused_monitor_idx(),
-                    StateQuery::FocusedWorkspaceIndex => self
-                        .focused_monitor()
-                        .ok_or_else(|| anyhow!("there is no monitor"))?
-                        .focused_workspace_idx(),
-                    StateQuery::FocusedContainerIndex => {
-                        self.focused_workspace()?.focused_container_idx()
-                    }
-                    StateQuery::FocusedWindowIndex => {
-                        self.focused_container()?.focused_window_idx()
-                    }
-                }
-                .to_string();
+                let response = self.state_query(&query)?;
+                write_response(&response)?;
+            }
+            SocketMessage::QueryWindowList => {
+                let window_list = match serde_json::to_string_pretty(&self.window_list()) {
+                    Ok(window_list) => window_list,
+                    Err(error) => error.to_string(),
+                };
+
+                write_response(&window_list)?;
+            }
+            SocketMessage::QueryMruWindowList => {
+                let window_list = match serde_json::to_string_pretty(&self.mru_window_list()) {
+                    Ok(window_list) => window_list,
+                    Err(error) => error.to_string(),
+                };
+
+                write_response(&window_list)?;
+            }
+            SocketMessage::LearningMode(seconds) => {
+                tracing::info!("starting learning mode for {} seconds", seconds);
+                learning::start(Duration::from_secs(seconds));
+            }
+            SocketMessage::QueryRuleSuggestions => {
+                let suggestions = match serde_json::to_string_pretty(&learning::suggestions()) {
+                    Ok(suggestions) => suggestions,
+                    Err(error) => error.to_string(),
+                };
+
+                write_response(&suggestions)?;
+            }
+            SocketMessage::QueryConfigurationDiff => {
+                let diff = match configuration::diff(self) {
+                    Ok(diff) => serde_json::to_string_pretty(&diff)?,
+                    Err(error) => error.to_string(),
+                };
 
-                let mut socket =
-                    dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
-                socket.push("komorebic.sock");
-                let socket = socket.as_path();
+                write_response(&diff)?;
+            }
+            SocketMessage::QueryHealth => {
+                let health = match serde_json::to_string_pretty(&self.health()) {
+                    Ok(health) => health,
+                    Err(error) => error.to_string(),
+                };
 
-                let mut stream = UnixStream::connect(&socket)?;
-                stream.write_all(response.as_bytes())?;
+                write_response(&health)?;
+            }
+            SocketMessage::ProtocolVersion => {
+                let protocol = match serde_json::to_string_pretty(&ProtocolInfo::default()) {
+                    Ok(protocol) => protocol,
+                    Err(error) => error.to_string(),
+                };
+
+                write_response(&protocol)?;
+            }
+            SocketMessage::WithResponse(_) | SocketMessage::WithResponseTo(..) => {
+                return Err(anyhow!("responses cannot be requested for nested messages"));
+            }
+            SocketMessage::DefineAlias(name, messages) => {
+                if messages.iter().any(|message| {
+                    matches!(
+                        message,
+                        SocketMessage::DefineAlias(..)
+                            | SocketMessage::InvokeAlias(_)
+                            | SocketMessage::WithResponse(_)
+                            | SocketMessage::WithResponseTo(..)
+                    )
+                }) {
+                    return Err(anyhow!(
+                        "aliases cannot define or invoke other aliases or request responses"
+                    ));
+                }
+
+                tracing::info!("defining alias: {}", name);
+                ALIASES.lock().insert(name, messages);
+            }
+            SocketMessage::InvokeAlias(name) => {
+                self.invoke_alias(&name)?;
             }
             SocketMessage::ResizeWindowEdge(direction, sizing) => {
-                self.resize_window(direction, sizing, self.resize_delta, true)?;
+                let resize_delta = self.focused_resize_delta()?;
+                self.resize_window(direction, sizing, resize_delta, true)?;
             }
+            SocketMessage::CycleSplitRatio(direction) => self.cycle_split_ratio(direction)?,
             SocketMessage::ResizeWindowAxis(axis, sizing) => {
+                let resize_delta = self.focused_resize_delta()?;
+
                 // If the user has a custom layout, allow for the resizing of the primary column
                 // with this signal
                 if let Layout::Custom(ref mut custom) = self.focused_workspace_mut()?.layout_mut() {
@@ -363,13 +783,13 @@ impl WindowManager {
                             self.resize_window(
                                 OperationDirection::Left,
                                 sizing,
-                                self.resize_delta,
+                                resize_delta,
                                 false,
                             )?;
                             self.resize_window(
                                 OperationDirection::Right,
                                 sizing,
-                                self.resize_delta,
+                                resize_delta,
                                 false,
                             )?;
                         }
@@ -377,13 +797,13 @@ impl WindowManager {
                             self.resize_window(
                                 OperationDirection::Up,
                                 sizing,
-                                self.resize_delta,
+                                resize_delta,
                                 false,
                             )?;
                             self.resize_window(
                                 OperationDirection::Down,
                                 sizing,
-                                self.resize_delta,
+                                resize_delta,
                                 false,
                             )?;
                         }
@@ -391,25 +811,25 @@ impl WindowManager {
                             self.resize_window(
                                 OperationDirection::Left,
                                 sizing,
-                                self.resize_delta,
+                                resize_delta,
                                 false,
                             )?;
                             self.resize_window(
                                 OperationDirection::Right,
                                 sizing,
-                                self.resize_delta,
+                                resize_delta,
                                 false,
                             )?;
                             self.resize_window(
                                 OperationDirection::Up,
                                 sizing,
-                                self.resize_delta,
+                                resize_delta,
                                 false,
                             )?;
                             self.resize_window(
                                 OperationDirection::Down,
                                 sizing,
-                                self.resize_delta,
+                                resize_delta,
                                 false,
                             )?;
                         }
@@ -456,6 +876,8 @@ impl WindowManager {
                         }
                     }
                 }
+
+                self.sync_workspace_focus_follows_mouse()?;
             }
             SocketMessage::ToggleFocusFollowsMouse(mut implementation) => {
                 if !CUSTOM_FFM.load(Ordering::SeqCst) {
@@ -510,6 +932,8 @@ impl WindowManager {
                         }
                     }
                 }
+
+                self.sync_workspace_focus_follows_mouse()?;
             }
             SocketMessage::ReloadConfiguration => {
                 Self::reload_configuration();
@@ -523,6 +947,35 @@ impl WindowManager {
                     identifiers.push(id);
                 }
             }
+            SocketMessage::AddClassBlocklistPattern(pattern) => {
+                let mut blocklist = CLASS_BLOCKLIST.lock();
+                if !blocklist.contains(&pattern) {
+                    blocklist.push(pattern);
+                }
+            }
+            SocketMessage::RemoveClassBlocklistPattern(pattern) => {
+                CLASS_BLOCKLIST.lock().retain(|p| *p != pattern);
+            }
+            SocketMessage::IdentifyNoBorderOverflow(_, id) => {
+                let mut identifiers = NO_BORDER_OVERFLOW_IDENTIFIERS.lock();
+                if !identifiers.contains(&id) {
+                    identifiers.push(id);
+                }
+            }
+            SocketMessage::SizeConstraintRule(_, id, constraint, value) => {
+                {
+                    let mut size_constraints = SIZE_CONSTRAINTS.lock();
+                    size_constraints
+                        .entry(id)
+                        .or_default()
+                        .set(constraint, value);
+                }
+
+                self.retile_all(true)?;
+            }
+            SocketMessage::AddRuleGroup(name) => rule_groups::add(name),
+            SocketMessage::EndRuleGroup => rule_groups::end(),
+            SocketMessage::ToggleRuleGroup(name) => self.toggle_rule_group(&name)?,
             SocketMessage::IdentifyTrayApplication(_, id) => {
                 let mut identifiers = TRAY_AND_MULTI_WINDOW_IDENTIFIERS.lock();
                 if !identifiers.contains(&id) {
@@ -545,60 +998,38 @@ impl WindowManager {
             }
             SocketMessage::QuickSave => {
                 let workspace = self.focused_workspace()?;
-                let resize = workspace.resize_dimensions();
+                let saved = SavedLayout::new(workspace.resize_dimensions().clone());
 
-                let mut quicksave_json = std::env::temp_dir();
-                quicksave_json.push("komorebi.quicksave.json");
-
-                let file = OpenOptions::new()
-                    .write(true)
-                    .truncate(true)
-                    .create(true)
-                    .open(quicksave_json)?;
-
-                serde_json::to_writer_pretty(&file, &resize)?;
+                saved.save(&temp_file("komorebi.quicksave.json"))?;
             }
             SocketMessage::QuickLoad => {
-                let workspace = self.focused_workspace_mut()?;
-
-                let mut quicksave_json = std::env::temp_dir();
-                quicksave_json.push("komorebi.quicksave.json");
-
-                let file = File::open(&quicksave_json).map_err(|_| {
-                    anyhow!(
-                        "no quicksave found at {}",
-                        quicksave_json.display().to_string()
-                    )
-                })?;
-
-                let resize: Vec<Option<Rect>> = serde_json::from_reader(file)?;
+                let saved = SavedLayout::load(&temp_file("komorebi.quicksave.json"))?;
 
-                workspace.set_resize_dimensions(resize);
+                let workspace = self.focused_workspace_mut()?;
+                workspace.set_resize_dimensions(saved.resize_dimensions);
                 self.update_focused_workspace(false)?;
             }
             SocketMessage::Save(path) => {
-                let workspace = self.focused_workspace_mut()?;
-                let resize = workspace.resize_dimensions();
-
-                let file = OpenOptions::new()
-                    .write(true)
-                    .truncate(true)
-                    .create(true)
-                    .open(path)?;
+                let workspace = self.focused_workspace()?;
+                let saved = SavedLayout::new(workspace.resize_dimensions().clone());
 
-                serde_json::to_writer_pretty(&file, &resize)?;
+                saved.save(&path)?;
             }
             SocketMessage::Load(path) => {
-                let workspace = self.focused_workspace_mut()?;
-
-                let file = File::open(&path)
-                    .map_err(|_| anyhow!("no file found at {}", path.display().to_string()))?;
-
-                let resize: Vec<Option<Rect>> = serde_json::from_reader(file)?;
+                let saved = SavedLayout::load(&path)?;
 
-                workspace.set_resize_dimensions(resize);
+                let workspace = self.focused_workspace_mut()?;
+                workspace.set_resize_dimensions(saved.resize_dimensions);
                 self.update_focused_workspace(false)?;
             }
+            SocketMessage::SaveWorkspaceSnapshot(path) => self.save_workspace_snapshot(&path)?,
+            SocketMessage::GenerateCustomLayoutFromWorkspace(path) => {
+                self.generate_custom_layout_from_workspace(&path)?;
+            }
+            SocketMessage::LoadWorkspaceSnapshot(path) => self.load_workspace_snapshot(&path)?,
+            SocketMessage::SpawnIntoWorkspace(command, monitor_idx, workspace_idx) => {
+                self.spawn_into_workspace(&command, monitor_idx, workspace_idx)?;
+            }
             SocketMessage::AddSubscriber(subscriber) => {
                 let mut pipes = SUBSCRIPTION_PIPES.lock();
                 let pipe_path = format!(r"\\.\pipe\{}", subscriber);
@@ -612,15 +1043,60 @@ impl WindowManager {
                 let mut pipes = SUBSCRIPTION_PIPES.lock();
                 pipes.remove(&subscriber);
             }
+            SocketMessage::WebSocketServer(port, secret) => websocket::start(port, secret)?,
+            SocketMessage::WebSocketAllowOrigin(origin) => websocket::allow_origin(origin),
+            SocketMessage::MirrorListen(port, secret) => mirror::listen(port, secret)?,
+            SocketMessage::MirrorConnect(address, secret) => mirror::connect(address, secret)?,
+            SocketMessage::PeerMessage(message) => mirror::send(*message)?,
+            SocketMessage::QueryPeerState => {
+                let state = match serde_json::to_string_pretty(&mirror::peer_state()) {
+                    Ok(state) => state,
+                    Err(error) => error.to_string(),
+                };
+
+                write_response(&state)?;
+            }
             SocketMessage::MouseFollowsFocus(enable) => {
                 self.mouse_follows_focus = enable;
             }
             SocketMessage::ToggleMouseFollowsFocus => {
                 self.mouse_follows_focus = !self.mouse_follows_focus;
             }
+            SocketMessage::MouseFollowsFocusPlacement(placement, offset) => {
+                *MOUSE_FOLLOWS_FOCUS_PLACEMENT.lock() = placement;
+                MOUSE_FOLLOWS_FOCUS_OFFSET.store(offset, Ordering::SeqCst);
+            }
+            SocketMessage::StackIndicator(enable) => {
+                self.stack_indicator = enable;
+            }
+            SocketMessage::FloatElevatedWindows(enable) => {
+                FLOAT_ELEVATED_WINDOWS.store(enable, Ordering::SeqCst);
+            }
             SocketMessage::ResizeDelta(delta) => {
                 self.resize_delta = delta;
             }
+            SocketMessage::FocusModeWidth(percentage) => {
+                if percentage == 0 || percentage > 100 {
+                    return Err(anyhow!(
+                        "the focus mode width must be a percentage between 1 and 100"
+                    ));
+                }
+
+                self.focus_mode_width = percentage;
+            }
+            SocketMessage::ThrottleRetiles(enable) => {
+                THROTTLE_RETILES.store(enable, Ordering::SeqCst);
+            }
+            SocketMessage::RetileFpsCap(fps) => {
+                RETILE_FPS_CAP.store(fps, Ordering::SeqCst);
+            }
+            SocketMessage::RetileBatchSize(windows) => {
+                RETILE_BATCH_SIZE.store(windows, Ordering::SeqCst);
+            }
+            SocketMessage::MessageRateLimit(per_second, burst) => {
+                MESSAGE_RATE_LIMIT.store(per_second, Ordering::SeqCst);
+                MESSAGE_BURST_LIMIT.store(burst, Ordering::SeqCst);
+            }
             SocketMessage::ToggleWindowContainerBehaviour => {
                 match self.window_container_behaviour {
                     WindowContainerBehaviour::Create => {
@@ -635,37 +1111,247 @@ impl WindowManager {
                 let mut hiding_behaviour = HIDING_BEHAVIOUR.lock();
                 *hiding_behaviour = behaviour;
             }
+            SocketMessage::OnEmptyWorkspace(behaviour) => {
+                self.empty_workspace_behaviour = behaviour;
+            }
+            SocketMessage::HidingBehaviourRule(_, id, behaviour) => {
+                let mut hiding_behaviour_rules = HIDING_BEHAVIOUR_RULES.lock();
+                hiding_behaviour_rules.insert(id, behaviour);
+            }
+            SocketMessage::SetLogLevel(directives) => {
+                logging::set_log_level(&directives)?;
+                tracing::info!("log level set to: {}", directives);
+            }
+            SocketMessage::PerformanceCounters(enable) => {
+                self.performance_counters.enabled = enable;
+            }
+            SocketMessage::ResetPerformanceCounters => {
+                self.performance_counters.reset();
+            }
         };
 
         tracing::info!("processed");
         Ok(())
     }
 
-    #[tracing::instrument(skip(self, stream))]
-    pub fn read_commands(&mut self, stream: UnixStream) -> Result<()> {
-        let stream = BufReader::new(stream);
-        for line in stream.lines() {
-            let message = SocketMessage::from_str(&line?)?;
+    pub fn state_query(&self, query: &StateQuery) -> Result<String> {
+        Ok(match query {
+            StateQuery::FocusedMonitorIndex => self.focused_monitor_idx().to_string(),
+            StateQuery::FocusedWorkspaceIndex => self
+                .focused_monitor()
+                .ok_or_else(|| anyhow!("there is no monitor"))?
+                .focused_workspace_idx()
+                .to_string(),
+            StateQuery::FocusedContainerIndex => self
+                .focused_workspace()?
+                .focused_container_idx()
+                .to_string(),
+            StateQuery::FocusedWindowIndex => {
+                self.focused_container()?.focused_window_idx().to_string()
+            }
+            StateQuery::FocusedContainerKind => self.focused_container_kind()?,
+            StateQuery::FocusedWorkspaceHasMaximizedWindow => {
+                let workspace = self.focused_workspace()?;
+                (workspace.has_monocle_container() || workspace.has_maximized_window()).to_string()
+            }
+            StateQuery::Version => env!("CARGO_PKG_VERSION").to_string(),
+        })
+    }
 
-            if self.is_paused {
-                return match message {
-                    SocketMessage::TogglePause | SocketMessage::State | SocketMessage::Stop => {
-                        Ok(self.process_command(message)?)
-                    }
-                    _ => {
-                        tracing::trace!("ignoring while paused");
-                        Ok(())
-                    }
-                };
+    fn response_data(&self, message: &SocketMessage) -> Result<Option<serde_json::Value>> {
+        Ok(match message {
+            SocketMessage::State => {
+                Option::from(serde_json::to_value(window_manager::State::from(self))?)
+            }
+            SocketMessage::Query(query) => {
+                Option::from(serde_json::Value::from(self.state_query(query)?))
+            }
+            SocketMessage::QueryHealth => Option::from(serde_json::to_value(self.health())?),
+            SocketMessage::QueryWindowList => {
+                Option::from(serde_json::to_value(self.window_list())?)
+            }
+            SocketMessage::QueryMruWindowList => {
+                Option::from(serde_json::to_value(self.mru_window_list())?)
+            }
+            SocketMessage::QueryRuleSuggestions => {
+                Option::from(serde_json::to_value(learning::suggestions())?)
+            }
+            SocketMessage::QueryConfigurationDiff => Option::from(configuration::diff(self)?),
+            SocketMessage::QueryPeerState => {
+                Option::from(serde_json::to_value(mirror::peer_state())?)
+            }
+            SocketMessage::ProtocolVersion => {
+                Option::from(serde_json::to_value(ProtocolInfo::default())?)
+            }
+            _ => None,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn read_command(&mut self, token: usize, message: SocketMessage) -> Result<()> {
+        if cancellation::is_cancelled(token) {
+            tracing::info!("discarding cancelled message: {}", message);
+
+            // Clients are still waiting for a response to these
+            if let Some(socket) = response_socket(&message) {
+                let response = SocketResponse::error("the operation was cancelled".to_string());
+                write_response_to(socket, &serde_json::to_string(&response)?)?;
+            }
+
+            return Ok(());
+        }
+
+        let (socket, message) = match message {
+            SocketMessage::WithResponse(message) => (COMMAND_RESPONSE_SOCKET.to_string(), *message),
+            SocketMessage::WithResponseTo(socket, message) => (socket, *message),
+            message => return self.handle_command(message),
+        };
+
+        // The process will have exited by the time we would usually respond
+        if matches!(message, SocketMessage::Stop | SocketMessage::Restart) {
+            if let Ok(response) = serde_json::to_string(&SocketResponse::ok(None)) {
+                if let Err(error) = write_response_to(&socket, &response) {
+                    tracing::error!("{}", error);
+                }
             }
+        }
 
-            self.process_command(message.clone())?;
-            notify_subscribers(&serde_json::to_string(&Notification {
-                event: NotificationEvent::Socket(message.clone()),
-                state: (&*self).into(),
-            })?)?;
+        let response = self.process_command_with_response(message);
+        write_response_to(&socket, &serde_json::to_string(&response)?)
+    }
+
+    // The window manager lock is held for the duration of the alias, so no other commands or
+    // events can be processed in between the messages that make it up
+    #[tracing::instrument(skip(self))]
+    fn invoke_alias(&mut self, name: &str) -> Result<()> {
+        let messages = ALIASES
+            .lock()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("there is no alias with this name"))?;
+
+        tracing::info!("invoking alias");
+
+        let token = cancellation::token();
+        for message in messages {
+            if cancellation::is_cancelled(token) {
+                tracing::info!("alias was cancelled");
+                break;
+            }
+
+            self.process_command(message)?;
         }
 
         Ok(())
     }
+
+    pub fn handle_command(&mut self, message: SocketMessage) -> Result<()> {
+        if self.is_paused && !is_allowed_while_paused(&message) {
+            tracing::trace!("ignoring while paused");
+            return Ok(());
+        }
+
+        // Rules are tagged with the rule group that is open, and held back while it is disabled
+        if rule_groups::is_rule(&message) && !rule_groups::record(&message) {
+            tracing::info!("holding back rule from a disabled rule group");
+            return Ok(());
+        }
+
+        let maximized_states = self.maximized_states();
+        self.process_command(message.clone())?;
+        self.record_workspace_history();
+        notify_subscribers(&serde_json::to_string(&Notification {
+            event: self.command_notification_event(message),
+            state: (&*self).into(),
+            stack: self.focused_stack_indicator(),
+        })?)?;
+        self.notify_maximized_state_changes(&maximized_states)?;
+
+        Ok(())
+    }
+
+    pub fn process_command_with_response(&mut self, message: SocketMessage) -> SocketResponse {
+        if self.is_paused && !is_allowed_while_paused(&message) {
+            return SocketResponse::error("ignoring while paused".to_string());
+        }
+
+        match self.response_data(&message) {
+            Ok(Some(data)) => return SocketResponse::ok(Option::from(data)),
+            Ok(None) => {}
+            Err(error) => return SocketResponse::error(error.to_string()),
+        }
+
+        match self.handle_command(message) {
+            Ok(()) => SocketResponse::ok(None),
+            Err(error) => SocketResponse::error(error.to_string()),
+        }
+    }
+}
+
+const fn is_allowed_while_paused(message: &SocketMessage) -> bool {
+    matches!(
+        message,
+        SocketMessage::TogglePause
+            | SocketMessage::State
+            | SocketMessage::QueryHealth
+            | SocketMessage::QueryWindowList
+            | SocketMessage::QueryMruWindowList
+            | SocketMessage::QueryRuleSuggestions
+            | SocketMessage::QueryConfigurationDiff
+            | SocketMessage::QueryPeerState
+            | SocketMessage::ProtocolVersion
+            | SocketMessage::SetLogLevel(_)
+            | SocketMessage::PerformanceCounters(_)
+            | SocketMessage::ResetPerformanceCounters
+            | SocketMessage::Stop
+            | SocketMessage::Restart
+            | SocketMessage::CancelPendingOperations
+    )
+}
+
+/// Queries and subscriptions, which leave the window manager state untouched
+pub const fn is_read_only(message: &SocketMessage) -> bool {
+    matches!(
+        message,
+        SocketMessage::State
+            | SocketMessage::Query(_)
+            | SocketMessage::QueryHealth
+            | SocketMessage::QueryWindowList
+            | SocketMessage::QueryMruWindowList
+            | SocketMessage::QueryRuleSuggestions
+            | SocketMessage::QueryConfigurationDiff
+            | SocketMessage::QueryPeerState
+            | SocketMessage::ProtocolVersion
+            | SocketMessage::AddSubscriber(_)
+            | SocketMessage::RemoveSubscriber(_)
+    )
+}
+
+/// The socket in the home directory that a client is waiting on for a response, if any
+fn response_socket(message: &SocketMessage) -> Option<&str> {
+    match message {
+        SocketMessage::WithResponse(_) => Option::from(COMMAND_RESPONSE_SOCKET),
+        SocketMessage::WithResponseTo(socket, _) => Option::from(socket.as_str()),
+        _ => None,
+    }
+}
+
+fn write_response(response: &str) -> Result<()> {
+    write_response_to(COMMAND_RESPONSE_SOCKET, response)
+}
+
+fn write_response_to(name: &str, response: &str) -> Result<()> {
+    // Clients can only name a socket directly inside the home directory
+    if Path::new(name).file_name().and_then(OsStr::to_str) != Option::from(name) {
+        return Err(anyhow!("response sockets must be in the home directory"));
+    }
+
+    let mut socket = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
+    socket.push(name);
+    let socket = socket.as_path();
+
+    let mut stream = UnixStream::connect(&socket)?;
+    stream.write_all(response.as_bytes())?;
+
+    Ok(())
 }
@@ -1,10 +1,16 @@
 use std::fs::OpenOptions;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 
 use color_eyre::eyre::anyhow;
 use color_eyre::Result;
 use crossbeam_channel::select;
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use lazy_static::lazy_static;
 use parking_lot::Mutex;
 
 use komorebi_core::OperationDirection;
@@ -13,29 +19,112 @@ use komorebi_core::Sizing;
 use komorebi_core::WindowContainerBehaviour;
 
 use crate::current_virtual_desktop;
+use crate::float_positions;
+use crate::icons;
 use crate::notify_subscribers;
+use crate::operation_guard;
+use crate::session;
 use crate::window_manager::WindowManager;
 use crate::window_manager_event::WindowManagerEvent;
 use crate::windows_api::WindowsApi;
 use crate::Notification;
-use crate::NotificationEvent;
+use crate::FLOAT_IDENTIFIERS;
 use crate::HIDDEN_HWNDS;
+use crate::RETILE_PENDING;
 use crate::TRAY_AND_MULTI_WINDOW_IDENTIFIERS;
+use crate::WORKSPACE_RULES_ON_TITLE_CHANGE;
+
+// How often throttled retiles are checked for while no events are arriving
+const RETILE_FLUSH_INTERVAL_MILLISECONDS: u64 = 4;
+// How long the title of a window has to stop changing for before subscribers are notified
+const TITLE_CHANGE_DEBOUNCE_MILLISECONDS: u64 = 100;
+
+lazy_static! {
+    /// Wakes the event loop up when a retile is deferred while it is waiting for events, which
+    /// happens when retiles are throttled on the command path
+    static ref RETILE_WAKE_CHANNEL: Arc<Mutex<(Sender<()>, Receiver<()>)>> =
+        Arc::new(Mutex::new(crossbeam_channel::unbounded()));
+    /// The last title change which subscribers have not been notified of yet, and when it arrived
+    static ref PENDING_TITLE_CHANGE: Arc<Mutex<Option<(WindowManagerEvent, Instant)>>> =
+        Arc::new(Mutex::new(None));
+}
+
+/// Mark a retile as pending, waking the event loop up so that it is flushed on time
+pub fn defer_retile() {
+    if !RETILE_PENDING.swap(true, Ordering::SeqCst) {
+        if let Err(error) = RETILE_WAKE_CHANNEL.lock().0.send(()) {
+            tracing::error!("{}", error);
+        }
+    }
+}
 
 #[tracing::instrument]
 pub fn listen_for_events(wm: Arc<Mutex<WindowManager>>) {
     let receiver = wm.lock().incoming_events.lock().clone();
+    let wake_receiver = RETILE_WAKE_CHANNEL.lock().1.clone();
 
     thread::spawn(move || {
         tracing::info!("listening");
+
+        if let Err(error) = wm.lock().process_startup_events(&receiver) {
+            tracing::error!("{}", error);
+        }
+
         loop {
+            // Throttled retiles have to be flushed even if no further events arrive
+            let timeout = if RETILE_PENDING.load(Ordering::SeqCst) {
+                Duration::from_millis(RETILE_FLUSH_INTERVAL_MILLISECONDS)
+            } else if PENDING_TITLE_CHANGE.lock().is_some() {
+                Duration::from_millis(TITLE_CHANGE_DEBOUNCE_MILLISECONDS)
+            } else {
+                Duration::from_secs(60)
+            };
+
             select! {
                 recv(receiver) -> mut maybe_event => {
                     if let Ok(event) = maybe_event.as_mut() {
-                        match wm.lock().process_event(event) {
+                        let received = Instant::now();
+                        let mut wm = wm.lock();
+                        match wm.process_event(event) {
                             Ok(()) => {},
                             Err(error) => tracing::error!("{}", error)
                         }
+
+                        if wm.performance_counters.enabled {
+                            let exe = event.window().exe().ok();
+                            wm.performance_counters.record_event(exe, received);
+                        }
+
+                        wm.record_workspace_history();
+
+                        if RETILE_PENDING.load(Ordering::SeqCst) {
+                            if let Err(error) = wm.flush_pending_retiles() {
+                                tracing::error!("{}", error);
+                            }
+                        }
+
+                        if let Err(error) = wm.flush_pending_title_change() {
+                            tracing::error!("{}", error);
+                        }
+                    }
+                }
+                recv(wake_receiver) -> _ => {
+                    if RETILE_PENDING.load(Ordering::SeqCst) {
+                        if let Err(error) = wm.lock().flush_pending_retiles() {
+                            tracing::error!("{}", error);
+                        }
+                    }
+                }
+                default(timeout) => {
+                    let mut wm = wm.lock();
+                    if RETILE_PENDING.load(Ordering::SeqCst) {
+                        if let Err(error) = wm.flush_pending_retiles() {
+                            tracing::error!("{}", error);
+                        }
+                    }
+
+                    if let Err(error) = wm.flush_pending_title_change() {
+                        tracing::error!("{}", error);
                     }
                 }
             }
@@ -52,6 +141,11 @@ impl WindowManager {
             return Ok(());
         }
 
+        if session::is_suspended() {
+            tracing::trace!("ignoring while the session is locked or the display is off");
+            return Ok(());
+        }
+
         if let Some(virtual_desktop_id) = &self.virtual_desktop_id {
             if let Some(id) = current_virtual_desktop() {
                 if id != *virtual_desktop_id {
@@ -64,6 +158,53 @@ impl WindowManager {
             }
         }
 
+        // Windows being peeked at fire show and focus events of their own, which must not switch
+        // the focused workspace over to them, but windows which go away still have to be removed
+        if self.peeked_workspace.is_some() {
+            if let WindowManagerEvent::Destroy(_, window) | WindowManagerEvent::Unmanage(window) =
+                event
+            {
+                self.remove_window_while_peeking(window.hwnd)?;
+            } else {
+                tracing::trace!("ignoring events while peeking at a workspace");
+            }
+
+            return Ok(());
+        }
+
+        // Title changes are frequent, so they are only processed for windows that are managed
+        if let WindowManagerEvent::TitleChange(_, window) = event {
+            if self.window_location(window.hwnd).is_none() {
+                tracing::trace!("ignoring title change for unmanaged window");
+                return Ok(());
+            }
+        }
+
+        // Fullscreen applications own their monitor until they exit, so we don't want to fight
+        // them by tiling or focusing anything in response to their events
+        if self.reconcile_fullscreen_windows(*event)? {
+            tracing::trace!("ignoring event from fullscreen window");
+            return Ok(());
+        }
+
+        // Some applications reuse a window for something else entirely, so whether it is managed
+        // and any rules have to be evaluated again whenever a window changes its class or styles
+        if let WindowManagerEvent::FocusChange(_, window)
+        | WindowManagerEvent::Show(_, window)
+        | WindowManagerEvent::TitleChange(_, window)
+        | WindowManagerEvent::MoveResizeEnd(_, window) = event
+        {
+            if self.reconcile_morphed_window(*window)? {
+                return Ok(());
+            }
+        }
+
+        let maximized_states = self.maximized_states();
+        let newly_managed = matches!(
+            event,
+            WindowManagerEvent::Show(..) | WindowManagerEvent::Manage(_)
+        ) && self.window_location(event.window().hwnd).is_none();
+
         // Make sure we have the most recently focused monitor from any event
         match event {
             WindowManagerEvent::MonitorPoll(_, window)
@@ -84,11 +225,12 @@ impl WindowManager {
         let offset = self.work_area_offset;
 
         for (i, monitor) in self.monitors_mut().iter_mut().enumerate() {
-            let work_area = *monitor.work_area_size();
+            let work_area = monitor.tiling_area();
+            let group = monitor.group();
             for (j, workspace) in monitor.workspaces_mut().iter_mut().enumerate() {
                 let reaped_orphans = workspace.reap_orphans()?;
                 if reaped_orphans.0 > 0 || reaped_orphans.1 > 0 {
-                    workspace.update(&work_area, offset, &invisible_borders)?;
+                    workspace.update(&work_area, offset, &invisible_borders, group)?;
                     tracing::info!(
                         "reaped {} orphan window(s) and {} orphaned container(s) on monitor: {}, workspace: {}",
                         reaped_orphans.0,
@@ -100,7 +242,11 @@ impl WindowManager {
             }
         }
 
-        self.enforce_workspace_rules()?;
+        if !matches!(event, WindowManagerEvent::TitleChange(..))
+            || WORKSPACE_RULES_ON_TITLE_CHANGE.load(Ordering::SeqCst)
+        {
+            self.enforce_workspace_rules()?;
+        }
 
         if matches!(event, WindowManagerEvent::MouseCapture(..)) {
             tracing::trace!(
@@ -115,8 +261,22 @@ impl WindowManager {
                 self.has_pending_raise_op = false;
             }
             WindowManagerEvent::Destroy(_, window) | WindowManagerEvent::Unmanage(window) => {
-                self.focused_workspace_mut()?.remove_window(window.hwnd)?;
+                self.urgent_hwnds.remove(&window.hwnd);
+                icons::forget(window.hwnd);
+
+                let was_on_workspace = self.focused_workspace()?.contains_window(window.hwnd);
+                if !self.restore_swallowed_window(window.hwnd)? {
+                    self.focused_workspace_mut()?.remove_window(window.hwnd)?;
+                }
+
+                // The workspace is only left if it was emptied by this window being destroyed,
+                // not if it was already empty when some other window was destroyed
+                let emptied = was_on_workspace && self.focused_workspace()?.is_empty();
                 self.update_focused_workspace(false)?;
+
+                if emptied && matches!(event, WindowManagerEvent::Destroy(..)) {
+                    self.leave_empty_workspace()?;
+                }
             }
             WindowManagerEvent::Minimize(_, window) => {
                 let mut hide = false;
@@ -157,17 +317,34 @@ impl WindowManager {
                 }
 
                 if hide {
-                    self.focused_workspace_mut()?.remove_window(window.hwnd)?;
+                    if !self.restore_swallowed_window(window.hwnd)? {
+                        self.focused_workspace_mut()?.remove_window(window.hwnd)?;
+                    }
+
                     self.update_focused_workspace(false)?;
                 }
             }
             WindowManagerEvent::FocusChange(_, window) => {
+                // Windows restored or repositioned by the last operation fire focus events of
+                // their own, which must not take the focus away from the window it focused
+                if operation_guard::settling() {
+                    tracing::trace!("ignoring focus change while the last operation settles");
+                    return Ok(());
+                }
+
+                self.record_window_focus(window.hwnd);
+                self.urgent_hwnds.remove(&window.hwnd);
+
                 let workspace = self.focused_workspace_mut()?;
                 if workspace
                     .floating_windows()
                     .iter()
                     .any(|w| w.hwnd == window.hwnd)
                 {
+                    if let Err(error) = float_positions::remember(*window) {
+                        tracing::warn!("could not remember floating window position: {}", error);
+                    }
+
                     return Ok(());
                 }
 
@@ -226,15 +403,52 @@ impl WindowManager {
                 }
 
                 let behaviour = self.window_container_behaviour;
+                let next_window_direction = self.next_window_direction;
                 let workspace = self.focused_workspace_mut()?;
 
                 if !workspace.contains_window(window.hwnd) {
-                    match behaviour {
-                        WindowContainerBehaviour::Create => {
+                    let app_group_idx = workspace.app_group_container_idx(*window);
+
+                    match (
+                        workspace.swallower_for_window(*window),
+                        behaviour,
+                        next_window_direction,
+                    ) {
+                        // A window launched from an application with a swallow rule takes its
+                        // place until it is closed
+                        (Some(swallower), _, _) => {
+                            workspace.swallow_window(swallower, *window)?;
+                            self.update_focused_workspace(true)?;
+                        }
+                        // A placement hint is only ever consumed once, and always takes
+                        // precedence over the window container behaviour
+                        (None, _, Some(direction)) => {
+                            workspace.new_container_for_window_in_direction(*window, direction);
+                            self.next_window_direction = None;
+                            self.update_focused_workspace(false)?;
+                        }
+                        // Windows of an application with an app group rule are stacked with the
+                        // windows of that application which are already on the workspace
+                        (None, _, None) if app_group_idx.is_some() => {
+                            let idx = app_group_idx
+                                .ok_or_else(|| anyhow!("there is no app group container"))?;
+
+                            workspace.add_window_to_container(idx, *window)?;
+                            self.update_focused_workspace(true)?;
+                        }
+                        // Once every region of a multi-stack layout is occupied, new windows are
+                        // stacked in the last region instead of splitting the layout further
+                        (None, WindowContainerBehaviour::Create, None)
+                            if workspace.multi_stack_full() =>
+                        {
+                            workspace.add_window_to_multi_stack(*window)?;
+                            self.update_focused_workspace(true)?;
+                        }
+                        (None, WindowContainerBehaviour::Create, None) => {
                             workspace.new_container_for_window(*window);
                             self.update_focused_workspace(false)?;
                         }
-                        WindowContainerBehaviour::Append => {
+                        (None, WindowContainerBehaviour::Append, None) => {
                             workspace
                                 .focused_container_mut()
                                 .ok_or_else(|| anyhow!("there is no focused container"))?
@@ -242,6 +456,22 @@ impl WindowManager {
                             self.update_focused_workspace(true)?;
                         }
                     }
+
+                    // Applications launched by loading a workspace snapshot go where they were saved
+                    if self
+                        .focused_workspace_mut()?
+                        .apply_snapshot_placement(*window)?
+                    {
+                        self.update_focused_workspace(false)?;
+                    }
+
+                    // Windows of commands launched with spawn-into-workspace go to the workspace
+                    // that was reserved for them, regardless of the order in which they appear
+                    if self.claim_spawn_reservation(*window)
+                        || self.claim_workspace_rule_once(*window)
+                    {
+                        self.enforce_workspace_rules()?;
+                    }
                 }
             }
             WindowManagerEvent::MoveResizeStart(_, _) => {
@@ -278,6 +508,10 @@ impl WindowManager {
                     .iter()
                     .any(|w| w.hwnd == window.hwnd)
                 {
+                    if let Err(error) = float_positions::remember(*window) {
+                        tracing::warn!("could not remember floating window position: {}", error);
+                    }
+
                     return Ok(());
                 }
 
@@ -341,30 +575,50 @@ impl WindowManager {
                                 .ok_or_else(|| anyhow!("there is no monitor at this idx"))?
                                 .focused_workspace_idx();
 
-                            let target_container_idx = self
+                            let target_workspace = self
                                 .monitors()
                                 .get(target_monitor_idx)
                                 .ok_or_else(|| anyhow!("there is no monitor at this idx"))?
                                 .focused_workspace()
                                 .ok_or_else(|| {
                                     anyhow!("there is no focused workspace for this monitor")
-                                })?
-                                .container_idx_from_current_point()
-                                // Default to 0 in the case of an empty workspace
-                                .unwrap_or(0);
-
-                            self.transfer_container(
-                                (
-                                    origin_monitor_idx,
-                                    origin_workspace_idx,
-                                    origin_container_idx,
-                                ),
-                                (
-                                    target_monitor_idx,
-                                    target_workspace_idx,
-                                    target_container_idx,
-                                ),
-                            )?;
+                                })?;
+
+                            let origin = (
+                                origin_monitor_idx,
+                                origin_workspace_idx,
+                                origin_container_idx,
+                            );
+
+                            // The window container behaviour decides whether a window dropped on
+                            // a container on another monitor is stacked in it or tiled next to it
+                            let hovered_container_idx =
+                                target_workspace.container_idx_from_current_point();
+
+                            if let (WindowContainerBehaviour::Append, Some(target_container_idx)) =
+                                (new_window_behaviour, hovered_container_idx)
+                            {
+                                self.transfer_container_into_stack(
+                                    origin,
+                                    (
+                                        target_monitor_idx,
+                                        target_workspace_idx,
+                                        target_container_idx,
+                                    ),
+                                )?;
+                            } else {
+                                let target_container_idx =
+                                    target_workspace.drop_idx_from_current_point();
+
+                                self.transfer_container(
+                                    origin,
+                                    (
+                                        target_monitor_idx,
+                                        target_workspace_idx,
+                                        target_container_idx,
+                                    ),
+                                )?;
+                            }
 
                             // We want to make sure both the origin and target monitors are updated,
                             // so that we don't have ghost tiles until we force an interaction on
@@ -380,6 +634,13 @@ impl WindowManager {
                     // Here we handle a simple move on the same monitor which is treated as
                     // a container swap
                     } else {
+                        // Dropping a window on another region of a multi-stack layout stacks it
+                        let new_window_behaviour = if workspace.multi_stack().is_some() {
+                            WindowContainerBehaviour::Append
+                        } else {
+                            new_window_behaviour
+                        };
+
                         match new_window_behaviour {
                             WindowContainerBehaviour::Create => {
                                 match workspace.container_idx_from_current_point() {
@@ -446,6 +707,17 @@ impl WindowManager {
                     self.update_focused_workspace(false)?;
                 }
             }
+            WindowManagerEvent::TitleChange(_, window) => {
+                let should_float = FLOAT_IDENTIFIERS.lock().contains(&window.title()?);
+                let workspace = self.focused_workspace_mut()?;
+
+                // Windows which now match a float rule by title should no longer be tiled
+                if should_float && workspace.container_for_window(window.hwnd).is_some() {
+                    tracing::info!("unmanaging window which matches a float rule by its new title");
+                    workspace.remove_window(window.hwnd)?;
+                    self.update_focused_workspace(false)?;
+                }
+            }
             WindowManagerEvent::MonitorPoll(..) | WindowManagerEvent::MouseCapture(..) => {}
         };
 
@@ -454,34 +726,90 @@ impl WindowManager {
             window.center(&self.focused_monitor_work_area()?, &invisible_borders)?;
         }
 
-        tracing::trace!("updating list of known hwnds");
-        let mut known_hwnds = vec![];
-        for monitor in self.monitors() {
-            for workspace in monitor.workspaces() {
-                for container in workspace.containers() {
-                    for window in container.windows() {
-                        known_hwnds.push(window.hwnd);
+        if newly_managed && self.window_location(event.window().hwnd).is_some() {
+            icons::extract(event.window());
+        }
+
+        // Titles can change many times a second, and a title change only changes which windows
+        // are known if the window was unmanaged for matching a float rule by its new title
+        let title_changed = match event {
+            WindowManagerEvent::TitleChange(_, window) => {
+                self.window_location(window.hwnd).is_some()
+            }
+            _ => false,
+        };
+
+        if title_changed {
+            self.debounce_title_change(*event)?;
+        } else {
+            tracing::trace!("updating list of known hwnds");
+            let mut known_hwnds = vec![];
+            for monitor in self.monitors() {
+                for workspace in monitor.workspaces() {
+                    for container in workspace.containers() {
+                        for window in container.windows() {
+                            known_hwnds.push(window.hwnd);
+                        }
                     }
                 }
             }
-        }
 
-        let mut hwnd_json =
-            dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
-        hwnd_json.push("komorebi.hwnd.json");
-        let file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .open(hwnd_json)?;
+            let mut hwnd_json =
+                dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
+            hwnd_json.push("komorebi.hwnd.json");
+            let file = OpenOptions::new()
+                .write(true)
+                .truncate(true)
+                .create(true)
+                .open(hwnd_json)?;
+
+            serde_json::to_writer_pretty(&file, &known_hwnds)?;
+            self.notify_event(*event, newly_managed)?;
+        }
 
-        serde_json::to_writer_pretty(&file, &known_hwnds)?;
-        notify_subscribers(&serde_json::to_string(&Notification {
-            event: NotificationEvent::WindowManager(*event),
-            state: (&*self).into(),
-        })?)?;
+        self.notify_maximized_state_changes(&maximized_states)?;
 
         tracing::info!("processed: {}", event.window().to_string());
         Ok(())
     }
+
+    fn notify_event(&self, event: WindowManagerEvent, newly_managed: bool) -> Result<()> {
+        notify_subscribers(&serde_json::to_string(&Notification {
+            event: self.notification_event(event, newly_managed),
+            state: self.into(),
+            stack: self.focused_stack_indicator(),
+        })?)
+    }
+
+    // Subscribers are only notified once the title of a window has settled, unless the title of
+    // another window changes in the meantime
+    fn debounce_title_change(&self, event: WindowManagerEvent) -> Result<()> {
+        let previous = PENDING_TITLE_CHANGE.lock().replace((event, Instant::now()));
+
+        match previous {
+            Some((previous, _)) if previous.window().hwnd != event.window().hwnd => {
+                self.notify_event(previous, false)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Notify subscribers of the last title change once the title has stopped changing
+    pub fn flush_pending_title_change(&self) -> Result<()> {
+        let event = {
+            let mut pending = PENDING_TITLE_CHANGE.lock();
+            match *pending {
+                Some((event, changed))
+                    if changed.elapsed()
+                        >= Duration::from_millis(TITLE_CHANGE_DEBOUNCE_MILLISECONDS) =>
+                {
+                    *pending = None;
+                    event
+                }
+                _ => return Ok(()),
+            }
+        };
+
+        self.notify_event(event, false)
+    }
 }
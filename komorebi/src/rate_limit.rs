@@ -0,0 +1,67 @@
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use komorebi_core::SocketMessage;
+
+/// How many messages a client can send per second once its burst allowance has been used up,
+/// where 0 disables rate limiting
+pub static MESSAGE_RATE_LIMIT: AtomicU32 = AtomicU32::new(100);
+/// How many messages a client can send in quick succession, such as when a configuration file
+/// is run at startup
+pub static MESSAGE_BURST_LIMIT: AtomicU32 = AtomicU32::new(500);
+
+pub static DROPPED_MESSAGES: AtomicUsize = AtomicUsize::new(0);
+pub static COALESCED_MESSAGES: AtomicUsize = AtomicUsize::new(0);
+
+/// A token bucket for a single client. komorebic opens a new connection for every message, so
+/// everything received on each of komorebi.sock and komorebi-priority.sock is treated as coming
+/// from one client, whereas every WebSocket connection is a client of its own
+#[derive(Debug)]
+pub struct RateLimiter {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self {
+            tokens: f64::from(MESSAGE_BURST_LIMIT.load(Ordering::SeqCst)),
+            refilled: Instant::now(),
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Whether the next message from this client should be handled, counting it as dropped if not
+    pub fn allow(&mut self) -> bool {
+        let rate = MESSAGE_RATE_LIMIT.load(Ordering::SeqCst);
+        if rate == 0 {
+            return true;
+        }
+
+        let burst = f64::from(MESSAGE_BURST_LIMIT.load(Ordering::SeqCst).max(1));
+        let elapsed = self.refilled.elapsed().as_secs_f64();
+        self.tokens = elapsed.mul_add(f64::from(rate), self.tokens).min(burst);
+        self.refilled = Instant::now();
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            DROPPED_MESSAGES.fetch_add(1, Ordering::SeqCst);
+            false
+        }
+    }
+}
+
+/// Whether a queued message can be skipped because the message queued right after it will have
+/// the same effect; messages which write a response are never skipped, as a client will be
+/// waiting to read it
+pub const fn is_redundant(message: &SocketMessage, next: &SocketMessage) -> bool {
+    matches!(
+        (message, next),
+        (SocketMessage::Retile, SocketMessage::Retile)
+    )
+}
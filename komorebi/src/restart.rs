@@ -0,0 +1,71 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufReader;
+use std::path::Path;
+use std::path::PathBuf;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use komorebi_core::Axis;
+use komorebi_core::Layout;
+use komorebi_core::Rect;
+
+/// The arrangement of a single workspace, where each container is a list of window handles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartWorkspace {
+    pub name: Option<String>,
+    pub layout: Layout,
+    pub layout_flip: Option<Axis>,
+    pub resize_dimensions: Vec<Option<Rect>>,
+    pub workspace_padding: Option<i32>,
+    pub container_padding: Option<i32>,
+    pub tile: bool,
+    pub containers: Vec<Vec<isize>>,
+    pub floating_windows: Vec<isize>,
+    /// Swallowed windows, paired with the window which swallowed each of them
+    #[serde(default)]
+    pub swallowed_windows: Vec<(isize, isize)>,
+    pub focused_container_idx: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartMonitor {
+    pub id: isize,
+    pub focused_workspace_idx: usize,
+    pub workspaces: Vec<RestartWorkspace>,
+}
+
+/// The runtime state which is handed over to a new komorebi process by the restart command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartState {
+    pub focused_monitor_idx: usize,
+    pub monitors: Vec<RestartMonitor>,
+}
+
+impl RestartState {
+    pub fn path() -> Result<PathBuf> {
+        let mut path = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
+        path.push("komorebi.restart.json");
+        Ok(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open(path)?;
+
+        Ok(serde_json::to_writer_pretty(&file, self)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|_| anyhow!("no restart state found at {}", path.display()))?;
+
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+}
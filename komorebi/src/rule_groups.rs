@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use komorebi_core::ApplicationIdentifier;
+use komorebi_core::SocketMessage;
+
+use crate::APP_GROUP_IDENTIFIERS;
+use crate::BORDER_OVERFLOW_IDENTIFIERS;
+use crate::COMMAND_LINE_FLOAT_IDENTIFIERS;
+use crate::COMMAND_LINE_WORKSPACE_RULES;
+use crate::FLOAT_IDENTIFIERS;
+use crate::FOLLOW_IDENTIFIERS;
+use crate::HIDING_BEHAVIOUR_RULES;
+use crate::MANAGE_IDENTIFIERS;
+use crate::NO_BORDER_OVERFLOW_IDENTIFIERS;
+use crate::SIZE_CONSTRAINTS;
+use crate::SWALLOW_IDENTIFIERS;
+use crate::TRAY_AND_MULTI_WINDOW_IDENTIFIERS;
+use crate::WORKSPACE_RULES;
+use crate::WORKSPACE_RULE_BINDINGS;
+
+lazy_static! {
+    /// The rule group which incoming rules are currently being tagged with, if any
+    static ref CURRENT_RULE_GROUP: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    static ref RULE_GROUPS: Arc<Mutex<HashMap<String, RuleGroup>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+#[derive(Debug)]
+struct RuleGroup {
+    enabled: bool,
+    rules: Vec<SocketMessage>,
+}
+
+/// Tag every rule received from now on with `name`, until the group is ended
+pub fn add(name: String) {
+    RULE_GROUPS.lock().entry(name.clone()).or_insert(RuleGroup {
+        enabled: true,
+        rules: vec![],
+    });
+
+    *CURRENT_RULE_GROUP.lock() = Option::from(name);
+}
+
+pub fn end() {
+    *CURRENT_RULE_GROUP.lock() = None;
+}
+
+/// The rules which can be removed again when the group they were tagged with is disabled
+pub const fn is_rule(message: &SocketMessage) -> bool {
+    matches!(
+        message,
+        SocketMessage::WorkspaceRule(..)
+            | SocketMessage::ApplyWorkspaceRule(..)
+            | SocketMessage::BoundWorkspaceRule(..)
+            | SocketMessage::FloatRule(..)
+            | SocketMessage::ManageRule(..)
+            | SocketMessage::FollowRule(..)
+            | SocketMessage::SwallowRule(..)
+            | SocketMessage::AppGroupRule(..)
+            | SocketMessage::IdentifyTrayApplication(..)
+            | SocketMessage::IdentifyBorderOverflow(..)
+            | SocketMessage::IdentifyNoBorderOverflow(..)
+            | SocketMessage::HidingBehaviourRule(..)
+            | SocketMessage::SizeConstraintRule(..)
+    )
+}
+
+/// Tag a rule with the current rule group, returning whether it should be applied now
+pub fn record(message: &SocketMessage) -> bool {
+    let current = CURRENT_RULE_GROUP.lock();
+    let name = match current.as_ref() {
+        Some(name) => name,
+        None => return true,
+    };
+
+    let mut groups = RULE_GROUPS.lock();
+    let group = groups.entry(name.clone()).or_insert(RuleGroup {
+        enabled: true,
+        rules: vec![],
+    });
+
+    // Reloading a configuration script shouldn't tag the same rule more than once
+    let json = serde_json::to_string(message).ok();
+    if !group
+        .rules
+        .iter()
+        .any(|rule| serde_json::to_string(rule).ok() == json)
+    {
+        group.rules.push(message.clone());
+    }
+
+    group.enabled
+}
+
+/// Flip whether the rules tagged with `name` are in effect, returning the new state and the
+/// rules which should be applied or removed as a result
+pub fn toggle(name: &str) -> Result<(bool, Vec<SocketMessage>)> {
+    let mut groups = RULE_GROUPS.lock();
+    let group = groups
+        .get_mut(name)
+        .ok_or_else(|| anyhow!("there is no rule group with this name"))?;
+
+    group.enabled = !group.enabled;
+
+    Ok((group.enabled, group.rules.clone()))
+}
+
+/// Undo the effect of a rule; an identifier which was also given by a rule outside of the group
+/// is removed as well
+pub fn remove(message: &SocketMessage) {
+    match message {
+        SocketMessage::WorkspaceRule(ApplicationIdentifier::CommandLine, id, ..)
+        | SocketMessage::ApplyWorkspaceRule(ApplicationIdentifier::CommandLine, id, ..) => {
+            COMMAND_LINE_WORKSPACE_RULES
+                .lock()
+                .retain(|(existing, _)| existing != id);
+        }
+        SocketMessage::WorkspaceRule(_, id, ..) | SocketMessage::ApplyWorkspaceRule(_, id, ..) => {
+            WORKSPACE_RULES.lock().remove(id);
+        }
+        SocketMessage::BoundWorkspaceRule(identifier, id, _) => {
+            WORKSPACE_RULE_BINDINGS
+                .lock()
+                .retain(|(_, existing, _)| existing != id);
+
+            if matches!(identifier, ApplicationIdentifier::CommandLine) {
+                COMMAND_LINE_WORKSPACE_RULES
+                    .lock()
+                    .retain(|(existing, _)| existing != id);
+            } else {
+                WORKSPACE_RULES.lock().remove(id);
+            }
+        }
+        SocketMessage::FloatRule(ApplicationIdentifier::CommandLine, id) => {
+            COMMAND_LINE_FLOAT_IDENTIFIERS
+                .lock()
+                .retain(|existing| existing != id);
+        }
+        SocketMessage::FloatRule(_, id) => {
+            FLOAT_IDENTIFIERS.lock().retain(|existing| existing != id);
+        }
+        SocketMessage::ManageRule(_, id) => {
+            MANAGE_IDENTIFIERS.lock().retain(|existing| existing != id);
+        }
+        SocketMessage::FollowRule(_, id) => {
+            FOLLOW_IDENTIFIERS.lock().retain(|existing| existing != id);
+        }
+        SocketMessage::SwallowRule(_, id) => {
+            SWALLOW_IDENTIFIERS.lock().retain(|existing| existing != id);
+        }
+        SocketMessage::AppGroupRule(_, id) => {
+            APP_GROUP_IDENTIFIERS
+                .lock()
+                .retain(|existing| existing != id);
+        }
+        SocketMessage::IdentifyTrayApplication(_, id) => {
+            TRAY_AND_MULTI_WINDOW_IDENTIFIERS
+                .lock()
+                .retain(|existing| existing != id);
+        }
+        SocketMessage::IdentifyBorderOverflow(_, id) => {
+            BORDER_OVERFLOW_IDENTIFIERS
+                .lock()
+                .retain(|existing| existing != id);
+        }
+        SocketMessage::IdentifyNoBorderOverflow(_, id) => {
+            NO_BORDER_OVERFLOW_IDENTIFIERS
+                .lock()
+                .retain(|existing| existing != id);
+        }
+        SocketMessage::HidingBehaviourRule(_, id, _) => {
+            HIDING_BEHAVIOUR_RULES.lock().remove(id);
+        }
+        SocketMessage::SizeConstraintRule(_, id, ..) => {
+            SIZE_CONSTRAINTS.lock().remove(id);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt::Write;
+
+use sha1::Digest;
+use sha1::Sha1;
+
+// The block size of SHA-1, which keys are padded or hashed to fit
+const BLOCK_SIZE: usize = 64;
+
+/// Compare a secret given by a client with the expected one, taking the same amount of time no
+/// matter how many leading bytes match, so that the secret cannot be guessed one byte at a time
+pub fn matches(expected: &[u8], given: &[u8]) -> bool {
+    if expected.len() != given.len() {
+        return false;
+    }
+
+    expected
+        .iter()
+        .zip(given)
+        .fold(0, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
+
+/// The HMAC-SHA1 of `message` keyed with `secret`, as a hex string, which proves knowledge of the
+/// secret without revealing it
+pub fn hmac(secret: &[u8], message: &[u8]) -> String {
+    let mut key = [0; BLOCK_SIZE];
+    if secret.len() > BLOCK_SIZE {
+        let digest = Sha1::digest(secret);
+        key[..digest.len()].copy_from_slice(&digest);
+    } else {
+        key[..secret.len()].copy_from_slice(secret);
+    }
+
+    let mut inner = Sha1::new();
+    inner.update(key.map(|byte| byte ^ 0x36));
+    inner.update(message);
+
+    let mut outer = Sha1::new();
+    outer.update(key.map(|byte| byte ^ 0x5c));
+    outer.update(inner.finalize());
+
+    outer
+        .finalize()
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            // Writing to a String never fails
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
@@ -0,0 +1,98 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use crate::window_manager::WindowManager;
+use crate::windows_api::WindowsApi;
+use crate::windows_callbacks;
+use crate::winevent_listener::MessageLoop;
+
+lazy_static! {
+    pub static ref SESSION_CALLBACK_CHANNEL: Arc<Mutex<(Sender<SessionEvent>, Receiver<SessionEvent>)>> =
+        Arc::new(Mutex::new(crossbeam_channel::unbounded()));
+}
+
+pub static SESSION_LOCKED: AtomicBool = AtomicBool::new(false);
+pub static DISPLAY_OFF: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy)]
+pub enum SessionEvent {
+    Locked,
+    Unlocked,
+    DisplayOff,
+    DisplayOn,
+}
+
+/// Events are not processed while the session is locked or the display is off, since nothing
+/// that happens on the desktop in the meantime is visible to the user
+pub fn is_suspended() -> bool {
+    SESSION_LOCKED.load(Ordering::SeqCst) || DISPLAY_OFF.load(Ordering::SeqCst)
+}
+
+#[tracing::instrument]
+pub fn listen_for_session_changes(wm: Arc<Mutex<WindowManager>>) {
+    thread::spawn(move || {
+        // Session and power notifications are sent as window messages to a window owned by
+        // this thread, so they are received and handled in its message loop
+        let hwnd = match WindowsApi::create_message_window(
+            "komorebi.session",
+            Some(windows_callbacks::session_window_proc),
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(error) => {
+                tracing::error!("could not create session notification window: {}", error);
+                return;
+            }
+        };
+
+        if let Err(error) = WindowsApi::register_session_notifications(hwnd) {
+            tracing::error!("could not register for session notifications: {}", error);
+            return;
+        }
+
+        tracing::info!("listening for session lock and display power changes");
+
+        let receiver = SESSION_CALLBACK_CHANNEL.lock().1.clone();
+        MessageLoop::start(10, |_msg| {
+            while let Ok(event) = receiver.try_recv() {
+                handle_session_event(&wm, event);
+            }
+
+            true
+        });
+    });
+}
+
+fn handle_session_event(wm: &Arc<Mutex<WindowManager>>, event: SessionEvent) {
+    let was_suspended = is_suspended();
+
+    match event {
+        SessionEvent::Locked => SESSION_LOCKED.store(true, Ordering::SeqCst),
+        SessionEvent::Unlocked => SESSION_LOCKED.store(false, Ordering::SeqCst),
+        SessionEvent::DisplayOff => DISPLAY_OFF.store(true, Ordering::SeqCst),
+        SessionEvent::DisplayOn => DISPLAY_OFF.store(false, Ordering::SeqCst),
+    }
+
+    tracing::info!("received session event: {:?}", event);
+
+    if !was_suspended && is_suspended() {
+        tracing::info!("suspending event processing");
+    }
+
+    if was_suspended && !is_suspended() {
+        tracing::info!("resuming event processing, reconciling changes made while suspended");
+
+        let mut wm = wm.lock();
+        if !wm.is_paused {
+            if let Err(error) = wm.reconcile() {
+                tracing::error!("{}", error);
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::atomic::AtomicU32;
+use std::sync::Arc;
+use std::thread;
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use crate::window_manager::WindowManager;
+use crate::windows_api::WindowsApi;
+use crate::windows_callbacks;
+use crate::winevent_listener::MessageLoop;
+
+lazy_static! {
+    pub static ref URGENCY_CALLBACK_CHANNEL: Arc<Mutex<(Sender<isize>, Receiver<isize>)>> =
+        Arc::new(Mutex::new(crossbeam_channel::unbounded()));
+}
+
+/// The message id which the shell uses for shell hook notifications, which is only known once it
+/// has been registered at runtime
+pub static SHELL_HOOK_MESSAGE: AtomicU32 = AtomicU32::new(0);
+
+#[tracing::instrument]
+pub fn listen_for_urgent_windows(wm: Arc<Mutex<WindowManager>>) {
+    thread::spawn(move || {
+        // Windows which flash their taskbar buttons to request attention are reported through
+        // shell hook messages sent to a window owned by this thread
+        let hwnd = match WindowsApi::create_message_window(
+            "komorebi.urgency",
+            Some(windows_callbacks::urgency_window_proc),
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(error) => {
+                tracing::error!("could not create urgency notification window: {}", error);
+                return;
+            }
+        };
+
+        if let Err(error) = WindowsApi::register_shell_hook(hwnd) {
+            tracing::error!("could not register for shell hook notifications: {}", error);
+            return;
+        }
+
+        tracing::info!("listening for windows requesting attention");
+
+        let receiver = URGENCY_CALLBACK_CHANNEL.lock().1.clone();
+        MessageLoop::start(10, |_msg| {
+            while let Ok(hwnd) = receiver.try_recv() {
+                if let Err(error) = wm.lock().mark_window_urgent(hwnd) {
+                    tracing::error!("{}", error);
+                }
+            }
+
+            true
+        });
+    });
+}
@@ -0,0 +1,48 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+use color_eyre::Result;
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use crate::windows_api::WindowsApi;
+
+lazy_static! {
+    static ref WALLPAPER_CHANNEL: Arc<Mutex<(Sender<PathBuf>, Receiver<PathBuf>)>> =
+        Arc::new(Mutex::new(crossbeam_channel::unbounded()));
+    /// The wallpaper which was last requested, so that switching between workspaces with the
+    /// same wallpaper doesn't set it again
+    static ref CURRENT_WALLPAPER: Arc<Mutex<Option<PathBuf>>> = Arc::new(Mutex::new(None));
+}
+
+/// Change the desktop wallpaper to `path` if it isn't already, without waiting for it to be
+/// applied since this writes to the registry and broadcasts the change to every window
+pub fn set(path: &Path) -> Result<()> {
+    let mut current = CURRENT_WALLPAPER.lock();
+    if current.as_deref() == Some(path) {
+        return Ok(());
+    }
+
+    *current = Option::from(path.to_path_buf());
+    Ok(WALLPAPER_CHANNEL.lock().0.send(path.to_path_buf())?)
+}
+
+#[tracing::instrument]
+pub fn listen_for_wallpaper_changes() {
+    let receiver = WALLPAPER_CHANNEL.lock().1.clone();
+
+    thread::spawn(move || {
+        for path in receiver.iter() {
+            // Only the most recently requested wallpaper has to be applied
+            let path = receiver.try_iter().last().unwrap_or(path);
+
+            if let Err(error) = WindowsApi::set_wallpaper(&path) {
+                tracing::error!("could not set wallpaper: {}", error);
+            }
+        }
+    });
+}
@@ -0,0 +1,131 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use color_eyre::Result;
+use parking_lot::Mutex;
+
+use komorebi_core::NotificationEvent;
+
+use crate::notify_subscribers;
+use crate::process_cache;
+use crate::session;
+use crate::window_manager::WindowManager;
+use crate::windows_api::WindowsApi;
+use crate::winevent_listener::LAST_WINEVENT_TIMESTAMP;
+use crate::winevent_listener::REREGISTER_WINEVENT_HOOK;
+use crate::Notification;
+
+const POLL_INTERVAL_SECONDS: u64 = 5;
+const STALE_HOOK_THRESHOLD_SECONDS: u64 = 10;
+const ORPHAN_POLL_INTERVAL_SECONDS: u64 = 30;
+
+/// Cleared when the hook is found to be stale, and set again once WinEvents are received
+static HOOKS_ALIVE: AtomicBool = AtomicBool::new(true);
+
+pub fn hooks_alive() -> bool {
+    HOOKS_ALIVE.load(Ordering::SeqCst)
+}
+
+#[tracing::instrument]
+pub fn listen_for_stale_hooks(wm: Arc<Mutex<WindowManager>>) {
+    thread::spawn(move || {
+        tracing::info!("watching winevent hook");
+
+        let mut previous_foreground = WindowsApi::foreground_window().unwrap_or_default();
+        let mut previous_poll = unix_timestamp();
+
+        loop {
+            thread::sleep(Duration::from_secs(POLL_INTERVAL_SECONDS));
+
+            let now = unix_timestamp();
+            let foreground = WindowsApi::foreground_window().unwrap_or_default();
+            let last_event = LAST_WINEVENT_TIMESTAMP.load(Ordering::SeqCst);
+
+            // A change of the foreground window always raises EVENT_SYSTEM_FOREGROUND, so if the
+            // foreground window has changed without a single WinEvent being received since the
+            // last poll, the hook has been silently removed (ie. after a UAC prompt or an RDP
+            // session reconnect)
+            let foreground_changed = foreground != previous_foreground;
+            let is_stale = last_event < previous_poll
+                && now.saturating_sub(last_event) >= STALE_HOOK_THRESHOLD_SECONDS;
+
+            if last_event >= previous_poll {
+                HOOKS_ALIVE.store(true, Ordering::SeqCst);
+            }
+
+            if foreground_changed && is_stale {
+                HOOKS_ALIVE.store(false, Ordering::SeqCst);
+                tracing::warn!(
+                    "no winevents received for {} seconds while the desktop has changed, re-registering hook",
+                    now.saturating_sub(last_event)
+                );
+
+                REREGISTER_WINEVENT_HOOK.store(true, Ordering::SeqCst);
+
+                let mut wm = wm.lock();
+                if !wm.is_paused && !session::is_suspended() {
+                    match wm.reconcile() {
+                        Ok(()) => {}
+                        Err(error) => tracing::error!("{}", error),
+                    }
+                }
+            }
+
+            previous_foreground = foreground;
+            previous_poll = now;
+        }
+    });
+}
+
+#[tracing::instrument]
+pub fn listen_for_orphans(wm: Arc<Mutex<WindowManager>>) {
+    thread::spawn(move || {
+        tracing::info!("watching for orphaned windows");
+
+        loop {
+            thread::sleep(Duration::from_secs(ORPHAN_POLL_INTERVAL_SECONDS));
+
+            let mut wm = wm.lock();
+            if wm.is_paused || session::is_suspended() {
+                continue;
+            }
+
+            // Process ids are reused, so anything looked up about processes which have since
+            // exited is forgotten
+            process_cache::prune();
+
+            // Windows of applications which have crashed never send a destroy event, and if they
+            // are in the background of a stack or on a hidden workspace they will never be reaped
+            match wm.prune_dead_windows() {
+                Ok(events) => {
+                    for event in events {
+                        if let Err(error) = notify_orphan_removed(&wm, event) {
+                            tracing::error!("{}", error);
+                        }
+                    }
+                }
+                Err(error) => tracing::error!("{}", error),
+            }
+        }
+    });
+}
+
+fn notify_orphan_removed(wm: &WindowManager, event: NotificationEvent) -> Result<()> {
+    notify_subscribers(&serde_json::to_string(&Notification {
+        event,
+        state: wm.into(),
+        stack: wm.focused_stack_indicator(),
+    })?)
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,266 @@
+use std::io::ErrorKind;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use tungstenite::handshake::server::ErrorResponse;
+use tungstenite::handshake::server::Request;
+use tungstenite::handshake::server::Response;
+use tungstenite::http::StatusCode;
+use tungstenite::Message;
+
+use komorebi_core::SocketMessage;
+use komorebi_core::SocketResponse;
+
+use crate::cancellation;
+use crate::process_command;
+use crate::rate_limit::RateLimiter;
+use crate::secret;
+use crate::window_manager::WindowManager;
+
+// How long a client thread waits for an incoming message before checking for notifications
+const POLL_INTERVAL_MILLISECONDS: u64 = 50;
+
+lazy_static! {
+    static ref WEBSOCKET_PORT_CHANNEL: Arc<Mutex<(Sender<u16>, Receiver<u16>)>> =
+        Arc::new(Mutex::new(crossbeam_channel::bounded(1)));
+    /// The secret which clients must give when connecting
+    static ref WEBSOCKET_SECRET: Arc<Mutex<String>> = Arc::new(Mutex::new(String::new()));
+    static ref WEBSOCKET_CLIENTS: Arc<Mutex<Vec<Sender<String>>>> = Arc::new(Mutex::new(vec![]));
+    /// The origins of web pages which are allowed to connect, given as scheme://host:port
+    static ref WEBSOCKET_ALLOWED_ORIGINS: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+}
+
+static WEBSOCKET_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Start the WebSocket server on `port`, bound to localhost only, accepting clients which give
+/// `secret` in the query string of the URL that they connect to
+pub fn start(port: u16, secret: String) -> Result<()> {
+    if secret.is_empty() {
+        return Err(anyhow!("the websocket secret cannot be empty"));
+    }
+
+    if WEBSOCKET_STARTED.swap(true, Ordering::SeqCst) {
+        return Err(anyhow!("the websocket server has already been started"));
+    }
+
+    *WEBSOCKET_SECRET.lock() = secret;
+    Ok(WEBSOCKET_PORT_CHANNEL.lock().0.send(port)?)
+}
+
+/// Allow web pages served from `origin` to connect to the WebSocket server
+pub fn allow_origin(origin: String) {
+    let mut allowed_origins = WEBSOCKET_ALLOWED_ORIGINS.lock();
+    if !allowed_origins.contains(&origin) {
+        allowed_origins.push(origin);
+    }
+}
+
+/// Send a notification to every connected WebSocket client
+pub fn broadcast(notification: &str) {
+    // Clients that have disconnected will have dropped their receivers
+    WEBSOCKET_CLIENTS
+        .lock()
+        .retain(|client| client.send(notification.to_string()).is_ok());
+}
+
+#[tracing::instrument]
+pub fn listen_for_websocket_connections(wm: Arc<Mutex<WindowManager>>) {
+    let receiver = WEBSOCKET_PORT_CHANNEL.lock().1.clone();
+
+    thread::spawn(move || {
+        // The server is only started once a port has been given with the websocket-server command
+        let port = match receiver.recv() {
+            Ok(port) => port,
+            Err(error) => {
+                tracing::error!("{}", error);
+                return;
+            }
+        };
+
+        let listener = match TcpListener::bind(("127.0.0.1", port)) {
+            Ok(listener) => listener,
+            Err(error) => {
+                tracing::error!(
+                    "could not start websocket server on port {}: {}",
+                    port,
+                    error
+                );
+                return;
+            }
+        };
+
+        tracing::info!("listening for websocket connections on port {}", port);
+
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let wm = wm.clone();
+                    thread::spawn(move || {
+                        if let Err(error) = handle_client(&wm, stream) {
+                            tracing::error!("{}", error);
+                        }
+                    });
+                }
+                Err(error) => tracing::error!("{}", error),
+            }
+        }
+    });
+}
+
+// Any web page open in a browser can connect to localhost, so connections from pages which have
+// not been allowed are refused, and every client has to know the secret
+fn authorise(request: &Request) -> std::result::Result<(), (StatusCode, &'static str)> {
+    if let Some(origin) = request.headers().get("Origin") {
+        let allowed = origin.to_str().map_or(false, |origin| {
+            WEBSOCKET_ALLOWED_ORIGINS
+                .lock()
+                .iter()
+                .any(|allowed| allowed == origin)
+        });
+
+        if !allowed {
+            return Err((StatusCode::FORBIDDEN, "this origin is not allowed"));
+        }
+    }
+
+    let given = request
+        .uri()
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .find_map(|parameter| parameter.strip_prefix("secret="))
+        .unwrap_or_default();
+
+    if !secret::matches(WEBSOCKET_SECRET.lock().as_bytes(), given.as_bytes()) {
+        return Err((StatusCode::UNAUTHORIZED, "the secret is not valid"));
+    }
+
+    Ok(())
+}
+
+fn handle_client(wm: &Arc<Mutex<WindowManager>>, stream: TcpStream) -> Result<()> {
+    let mut websocket = tungstenite::accept_hdr(stream, |request: &Request, response: Response| {
+        authorise(request)
+            .map(|()| response)
+            .map_err(|(status, reason)| {
+                tracing::warn!("refusing websocket connection: {}", reason);
+                let mut response = ErrorResponse::new(Option::from(reason.to_string()));
+                *response.status_mut() = status;
+                response
+            })
+    })
+    .map_err(|error| anyhow!("{}", error))?;
+    websocket
+        .get_ref()
+        .set_read_timeout(Option::from(Duration::from_millis(
+            POLL_INTERVAL_MILLISECONDS,
+        )))?;
+
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    WEBSOCKET_CLIENTS.lock().push(sender);
+
+    tracing::info!("websocket client connected");
+
+    let mut limiter = RateLimiter::default();
+    loop {
+        match websocket.read_message() {
+            Ok(Message::Text(_)) if !limiter.allow() => {
+                tracing::warn!("dropping websocket message over the rate limit");
+            }
+            Ok(Message::Text(text)) => {
+                if let Some(response) = handle_message(wm, &text) {
+                    websocket.write_message(Message::Text(response))?;
+                }
+            }
+            Ok(Message::Close(_)) | Err(tungstenite::Error::ConnectionClosed) => {
+                tracing::info!("websocket client disconnected");
+                return Ok(());
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(error))
+                if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(error) => return Err(error.into()),
+        }
+
+        while let Ok(notification) = receiver.try_recv() {
+            websocket.write_message(Message::Text(notification))?;
+        }
+    }
+}
+
+// Only queries are accepted over the WebSocket, and subscribers cannot be added since clients
+// already receive every notification
+const fn is_allowed(message: &SocketMessage) -> bool {
+    process_command::is_read_only(message)
+        && !matches!(
+            message,
+            SocketMessage::AddSubscriber(_) | SocketMessage::RemoveSubscriber(_)
+        )
+}
+
+// Messages are handled in the same way as those received on komorebi.sock, except that responses
+// to messages wrapped in WithResponse are sent back over the WebSocket
+fn handle_message(wm: &Arc<Mutex<WindowManager>>, text: &str) -> Option<String> {
+    let token = cancellation::token();
+
+    let message = match SocketMessage::from_str(text) {
+        Ok(message) => message,
+        Err(error) => {
+            tracing::error!("could not parse websocket message: {}", error);
+            return None;
+        }
+    };
+
+    let (message, with_response) = match message {
+        SocketMessage::WithResponse(message) | SocketMessage::WithResponseTo(_, message) => {
+            (*message, true)
+        }
+        message => (message, false),
+    };
+
+    let response = if is_allowed(&message) {
+        let mut wm = wm.lock();
+
+        // Messages received before a CancelPendingOperations which were still waiting for the
+        // lock are discarded, as they would be if they had been sent to komorebi.sock
+        if cancellation::is_cancelled(token) {
+            tracing::info!("discarding cancelled message: {}", message);
+            SocketResponse::error("the operation was cancelled".to_string())
+        } else if with_response {
+            wm.process_command_with_response(message)
+        } else {
+            if let Err(error) = wm.handle_command(message) {
+                tracing::error!("{}", error);
+            }
+
+            return None;
+        }
+    } else {
+        tracing::warn!("refusing websocket message: {}", message);
+        SocketResponse::error("only queries can be sent over the websocket".to_string())
+    };
+
+    if !with_response {
+        return None;
+    }
+
+    match serde_json::to_string(&response) {
+        Ok(response) => Option::from(response),
+        Err(error) => {
+            tracing::error!("{}", error);
+            None
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::convert::TryFrom;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::sync::atomic::Ordering;
 
 use color_eyre::eyre::anyhow;
 use color_eyre::Result;
@@ -8,23 +9,57 @@ use serde::ser::Error;
 use serde::ser::SerializeStruct;
 use serde::Serialize;
 use serde::Serializer;
+use sysinfo::Pid;
+use sysinfo::ProcessExt;
+use sysinfo::SystemExt;
 use windows::Win32::Foundation::HWND;
 
+use komorebi_core::ApplicationIdentifier;
+use komorebi_core::CursorPlacement;
 use komorebi_core::HidingBehaviour;
+use komorebi_core::NotificationWindow;
 use komorebi_core::Rect;
+use komorebi_core::SizeConstraints;
 
+use crate::icons;
+use crate::operation_guard;
+use crate::process_cache;
 use crate::styles::ExtendedWindowStyle;
 use crate::styles::WindowStyle;
 use crate::window_manager_event::WindowManagerEvent;
 use crate::windows_api::WindowsApi;
+use crate::APP_GROUP_IDENTIFIERS;
 use crate::BORDER_OVERFLOW_IDENTIFIERS;
+use crate::CLASS_BLOCKLIST;
+use crate::COMMAND_LINE_FLOAT_IDENTIFIERS;
+use crate::COMMAND_LINE_WORKSPACE_RULES;
+use crate::FLOATED_ONCE_HWNDS;
+use crate::FLOAT_ELEVATED_WINDOWS;
 use crate::FLOAT_IDENTIFIERS;
+use crate::FLOAT_RULES_ONCE;
+use crate::FOLLOW_IDENTIFIERS;
 use crate::HIDDEN_HWNDS;
 use crate::HIDING_BEHAVIOUR;
+use crate::HIDING_BEHAVIOUR_RULES;
+use crate::KOMOREBI_ELEVATED;
+use crate::LAST_CURSOR_POSITIONS;
 use crate::LAYERED_EXE_WHITELIST;
 use crate::MANAGE_IDENTIFIERS;
+use crate::MONITOR_SIZES;
+use crate::MOUSE_FOLLOWS_FOCUS_OFFSET;
+use crate::MOUSE_FOLLOWS_FOCUS_PLACEMENT;
+use crate::NO_BORDER_OVERFLOW_IDENTIFIERS;
+use crate::SIZE_CONSTRAINTS;
+use crate::SWALLOW_IDENTIFIERS;
+use crate::WINDOW_SIGNATURES;
 use crate::WSL2_UI_PROCESSES;
 
+const CHROMIUM_WINDOW_CLASS_PREFIX: &str = "Chrome_WidgetWin_";
+
+/// The class of a window and the bits of its styles and extended styles which decide whether it
+/// is managed
+pub type MorphSignature = (String, u32, u32);
+
 #[derive(Debug, Clone, Copy)]
 pub struct Window {
     pub(crate) hwnd: isize,
@@ -52,12 +87,25 @@ impl Display for Window {
     }
 }
 
+impl From<Window> for NotificationWindow {
+    fn from(window: Window) -> Self {
+        Self {
+            hwnd: window.hwnd,
+            title: window.title().ok(),
+            exe: window.exe().ok(),
+            class: window.class().ok(),
+            elevation_mismatch: window.elevation_mismatch(),
+            icon: icons::path(window),
+        }
+    }
+}
+
 impl Serialize for Window {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Window", 5)?;
+        let mut state = serializer.serialize_struct("Window", 7)?;
         state.serialize_field("hwnd", &self.hwnd)?;
         state.serialize_field(
             "title",
@@ -82,6 +130,8 @@ impl Serialize for Window {
             &WindowsApi::window_rect(self.hwnd())
                 .map_err(|_| S::Error::custom("could not get window rect"))?,
         )?;
+        state.serialize_field("elevation_mismatch", &self.elevation_mismatch())?;
+        state.serialize_field("icon", &icons::path(*self))?;
         state.end()
     }
 }
@@ -114,17 +164,8 @@ impl Window {
         top: bool,
     ) -> Result<()> {
         let mut rect = *layout;
-        let mut should_remove_border = true;
 
-        let border_overflows = BORDER_OVERFLOW_IDENTIFIERS.lock();
-        if border_overflows.contains(&self.title()?)
-            || border_overflows.contains(&self.exe()?)
-            || border_overflows.contains(&self.class()?)
-        {
-            should_remove_border = false;
-        }
-
-        if should_remove_border {
+        if !self.border_overflows()? {
             // Remove the invisible borders
             rect.left -= invisible_borders.left;
             rect.top -= invisible_borders.top;
@@ -135,19 +176,122 @@ impl Window {
         WindowsApi::position_window(self.hwnd(), &rect, top)
     }
 
+    /// Whether this window has been identified as having overflowing borders, or looks like a
+    /// Chromium-based application (including PWAs and Electron applications) which does not
+    /// have invisible borders
+    pub fn border_overflows(self) -> Result<bool> {
+        let identifiers = [self.title()?, self.exe()?, self.class()?];
+
+        {
+            let border_overflows = BORDER_OVERFLOW_IDENTIFIERS.lock();
+            if identifiers.iter().any(|id| border_overflows.contains(id)) {
+                return Ok(true);
+            }
+        }
+
+        {
+            let no_border_overflows = NO_BORDER_OVERFLOW_IDENTIFIERS.lock();
+            if identifiers
+                .iter()
+                .any(|id| no_border_overflows.contains(id))
+            {
+                return Ok(false);
+            }
+        }
+
+        // Chromium draws its own window frame, so unlike other windows there are no invisible
+        // borders between the window rect and the frame bounds reported by DWM
+        if identifiers[2].starts_with(CHROMIUM_WINDOW_CLASS_PREFIX) {
+            let window_rect = WindowsApi::window_rect(self.hwnd())?;
+            let frame_bounds = WindowsApi::window_rect_with_extended_frame_bounds(self.hwnd())?;
+
+            return Ok(window_rect == frame_bounds);
+        }
+
+        Ok(false)
+    }
+
+    pub fn size_constraints(self) -> Option<SizeConstraints> {
+        let size_constraints = SIZE_CONSTRAINTS.lock();
+        if size_constraints.is_empty() {
+            return None;
+        }
+
+        if let Some(constraints) = size_constraints.get(&self.exe().ok()?) {
+            return Option::from(*constraints);
+        }
+
+        if let Some(constraints) = size_constraints.get(&self.class().ok()?) {
+            return Option::from(*constraints);
+        }
+
+        size_constraints.get(&self.title().ok()?).copied()
+    }
+
     pub fn hide(self) {
         let mut programmatically_hidden_hwnds = HIDDEN_HWNDS.lock();
         if !programmatically_hidden_hwnds.contains(&self.hwnd) {
             programmatically_hidden_hwnds.push(self.hwnd);
         }
 
-        let hiding_behaviour = HIDING_BEHAVIOUR.lock();
-        match *hiding_behaviour {
+        match self.hiding_behaviour() {
             HidingBehaviour::Hide => WindowsApi::hide_window(self.hwnd()),
             HidingBehaviour::Minimize => WindowsApi::minimize_window(self.hwnd()),
         }
     }
 
+    pub fn should_follow(self) -> bool {
+        let follow_identifiers = FOLLOW_IDENTIFIERS.lock();
+        [self.exe(), self.class(), self.title()]
+            .into_iter()
+            .flatten()
+            .any(|id| follow_identifiers.contains(&id))
+    }
+
+    pub fn can_swallow(self) -> bool {
+        let swallow_identifiers = SWALLOW_IDENTIFIERS.lock();
+        if swallow_identifiers.is_empty() {
+            return false;
+        }
+
+        [self.exe(), self.class(), self.title()]
+            .into_iter()
+            .flatten()
+            .any(|id| swallow_identifiers.contains(&id))
+    }
+
+    /// The identifier of the app group rule matching this window, which every window of the
+    /// application is stacked under
+    pub fn app_group(self) -> Option<String> {
+        let app_group_identifiers = APP_GROUP_IDENTIFIERS.lock();
+        if app_group_identifiers.is_empty() {
+            return None;
+        }
+
+        [self.exe(), self.class(), self.title()]
+            .into_iter()
+            .flatten()
+            .find(|id| app_group_identifiers.contains(id))
+    }
+
+    pub fn hiding_behaviour(self) -> HidingBehaviour {
+        {
+            let hiding_behaviour_rules = HIDING_BEHAVIOUR_RULES.lock();
+            if !hiding_behaviour_rules.is_empty() {
+                for id in [self.exe(), self.class(), self.title()]
+                    .into_iter()
+                    .flatten()
+                {
+                    if let Some(behaviour) = hiding_behaviour_rules.get(&id) {
+                        return *behaviour;
+                    }
+                }
+            }
+        }
+
+        *HIDING_BEHAVIOUR.lock()
+    }
+
     pub fn restore(self) {
         let mut programmatically_hidden_hwnds = HIDDEN_HWNDS.lock();
         if let Some(idx) = programmatically_hidden_hwnds
@@ -194,6 +338,14 @@ impl Window {
     }
 
     pub fn focus(self, mouse_follows_focus: bool) -> Result<()> {
+        if mouse_follows_focus {
+            if let Ok(foreground) = WindowsApi::foreground_window() {
+                if foreground != self.hwnd {
+                    Self { hwnd: foreground }.remember_cursor_position();
+                }
+            }
+        }
+
         // Attach komorebi thread to Window thread
         let (_, window_thread_id) = WindowsApi::window_thread_process_id(self.hwnd());
         let current_thread_id = WindowsApi::current_thread_id();
@@ -210,20 +362,112 @@ impl Window {
             }
         };
 
-        // Center cursor in Window
-        if mouse_follows_focus {
-            WindowsApi::center_cursor_in_rect(&WindowsApi::window_rect(self.hwnd())?)?;
+        // Move cursor into Window, unless the focus is being moved around by a retile pass
+        if mouse_follows_focus && !operation_guard::in_progress() {
+            self.place_cursor()?;
         }
 
         // This isn't really needed when the above command works as expected via AHK
         WindowsApi::set_focus(self.hwnd())
     }
 
+    /// Record where the cursor is within this window, for the last_position cursor placement
+    fn remember_cursor_position(self) {
+        if !matches!(
+            *MOUSE_FOLLOWS_FOCUS_PLACEMENT.lock(),
+            CursorPlacement::LastPosition
+        ) {
+            return;
+        }
+
+        if let (Ok(rect), Ok(cursor)) = (
+            WindowsApi::window_rect(self.hwnd()),
+            WindowsApi::cursor_pos(),
+        ) {
+            if rect.contains_point((cursor.x, cursor.y)) {
+                LAST_CURSOR_POSITIONS
+                    .lock()
+                    .insert(self.hwnd, (cursor.x - rect.left, cursor.y - rect.top));
+            }
+        }
+    }
+
+    fn place_cursor(self) -> Result<()> {
+        let rect = WindowsApi::window_rect(self.hwnd())?;
+        let offset = MOUSE_FOLLOWS_FOCUS_OFFSET.load(Ordering::SeqCst);
+        let placement = *MOUSE_FOLLOWS_FOCUS_PLACEMENT.lock();
+
+        // Keep the cursor at least the offset away from the edges, as long as the window is big
+        // enough for that to be possible
+        let clamp = |value: i32, start: i32, length: i32| {
+            let inset = offset.min(length / 2);
+            value.clamp(start + inset, start + length - inset)
+        };
+
+        let (x, y) = match placement {
+            CursorPlacement::Center => return WindowsApi::center_cursor_in_rect(&rect),
+            CursorPlacement::TopLeft => (
+                clamp(rect.left + offset, rect.left, rect.right),
+                clamp(rect.top + offset, rect.top, rect.bottom),
+            ),
+            CursorPlacement::NearestEdge => {
+                let cursor = WindowsApi::cursor_pos()?;
+                (
+                    clamp(cursor.x, rect.left, rect.right),
+                    clamp(cursor.y, rect.top, rect.bottom),
+                )
+            }
+            CursorPlacement::LastPosition => {
+                let last_position = LAST_CURSOR_POSITIONS.lock().get(&self.hwnd).copied();
+                match last_position {
+                    None => return WindowsApi::center_cursor_in_rect(&rect),
+                    Some((x, y)) => (
+                        clamp(rect.left + x, rect.left, rect.right),
+                        clamp(rect.top + y, rect.top, rect.bottom),
+                    ),
+                }
+            }
+        };
+
+        WindowsApi::set_cursor_pos(x, y)
+    }
+
     #[allow(dead_code)]
     pub fn update_style(self, style: WindowStyle) -> Result<()> {
         WindowsApi::update_style(self.hwnd(), isize::try_from(style.bits())?)
     }
 
+    /// Whether the class or styles of a window have changed since it was last seen by the window
+    /// manager, or when it was last refused
+    pub fn has_morphed(self) -> bool {
+        let previous = WINDOW_SIGNATURES.lock().get(&self.hwnd).cloned();
+        previous.map_or(false, |previous| {
+            self.morph_signature()
+                .map_or(false, |signature| signature != previous)
+        })
+    }
+
+    /// The exe, class and title of this window, which identify it in workspace snapshots
+    pub fn identity(self) -> Option<(String, String, String)> {
+        match (self.exe(), self.class(), self.title()) {
+            (Ok(exe), Ok(class), Ok(title)) => Option::from((exe, class, title)),
+            _ => None,
+        }
+    }
+
+    /// The class and the styles which decide whether a window is managed, used to detect windows
+    /// which are reused by their application for something else, like a splash screen which
+    /// becomes the main window
+    pub fn morph_signature(self) -> Result<MorphSignature> {
+        let style = self.style()? & WindowStyle::CAPTION;
+        let ex_style = self.ex_style()?
+            & (ExtendedWindowStyle::WINDOWEDGE
+                | ExtendedWindowStyle::DLGMODALFRAME
+                | ExtendedWindowStyle::LAYERED);
+
+        Ok((self.class()?, style.bits(), ex_style.bits()))
+    }
+
     pub fn style(self) -> Result<WindowStyle> {
         let bits = u32::try_from(WindowsApi::gwl_style(self.hwnd())?)?;
         WindowStyle::from_bits(bits).ok_or_else(|| anyhow!("there is no gwl style"))
@@ -239,8 +483,125 @@ impl Window {
     }
 
     pub fn exe(self) -> Result<String> {
+        WindowsApi::exe(WindowsApi::process_handle(self.process_id())?)
+    }
+
+    pub fn path(self) -> Result<String> {
+        WindowsApi::exe_path(WindowsApi::process_handle(self.process_id())?)
+    }
+
+    /// The command line that this window's process was launched with, with arguments separated by
+    /// spaces
+    pub fn command_line(self) -> Result<String> {
+        process_cache::command_line(self.process_id())
+    }
+
+    /// The target of the first command line workspace rule which matches this window
+    pub fn command_line_workspace_rule(self) -> Option<(usize, usize)> {
+        let rules = COMMAND_LINE_WORKSPACE_RULES.lock();
+        if rules.is_empty() {
+            return None;
+        }
+
+        let command_line = self.command_line().ok()?;
+        rules
+            .iter()
+            .find(|(id, _)| command_line.contains(id))
+            .map(|(_, target)| *target)
+    }
+
+    fn matches_command_line_float_rule(self) -> bool {
+        let identifiers = COMMAND_LINE_FLOAT_IDENTIFIERS.lock();
+        if identifiers.is_empty() {
+            return false;
+        }
+
+        self.command_line().map_or(false, |command_line| {
+            identifiers.iter().any(|id| command_line.contains(id))
+        })
+    }
+
+    /// Whether this window matches `id` for the given identifier, where command lines match on a
+    /// substring and everything else has to match exactly
+    pub fn matches_identifier(self, identifier: &ApplicationIdentifier, id: &str) -> bool {
+        match identifier {
+            ApplicationIdentifier::Exe => self.exe().map_or(false, |exe| exe == id),
+            ApplicationIdentifier::Class => self.class().map_or(false, |class| class == id),
+            ApplicationIdentifier::Title => self.title().map_or(false, |title| title == id),
+            ApplicationIdentifier::CommandLine => self
+                .command_line()
+                .map_or(false, |command_line| command_line.contains(id)),
+        }
+    }
+
+    /// Remove the first one-shot float rule which matches this window, returning whether there
+    /// was one
+    fn take_float_rule_once(self) -> bool {
+        let mut rules = FLOAT_RULES_ONCE.lock();
+        match rules
+            .iter()
+            .position(|(identifier, id)| self.matches_identifier(identifier, id))
+        {
+            None => false,
+            Some(idx) => {
+                rules.remove(idx);
+                FLOATED_ONCE_HWNDS.lock().push(self.hwnd);
+                true
+            }
+        }
+    }
+
+    /// Whether a float rule keeps this window from being managed, as opposed to it having been
+    /// floated with toggle-float
+    pub fn is_floated_by_rule(self) -> bool {
+        if let (Ok(title), Ok(exe_name), Ok(class)) = (self.title(), self.exe(), self.class()) {
+            let float_identifiers = FLOAT_IDENTIFIERS.lock();
+            if float_identifiers.contains(&title)
+                || float_identifiers.contains(&exe_name)
+                || float_identifiers.contains(&class)
+            {
+                return true;
+            }
+        }
+
+        self.matches_command_line_float_rule() || FLOATED_ONCE_HWNDS.lock().contains(&self.hwnd)
+    }
+
+    /// Windows of elevated processes cannot be moved, resized or focused by komorebi unless it is
+    /// also running elevated
+    pub fn elevation_mismatch(self) -> bool {
+        !KOMOREBI_ELEVATED.load(Ordering::SeqCst)
+            && process_cache::is_elevated(self.process_id()).unwrap_or_default()
+    }
+
+    pub fn process_id(self) -> u32 {
         let (process_id, _) = WindowsApi::window_thread_process_id(self.hwnd());
-        WindowsApi::exe(WindowsApi::process_handle(process_id)?)
+        process_id
+    }
+
+    /// The ids of the chain of processes which led to the launch of this window's process,
+    /// starting with its parent
+    pub fn ancestor_process_ids(self) -> Vec<Pid> {
+        let mut system = sysinfo::System::new();
+        system.refresh_processes();
+
+        let mut ancestors = vec![];
+        let mut current = Pid::try_from(self.process_id()).ok();
+
+        while let Some(parent) = current
+            .and_then(|pid| system.process(pid))
+            .and_then(ProcessExt::parent)
+        {
+            // Process ids can be reused, so guard against cycles in the reported parents
+            if ancestors.contains(&parent) {
+                break;
+            }
+
+            ancestors.push(parent);
+            current = Option::from(parent);
+        }
+
+        ancestors
     }
 
     pub fn class(self) -> Result<String> {
@@ -255,6 +616,35 @@ impl Window {
         WindowsApi::is_window(self.hwnd())
     }
 
+    pub fn is_maximized(self) -> bool {
+        WindowsApi::is_zoomed(self.hwnd())
+    }
+
+    /// Borderless windows which cover the entirety of their monitor, such as games and video
+    /// players, are considered to be fullscreen
+    pub fn is_fullscreen(self) -> Result<bool> {
+        if !self.is_window() || WindowsApi::is_iconic(self.hwnd()) {
+            return Ok(false);
+        }
+
+        let style = self.style()?;
+        if style.contains(WindowStyle::CAPTION) && !style.contains(WindowStyle::POPUP) {
+            return Ok(false);
+        }
+
+        let hmonitor = WindowsApi::monitor_from_window(self.hwnd());
+        let cached = MONITOR_SIZES.lock().get(&hmonitor).copied();
+        let monitor_size = if let Some(size) = cached {
+            size
+        } else {
+            let size = *WindowsApi::monitor(hmonitor)?.size();
+            MONITOR_SIZES.lock().insert(hmonitor, size);
+            size
+        };
+
+        Ok(WindowsApi::window_rect(self.hwnd())? == monitor_size)
+    }
+
     #[tracing::instrument(fields(exe, title))]
     pub fn should_manage(self, event: Option<WindowManagerEvent>) -> Result<bool> {
         if let Some(WindowManagerEvent::MonitorPoll(_, _)) = event {
@@ -279,13 +669,13 @@ impl Window {
             // If not allowing cloaked windows, we need to ensure the window is not cloaked
             (false, false) => {
                 if let (Ok(title), Ok(exe_name), Ok(class)) = (self.title(), self.exe(), self.class()) {
-                    {
-                        let float_identifiers = FLOAT_IDENTIFIERS.lock();
-                        if float_identifiers.contains(&title)
-                            || float_identifiers.contains(&exe_name)
-                            || float_identifiers.contains(&class) {
-                            return Ok(false);
-                        }
+                    if self.is_floated_by_rule() {
+                        return Ok(false);
+                    }
+
+                    let elevation_mismatch = self.elevation_mismatch();
+                    if elevation_mismatch && FLOAT_ELEVATED_WINDOWS.load(Ordering::SeqCst) {
+                        return Ok(false);
                     }
 
                     let managed_override = {
@@ -293,6 +683,10 @@ impl Window {
                         manage_identifiers.contains(&exe_name) || manage_identifiers.contains(&class)
                     };
 
+                    if !managed_override && is_blocklisted_class(&class) {
+                        return Ok(false);
+                    }
+
                     let allow_layered = {
                         let layered_exe_whitelist = LAYERED_EXE_WHITELIST.lock();
                         layered_exe_whitelist.contains(&exe_name)
@@ -315,6 +709,19 @@ impl Window {
                         && (allow_layered || !ex_style.contains(ExtendedWindowStyle::LAYERED))
                         || managed_override
                     {
+                        // One-shot rules are only used up by windows which would otherwise have
+                        // been managed
+                        if matches!(event, Some(WindowManagerEvent::Show(..))) && self.take_float_rule_once() {
+                            return Ok(false);
+                        }
+
+                        if elevation_mismatch && matches!(event, Some(WindowManagerEvent::Show(..))) {
+                            tracing::warn!(
+                                "{} belongs to an elevated process and cannot be positioned unless komorebi is also run as an administrator",
+                                exe_name
+                            );
+                        }
+
                         return Ok(true);
                     } else if event.is_some() {
                         tracing::debug!("ignoring (exe: {}, title: {})", exe_name, title);
@@ -327,3 +734,44 @@ impl Window {
         Ok(false)
     }
 }
+
+fn is_blocklisted_class(class: &str) -> bool {
+    CLASS_BLOCKLIST
+        .lock()
+        .iter()
+        .any(|pattern| glob_matches(pattern, class))
+}
+
+/// Match `text` against a pattern where * matches any run of characters and ? matches any single
+/// character
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // The position of the last * and the position in the text that it is currently matched up to
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Option::from((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Option::from((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
@@ -1,9 +1,16 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::io::ErrorKind;
 use std::num::NonZeroUsize;
+use std::path::Path;
 use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 
 use color_eyre::eyre::anyhow;
 use color_eyre::Result;
@@ -12,55 +19,124 @@ use hotwatch::notify::DebouncedEvent;
 use hotwatch::Hotwatch;
 use parking_lot::Mutex;
 use serde::Serialize;
+use sysinfo::Pid;
 use uds_windows::UnixListener;
 
 use komorebi_core::custom_layout::CustomLayout;
+use komorebi_core::ApplicationIdentifier;
+use komorebi_core::ApplicationPlaceholder;
 use komorebi_core::Arrangement;
 use komorebi_core::Axis;
 use komorebi_core::CycleDirection;
 use komorebi_core::DefaultLayout;
+use komorebi_core::EmptyWorkspaceBehaviour;
 use komorebi_core::FocusFollowsMouseImplementation;
 use komorebi_core::Layout;
+use komorebi_core::NotificationEvent;
 use komorebi_core::OperationDirection;
 use komorebi_core::Rect;
+use komorebi_core::SizeConstraints;
 use komorebi_core::Sizing;
+use komorebi_core::SnapshotContainer;
+use komorebi_core::SocketMessage;
 use komorebi_core::WindowContainerBehaviour;
+use komorebi_core::WorkspaceSnapshot;
 
 use crate::container::Container;
 use crate::current_virtual_desktop;
+use crate::float_positions;
+use crate::hints;
+use crate::icons;
+use crate::learning;
+use crate::learning::SuggestionReason;
 use crate::load_configuration;
+use crate::load_display_profile;
 use crate::monitor::Monitor;
+use crate::monitor::MonitorGroup;
+use crate::notify_subscribers;
+use crate::operation_guard;
+use crate::operation_guard::OperationGuard;
+use crate::performance::PerformanceCounters;
+use crate::performance::PerformanceReport;
+use crate::rate_limit::COALESCED_MESSAGES;
+use crate::rate_limit::DROPPED_MESSAGES;
+use crate::restart::RestartMonitor;
+use crate::restart::RestartState;
+use crate::restart::RestartWorkspace;
 use crate::ring::Ring;
+use crate::rule_groups;
+use crate::wallpaper;
+use crate::watchdog;
 use crate::window::Window;
 use crate::window_manager_event::WindowManagerEvent;
 use crate::windows_api::WindowsApi;
+use crate::windows_api::ZOrder;
+use crate::winevent_listener::LAST_WINEVENT_TIMESTAMP;
 use crate::winevent_listener::WINEVENT_CALLBACK_CHANNEL;
+use crate::winevent_listener::WINEVENT_HOOK;
 use crate::workspace::Workspace;
+use crate::workspace_history::WorkspaceHistory;
+use crate::Notification;
+use crate::StackIndicator;
 use crate::BORDER_OVERFLOW_IDENTIFIERS;
+use crate::CLASS_BLOCKLIST;
+use crate::COMMAND_LINE_WORKSPACE_RULES;
+use crate::CUSTOM_FFM;
 use crate::FLOAT_IDENTIFIERS;
 use crate::LAYERED_EXE_WHITELIST;
 use crate::MANAGE_IDENTIFIERS;
+use crate::MONITOR_SIZES;
+use crate::RETILE_PENDING;
+use crate::SIZE_CONSTRAINTS;
 use crate::TRAY_AND_MULTI_WINDOW_IDENTIFIERS;
+use crate::WINDOW_SIGNATURES;
 use crate::WORKSPACE_RULES;
+use crate::WORKSPACE_RULES_ONCE;
+use crate::WORKSPACE_RULE_BINDINGS;
 
+/// The percentages of its split that the focused container is cycled through, in order
+const SPLIT_RATIO_PRESETS: [i32; 4] = [50, 66, 75, 33];
+
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug)]
 pub struct WindowManager {
     pub monitors: Ring<Monitor>,
     pub incoming_events: Arc<Mutex<Receiver<WindowManagerEvent>>>,
     pub command_listener: UnixListener,
+    /// Messages received on this socket are handled ahead of anything queued on command_listener
+    pub priority_command_listener: UnixListener,
     pub is_paused: bool,
     pub invisible_borders: Rect,
     pub work_area_offset: Option<Rect>,
     pub resize_delta: i32,
     pub window_container_behaviour: WindowContainerBehaviour,
+    pub empty_workspace_behaviour: EmptyWorkspaceBehaviour,
+    pub workspace_sync: bool,
     pub focus_follows_mouse: Option<FocusFollowsMouseImplementation>,
     pub mouse_follows_focus: bool,
+    pub stack_indicator: bool,
     pub hotwatch: Hotwatch,
     pub virtual_desktop_id: Option<Vec<u8>>,
+    pub display_set: Option<String>,
     pub has_pending_raise_op: bool,
     pub pending_move_op: Option<(usize, usize, usize)>,
+    pub next_window_direction: Option<OperationDirection>,
+    pub peeked_workspace: Option<(usize, usize)>,
+    pub workspace_history: WorkspaceHistory,
+    /// Managed window hwnds, most recently focused first
+    pub window_focus_history: VecDeque<isize>,
+    /// Managed windows which have flashed their taskbar buttons and not been focused since
+    pub urgent_hwnds: HashSet<isize>,
+    pub performance_counters: PerformanceCounters,
+    pub workspace_rule_moves: HashMap<isize, Instant>,
+    pub spawn_reservations: Vec<SpawnReservation>,
+    pub container_hints: Option<(Instant, HashMap<String, String>)>,
+    pub focus_mode_width: u8,
+    pub hydrating: bool,
+    pub start_time: Instant,
 }
 
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Serialize)]
 pub struct State {
     pub monitors: Ring<Monitor>,
@@ -68,15 +144,26 @@ pub struct State {
     pub invisible_borders: Rect,
     pub resize_delta: i32,
     pub new_window_behaviour: WindowContainerBehaviour,
+    pub empty_workspace_behaviour: EmptyWorkspaceBehaviour,
+    pub workspace_sync: bool,
     pub work_area_offset: Option<Rect>,
     pub focus_follows_mouse: Option<FocusFollowsMouseImplementation>,
     pub mouse_follows_focus: bool,
+    pub stack_indicator: bool,
+    pub display_set: Option<String>,
     pub has_pending_raise_op: bool,
+    pub next_window_direction: Option<OperationDirection>,
     pub float_identifiers: Vec<String>,
+    pub class_blocklist: Vec<String>,
     pub manage_identifiers: Vec<String>,
     pub layered_exe_whitelist: Vec<String>,
     pub tray_and_multi_window_identifiers: Vec<String>,
     pub border_overflow_identifiers: Vec<String>,
+    pub size_constraints: HashMap<String, SizeConstraints>,
+    pub performance_counters: Option<PerformanceReport>,
+    /// The number of windows requesting attention on each workspace of each monitor, for
+    /// rendering notification badges on workspace labels
+    pub workspace_badges: Vec<Vec<usize>>,
 }
 
 impl From<&WindowManager> for State {
@@ -88,20 +175,105 @@ impl From<&WindowManager> for State {
             work_area_offset: wm.work_area_offset,
             resize_delta: wm.resize_delta,
             new_window_behaviour: wm.window_container_behaviour,
+            empty_workspace_behaviour: wm.empty_workspace_behaviour,
+            workspace_sync: wm.workspace_sync,
             focus_follows_mouse: wm.focus_follows_mouse.clone(),
             mouse_follows_focus: wm.mouse_follows_focus,
+            stack_indicator: wm.stack_indicator,
+            display_set: wm.display_set.clone(),
             has_pending_raise_op: wm.has_pending_raise_op,
+            next_window_direction: wm.next_window_direction,
             float_identifiers: FLOAT_IDENTIFIERS.lock().clone(),
+            class_blocklist: CLASS_BLOCKLIST.lock().clone(),
             manage_identifiers: MANAGE_IDENTIFIERS.lock().clone(),
             layered_exe_whitelist: LAYERED_EXE_WHITELIST.lock().clone(),
             tray_and_multi_window_identifiers: TRAY_AND_MULTI_WINDOW_IDENTIFIERS.lock().clone(),
             border_overflow_identifiers: BORDER_OVERFLOW_IDENTIFIERS.lock().clone(),
+            size_constraints: SIZE_CONSTRAINTS.lock().clone(),
+            performance_counters: wm.performance_counters.report(),
+            workspace_badges: wm.workspace_badges(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Health {
+    pub version: String,
+    pub uptime_seconds: u64,
+    pub hooks_alive: bool,
+    pub managed_windows: usize,
+    pub last_event_timestamp: Option<u64>,
+    pub pending_events: usize,
+    /// Messages which were dropped for exceeding the rate limit
+    pub dropped_messages: usize,
+    /// Messages which were skipped because an identical message was queued right after them
+    pub coalesced_messages: usize,
+}
+
+/// A flattened view of a managed window and where it lives, for external window pickers
+#[derive(Debug, Serialize)]
+pub struct WindowListEntry {
+    pub hwnd: isize,
+    pub title: Option<String>,
+    pub exe: Option<String>,
+    pub monitor_idx: usize,
+    pub workspace_idx: usize,
+    /// Floating, maximized and monocle windows are not part of the tiled container list
+    pub container_idx: Option<usize>,
+    pub stack_idx: Option<usize>,
+    /// Full path to the executable, which window switchers can extract an icon from
+    pub path: Option<String>,
+    /// Where the window was last positioned, for sizing thumbnails
+    pub rect: Option<Rect>,
+}
+
+impl WindowListEntry {
+    fn new(
+        window: Window,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        container_idx: Option<usize>,
+        stack_idx: Option<usize>,
+    ) -> Self {
+        Self {
+            hwnd: window.hwnd,
+            title: window.title().ok(),
+            exe: window.exe().ok(),
+            monitor_idx,
+            workspace_idx,
+            container_idx,
+            stack_idx,
+            path: window.path().ok(),
+            rect: WindowsApi::window_rect(window.hwnd()).ok(),
         }
     }
 }
 
 impl_ring_elements!(WindowManager, Monitor);
 
+const WINDOW_FOCUS_HISTORY_LIMIT: usize = 256;
+
+// How far the cursor has to travel into the work area of another monitor before focus follows it
+// there, so that skimming along the edge between two monitors doesn't bounce focus back and forth
+const FOCUS_FOLLOWS_MOUSE_MONITOR_HYSTERESIS: i32 = 20;
+
+// How long a window moved by a workspace rule is left alone before rules can move it again
+const WORKSPACE_RULE_FLAP_GUARD_SECONDS: u64 = 5;
+
+// How long a placement reserved by spawn-into-workspace waits for the first window of the launched
+// process to appear before it is discarded
+const SPAWN_RESERVATION_TIMEOUT_SECONDS: u64 = 60;
+
+/// A placement on a workspace which is reserved for the first window of a launched process
+#[derive(Debug, Clone, Copy)]
+pub struct SpawnReservation {
+    process_id: u32,
+    monitor_idx: usize,
+    workspace_idx: usize,
+    hwnd: Option<isize>,
+    spawned: Instant,
+}
+
 #[derive(Debug, Clone, Copy)]
 struct EnforceWorkspaceRuleOp {
     hwnd: isize,
@@ -126,31 +298,37 @@ impl EnforceWorkspaceRuleOp {
     }
 }
 
+fn bind_socket(name: &str) -> Result<UnixListener> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
+    let mut socket = home;
+    socket.push(name);
+    let socket = socket.as_path();
+
+    match std::fs::remove_file(&socket) {
+        Ok(_) => {}
+        Err(error) => match error.kind() {
+            // Doing this because ::exists() doesn't work reliably on Windows via IntelliJ
+            ErrorKind::NotFound => {}
+            _ => {
+                return Err(error.into());
+            }
+        },
+    };
+
+    Ok(UnixListener::bind(&socket)?)
+}
+
 impl WindowManager {
     #[tracing::instrument]
     pub fn new(incoming: Arc<Mutex<Receiver<WindowManagerEvent>>>) -> Result<Self> {
-        let home = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
-        let mut socket = home;
-        socket.push("komorebi.sock");
-        let socket = socket.as_path();
-
-        match std::fs::remove_file(&socket) {
-            Ok(_) => {}
-            Err(error) => match error.kind() {
-                // Doing this because ::exists() doesn't work reliably on Windows via IntelliJ
-                ErrorKind::NotFound => {}
-                _ => {
-                    return Err(error.into());
-                }
-            },
-        };
-
-        let listener = UnixListener::bind(&socket)?;
+        let listener = bind_socket("komorebi.sock")?;
+        let priority_listener = bind_socket("komorebi-priority.sock")?;
 
         Ok(Self {
             monitors: Ring::default(),
             incoming_events: incoming,
             command_listener: listener,
+            priority_command_listener: priority_listener,
             is_paused: false,
             invisible_borders: Rect {
                 left: 7,
@@ -159,14 +337,30 @@ impl WindowManager {
                 bottom: 7,
             },
             virtual_desktop_id: current_virtual_desktop(),
+            display_set: None,
             work_area_offset: None,
             window_container_behaviour: WindowContainerBehaviour::Create,
+            empty_workspace_behaviour: EmptyWorkspaceBehaviour::Stay,
             resize_delta: 50,
             focus_follows_mouse: None,
             mouse_follows_focus: true,
+            stack_indicator: true,
             hotwatch: Hotwatch::new()?,
             has_pending_raise_op: false,
             pending_move_op: None,
+            next_window_direction: None,
+            peeked_workspace: None,
+            workspace_history: WorkspaceHistory::default(),
+            window_focus_history: VecDeque::new(),
+            urgent_hwnds: HashSet::new(),
+            performance_counters: PerformanceCounters::default(),
+            workspace_rule_moves: HashMap::new(),
+            spawn_reservations: vec![],
+            container_hints: None,
+            focus_mode_width: 60,
+            hydrating: false,
+            workspace_sync: false,
+            start_time: Instant::now(),
         })
     }
 
@@ -174,8 +368,33 @@ impl WindowManager {
     pub fn init(&mut self) -> Result<()> {
         tracing::info!("initialising");
         WindowsApi::load_monitor_information(&mut self.monitors)?;
-        WindowsApi::load_workspace_information(&mut self.monitors)?;
-        self.update_focused_workspace(false)
+        self.adopt_windows(WindowsApi::manageable_windows()?)?;
+        self.reconcile_display_set();
+
+        // One layout pass for the visible workspace of each monitor; hidden workspaces are laid
+        // out when they are first focused
+        self.retile_all(true)
+    }
+
+    /// Process the events which were queued while windows were being adopted by `init`, most of
+    /// which are raised by komorebi itself hiding the windows of inactive workspaces, with a single
+    /// layout pass at the end instead of one for every event
+    #[tracing::instrument(skip(self, receiver))]
+    pub fn process_startup_events(
+        &mut self,
+        receiver: &Receiver<WindowManagerEvent>,
+    ) -> Result<()> {
+        tracing::info!("processing events queued during startup");
+
+        self.hydrating = true;
+        while let Ok(mut event) = receiver.try_recv() {
+            if let Err(error) = self.process_event(&mut event) {
+                tracing::error!("{}", error);
+            }
+        }
+
+        self.hydrating = false;
+        self.retile_all(true)
     }
 
     #[tracing::instrument]
@@ -289,7 +508,36 @@ impl WindowManager {
 
         for monitor in self.monitors_mut() {
             let mut should_update = false;
-            let reference = WindowsApi::monitor(monitor.id())?;
+
+            // Displays which are asleep or turned off can be reported with a zero-sized work
+            // area, and tiling across that would squash every window on the monitor
+            let reference = match WindowsApi::monitor(monitor.id()) {
+                Ok(reference)
+                    if reference.size().right > 0
+                        && reference.size().bottom > 0
+                        && reference.work_area_size().right > 0
+                        && reference.work_area_size().bottom > 0 =>
+                {
+                    reference
+                }
+                _ => {
+                    if !monitor.asleep() {
+                        tracing::info!("monitor is asleep, keeping its last known geometry");
+                        monitor.set_asleep(true);
+                    }
+
+                    continue;
+                }
+            };
+
+            MONITOR_SIZES.lock().insert(monitor.id(), *reference.size());
+
+            if monitor.asleep() {
+                tracing::info!("monitor has woken up, restoring its layout");
+                monitor.set_asleep(false);
+                should_update = true;
+            }
+
             // TODO: If this is different, force a redraw
 
             if reference.work_area_size() != monitor.work_area_size() {
@@ -320,72 +568,413 @@ impl WindowManager {
 
         // Check for and add any new monitors that may have been plugged in
         WindowsApi::load_monitor_information(&mut self.monitors)?;
+        self.reconcile_display_set();
+
+        if self.reconcile_monitor_groups() {
+            self.retile_all(true)?;
+        }
 
         Ok(())
     }
 
+    pub fn health(&self) -> Health {
+        let mut managed_windows = 0;
+        for monitor in self.monitors() {
+            for workspace in monitor.workspaces() {
+                managed_windows += workspace
+                    .containers()
+                    .iter()
+                    .map(|container| container.windows().len())
+                    .sum::<usize>();
+
+                if let Some(container) = workspace.monocle_container() {
+                    managed_windows += container.windows().len();
+                }
+
+                if workspace.maximized_window().is_some() {
+                    managed_windows += 1;
+                }
+
+                managed_windows += workspace.floating_windows().len();
+            }
+        }
+
+        let last_event_timestamp = match LAST_WINEVENT_TIMESTAMP.load(Ordering::SeqCst) {
+            0 => None,
+            timestamp => Option::from(timestamp),
+        };
+
+        Health {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_seconds: self.start_time.elapsed().as_secs(),
+            hooks_alive: WINEVENT_HOOK.load(Ordering::SeqCst) != 0 && watchdog::hooks_alive(),
+            managed_windows,
+            last_event_timestamp,
+            pending_events: self.incoming_events.lock().len(),
+            dropped_messages: DROPPED_MESSAGES.load(Ordering::SeqCst),
+            coalesced_messages: COALESCED_MESSAGES.load(Ordering::SeqCst),
+        }
+    }
+
+    pub fn current_display_set(&self) -> String {
+        // Device names like \\.\DISPLAY1 are reassigned by Windows whenever displays are
+        // reconnected, so the device interface path is used where it is available
+        let mut devices = self
+            .monitors()
+            .iter()
+            .map(|monitor| {
+                monitor
+                    .device_id()
+                    .as_deref()
+                    .unwrap_or_else(|| monitor.device().as_str())
+            })
+            .collect::<Vec<_>>();
+
+        devices.sort_unstable();
+
+        // FNV-1a is used here instead of the std hasher because the identifier needs to remain
+        // stable across komorebi builds for users to be able to name their profile files after it
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in devices.join(";").bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        format!("{:016x}", hash)
+    }
+
+    /// Tracks fullscreen windows on each monitor, resuming tiling on any monitor whose
+    /// fullscreen window has exited. Returns true if the event came from a fullscreen window and
+    /// should not be handled any further.
     #[tracing::instrument(skip(self))]
-    pub fn enforce_workspace_rules(&mut self) -> Result<()> {
-        let mut to_move = vec![];
+    pub fn reconcile_fullscreen_windows(&mut self, event: WindowManagerEvent) -> Result<bool> {
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
 
-        let focused_monitor_idx = self.focused_monitor_idx();
-        let focused_workspace_idx = self
+        for monitor in self.monitors_mut() {
+            if let Some(hwnd) = monitor.fullscreen_window() {
+                if !(Window { hwnd }).is_fullscreen().unwrap_or_default() {
+                    tracing::info!("fullscreen window has exited, resuming tiling on monitor");
+                    monitor.set_fullscreen_window(None);
+                    monitor.update_focused_workspace(offset, &invisible_borders)?;
+                }
+            }
+        }
+
+        let window = event.window();
+        match event {
+            WindowManagerEvent::FocusChange(..)
+            | WindowManagerEvent::Show(..)
+            | WindowManagerEvent::MoveResizeEnd(..)
+            | WindowManagerEvent::Manage(_) => {
+                if window.is_fullscreen()? {
+                    let monitor_idx = self
+                        .monitor_idx_from_window(window)
+                        .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+                    let monitor = self
+                        .monitors_mut()
+                        .get_mut(monitor_idx)
+                        .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+                    if monitor.fullscreen_window().is_none() {
+                        tracing::info!("fullscreen window detected, pausing tiling on monitor");
+                    }
+
+                    monitor.set_fullscreen_window(Option::from(window.hwnd));
+                    return Ok(true);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(self
             .monitors()
-            .get(focused_monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor with that index"))?
-            .focused_workspace_idx();
+            .iter()
+            .any(|monitor| monitor.fullscreen_window() == Option::from(window.hwnd)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn reconcile_display_set(&mut self) {
+        let display_set = self.current_display_set();
+        if self.display_set.as_deref() == Some(display_set.as_str()) {
+            return;
+        }
+
+        tracing::info!("display set changed: {}", display_set);
+        self.display_set = Option::from(display_set.clone());
+
+        thread::spawn(move || {
+            if let Err(error) = load_display_profile(&display_set) {
+                tracing::error!("could not load display profile: {}", error);
+            }
+        });
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_monitor_group(
+        &mut self,
+        primary_idx: usize,
+        secondary_idx: usize,
+        bezel_gap: i32,
+    ) -> Result<()> {
+        tracing::info!("grouping monitors");
+
+        if primary_idx == secondary_idx {
+            return Err(anyhow!("a monitor cannot be grouped with itself"));
+        }
+
+        let secondary = self
+            .monitors()
+            .get(secondary_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        if secondary.group().is_some() || secondary.grouped_into().is_some() {
+            return Err(anyhow!("this monitor is already part of a monitor group"));
+        }
+
+        let secondary_id = secondary.id();
+        let secondary_work_area = *secondary.work_area_size();
+
+        let primary = self
+            .monitors()
+            .get(primary_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        if primary.grouped_into().is_some() || primary.group().is_some() {
+            return Err(anyhow!("this monitor is already part of a monitor group"));
+        }
+
+        let primary_id = primary.id();
+        let group = MonitorGroup::new(
+            secondary_id,
+            bezel_gap,
+            primary.work_area_size(),
+            &secondary_work_area,
+        )?;
+
+        // Any windows on the secondary monitor's focused workspace are now tiled by the group
+        let mut containers = vec![];
+        let secondary = self
+            .monitors_mut()
+            .get_mut(secondary_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        secondary.set_grouped_into(Option::from(primary_id));
+        if let Some(workspace) = secondary.focused_workspace_mut() {
+            while let Some(container) = workspace.remove_focused_container() {
+                containers.push(container);
+            }
+        }
+
+        let primary = self
+            .monitors_mut()
+            .get_mut(primary_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        primary.set_group(Option::from(group));
+        for container in containers {
+            primary.add_container(container)?;
+        }
+
+        self.retile_all(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn remove_monitor_group(&mut self, primary_idx: usize) -> Result<()> {
+        tracing::info!("removing monitor group");
+
+        let primary = self
+            .monitors_mut()
+            .get_mut(primary_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        if primary.group().is_none() {
+            return Err(anyhow!(
+                "this monitor is not the primary monitor of a group"
+            ));
+        }
+
+        let primary_id = primary.id();
+        primary.set_group(None);
+
+        for monitor in self.monitors_mut() {
+            if monitor.grouped_into() == Option::from(primary_id) {
+                monitor.set_grouped_into(None);
+            }
+        }
+
+        self.retile_all(false)
+    }
+
+    // Recalculates the combined work area of every monitor group, dissolving any group which
+    // has lost one of its monitors. Returns true if any group changed.
+    fn reconcile_monitor_groups(&mut self) -> bool {
+        let work_areas: HashMap<isize, Rect> = self
+            .monitors()
+            .iter()
+            .map(|monitor| (monitor.id(), *monitor.work_area_size()))
+            .collect();
+
+        let mut changed = false;
+        let mut dissolved = vec![];
+
+        for monitor in self.monitors_mut() {
+            if let Some(group) = monitor.group() {
+                let reconciled = work_areas.get(&group.secondary).and_then(|secondary| {
+                    MonitorGroup::new(
+                        group.secondary,
+                        group.bezel_gap,
+                        monitor.work_area_size(),
+                        secondary,
+                    )
+                    .ok()
+                });
+
+                if reconciled.is_none() {
+                    tracing::info!("dissolving monitor group");
+                    dissolved.push(monitor.id());
+                }
+
+                if reconciled.map(|group| group.work_area_size)
+                    != Option::from(group.work_area_size)
+                {
+                    monitor.set_group(reconciled);
+                    changed = true;
+                }
+            }
+        }
+
+        for monitor in self.monitors_mut() {
+            if let Some(primary) = monitor.grouped_into() {
+                if dissolved.contains(&primary) || !work_areas.contains_key(&primary) {
+                    monitor.set_grouped_into(None);
+                    changed = true;
+                }
+            }
+        }
+
+        changed
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn enforce_workspace_rules(&mut self) -> Result<()> {
+        self.sweep_workspace_rules(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn apply_workspace_rules(&mut self) -> Result<()> {
+        tracing::info!("applying workspace rules to all managed windows");
+        self.sweep_workspace_rules(true)
+    }
+
+    // Spawn reservations claimed by any of the windows are consumed here, and take precedence
+    // over any matching workspace rules
+    fn workspace_rule_operations(
+        &mut self,
+        all_windows: bool,
+    ) -> Result<Vec<EnforceWorkspaceRuleOp>> {
+        let mut to_move = vec![];
 
+        let mut spawn_reservations = std::mem::take(&mut self.spawn_reservations);
         let workspace_rules = WORKSPACE_RULES.lock();
         // Go through all the monitors and workspaces
         for (i, monitor) in self.monitors().iter().enumerate() {
             for (j, workspace) in monitor.workspaces().iter().enumerate() {
                 // And all the visible windows (at the top of a container)
-                for window in workspace.visible_windows().into_iter().flatten() {
-                    // If the executable names or titles of any of those windows are in our rules map
-                    if let Some((monitor_idx, workspace_idx)) = workspace_rules.get(&window.exe()?)
-                    {
-                        tracing::info!(
-                            "{} should be on monitor {}, workspace {}",
-                            window.title()?,
-                            *monitor_idx,
-                            *workspace_idx
-                        );
-
-                        // Create an operation outline and save it for later in the fn
-                        to_move.push(EnforceWorkspaceRuleOp {
-                            hwnd: window.hwnd,
-                            origin_monitor_idx: i,
-                            origin_workspace_idx: j,
-                            target_monitor_idx: *monitor_idx,
-                            target_workspace_idx: *workspace_idx,
-                        });
-                    } else if let Some((monitor_idx, workspace_idx)) =
-                        workspace_rules.get(&window.title()?)
+                let windows: Vec<&Window> = if all_windows {
+                    workspace
+                        .containers()
+                        .iter()
+                        .flat_map(|container| container.windows().iter())
+                        .collect()
+                } else {
+                    workspace.visible_windows().into_iter().flatten().collect()
+                };
+
+                for window in windows {
+                    // Windows with a claimed spawn reservation are placed once, ahead of any rules
+                    let target = if let Some(idx) = spawn_reservations
+                        .iter()
+                        .position(|reservation| reservation.hwnd == Some(window.hwnd))
                     {
-                        tracing::info!(
-                            "{} should be on monitor {}, workspace {}",
-                            window.title()?,
-                            *monitor_idx,
-                            *workspace_idx
-                        );
+                        let reservation = spawn_reservations.remove(idx);
+                        Option::from((reservation.monitor_idx, reservation.workspace_idx))
+                    } else {
+                        // If the executable names or titles of any of those windows are in our
+                        // rules map, or they were launched with a workspace rule on the command line
+                        let rule = workspace_rules
+                            .get(&window.exe()?)
+                            .or(workspace_rules.get(&window.title()?))
+                            .copied()
+                            .or_else(|| window.command_line_workspace_rule());
+
+                        if let Some((monitor_idx, workspace_idx)) = rule {
+                            tracing::info!(
+                                "{} should be on monitor {}, workspace {}",
+                                window.title()?,
+                                monitor_idx,
+                                workspace_idx
+                            );
+                        }
 
+                        rule
+                    };
+
+                    // Create an operation outline and save it for later
+                    if let Some((monitor_idx, workspace_idx)) = target {
                         to_move.push(EnforceWorkspaceRuleOp {
                             hwnd: window.hwnd,
                             origin_monitor_idx: i,
                             origin_workspace_idx: j,
-                            target_monitor_idx: *monitor_idx,
-                            target_workspace_idx: *workspace_idx,
+                            target_monitor_idx: monitor_idx,
+                            target_workspace_idx: workspace_idx,
                         });
                     }
                 }
             }
         }
 
+        drop(workspace_rules);
+        self.spawn_reservations = spawn_reservations;
+
+        Ok(to_move)
+    }
+
+    // When applying rules to every managed window, windows in the background of stacks are
+    // included, and windows will also be moved to the focused workspace if that is their target
+    fn sweep_workspace_rules(&mut self, all_windows: bool) -> Result<()> {
+        let focused_monitor_idx = self.focused_monitor_idx();
+        let focused_workspace_idx = self
+            .monitors()
+            .get(focused_monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor with that index"))?
+            .focused_workspace_idx();
+
+        let mut to_move = self.workspace_rule_operations(all_windows)?;
+
         // Only retain operations where the target is not the current workspace
-        to_move.retain(|op| !op.is_target(focused_monitor_idx, focused_workspace_idx));
+        if !all_windows {
+            to_move.retain(|op| !op.is_target(focused_monitor_idx, focused_workspace_idx));
+        }
+
         // Only retain operations where the rule has not already been enforced
         to_move.retain(|op| !op.is_enforced());
 
+        // Windows with changing titles can match different rules in quick succession, so unless
+        // rules are being applied explicitly, recently moved windows are left where they are
+        let flap_guard = Duration::from_secs(WORKSPACE_RULE_FLAP_GUARD_SECONDS);
+        self.workspace_rule_moves
+            .retain(|_, moved| moved.elapsed() < flap_guard);
+
+        if !all_windows {
+            to_move.retain(|op| !self.workspace_rule_moves.contains_key(&op.hwnd));
+        }
+
+        for op in &to_move {
+            self.workspace_rule_moves.insert(op.hwnd, Instant::now());
+        }
+
         let mut should_update_focused_workspace = false;
 
         // Parse the operation and remove any windows that are not placed according to their rules
@@ -431,6 +1020,12 @@ impl WindowManager {
                 .ok_or_else(|| anyhow!("there is no workspace with that index"))?;
 
             target_workspace.new_container_for_window(Window { hwnd: op.hwnd });
+
+            // Windows moved from a hidden workspace to the focused workspace need to be shown
+            if op.is_target(focused_monitor_idx, focused_workspace_idx) {
+                Window { hwnd: op.hwnd }.restore();
+                should_update_focused_workspace = true;
+            }
         }
 
         // Only re-tile the focused workspace if we need to
@@ -438,16 +1033,138 @@ impl WindowManager {
             self.update_focused_workspace(false)?;
         }
 
+        // Newly placed windows of applications with a follow rule pull focus along with them
+        if !all_windows {
+            if let Some(op) = to_move
+                .iter()
+                .rev()
+                .find(|op| Window { hwnd: op.hwnd }.should_follow())
+            {
+                tracing::info!(
+                    "following window to monitor {}, workspace {}",
+                    op.target_monitor_idx,
+                    op.target_workspace_idx
+                );
+                self.focus_window_by_hwnd(op.hwnd)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn spawn_into_workspace(
+        &mut self,
+        command: &str,
+        monitor_idx: usize,
+        workspace_idx: usize,
+    ) -> Result<()> {
+        tracing::info!("spawning command");
+
+        if self.monitors().get(monitor_idx).is_none() {
+            return Err(anyhow!("there is no monitor with that index"));
+        }
+
+        let child = Command::new("cmd").args(["/C", command]).spawn()?;
+
+        self.spawn_reservations.push(SpawnReservation {
+            process_id: child.id(),
+            monitor_idx,
+            workspace_idx,
+            hwnd: None,
+            spawned: Instant::now(),
+        });
+
         Ok(())
     }
 
+    /// Claim the oldest pending spawn reservation made for the process of `window` or any of the
+    /// processes which led to it being launched, returning whether one was claimed
+    pub fn claim_spawn_reservation(&mut self, window: Window) -> bool {
+        let timeout = Duration::from_secs(SPAWN_RESERVATION_TIMEOUT_SECONDS);
+        self.spawn_reservations
+            .retain(|reservation| reservation.spawned.elapsed() < timeout);
+
+        // Walking the process tree is expensive, so only do it if there is something to claim
+        if self
+            .spawn_reservations
+            .iter()
+            .all(|reservation| reservation.hwnd.is_some())
+        {
+            return false;
+        }
+
+        let process_id = window.process_id();
+        let ancestors = window.ancestor_process_ids();
+
+        let reservation = self.spawn_reservations.iter_mut().find(|reservation| {
+            reservation.hwnd.is_none()
+                && (reservation.process_id == process_id
+                    || Pid::try_from(reservation.process_id)
+                        .map_or(false, |pid| ancestors.contains(&pid)))
+        });
+
+        match reservation {
+            None => false,
+            Some(reservation) => {
+                tracing::info!(
+                    "placing spawned window on monitor {}, workspace {}",
+                    reservation.monitor_idx,
+                    reservation.workspace_idx
+                );
+
+                reservation.hwnd = Option::from(window.hwnd);
+                true
+            }
+        }
+    }
+
+    /// Remove the first one-shot workspace rule which matches `window`, placing it with a claimed
+    /// spawn reservation so that it is moved once and then left alone, returning whether there
+    /// was one
+    pub fn claim_workspace_rule_once(&mut self, window: Window) -> bool {
+        let (monitor_idx, workspace_idx) = {
+            let mut rules = WORKSPACE_RULES_ONCE.lock();
+            match rules
+                .iter()
+                .position(|(identifier, id, _)| window.matches_identifier(identifier, id))
+            {
+                None => return false,
+                Some(idx) => rules.remove(idx).2,
+            }
+        };
+
+        tracing::info!(
+            "placing window with a one-shot workspace rule on monitor {}, workspace {}",
+            monitor_idx,
+            workspace_idx
+        );
+
+        self.spawn_reservations.push(SpawnReservation {
+            process_id: window.process_id(),
+            monitor_idx,
+            workspace_idx,
+            hwnd: Option::from(window.hwnd),
+            spawned: Instant::now(),
+        });
+
+        true
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn retile_all(&mut self, preserve_resize_dimensions: bool) -> Result<()> {
+        let _guard = OperationGuard::start();
+        let started = Instant::now();
         let invisible_borders = self.invisible_borders;
         let offset = self.work_area_offset;
 
         for monitor in self.monitors_mut() {
-            let work_area = *monitor.work_area_size();
+            if monitor.fullscreen_window().is_some() {
+                continue;
+            }
+
+            let work_area = monitor.tiling_area();
+            let group = monitor.group();
             let workspace = monitor
                 .focused_workspace_mut()
                 .ok_or_else(|| anyhow!("there is no workspace"))?;
@@ -459,16 +1176,497 @@ impl WindowManager {
                 }
             }
 
-            workspace.update(&work_area, offset, &invisible_borders)?;
+            workspace.update(&work_area, offset, &invisible_borders, group)?;
         }
 
+        self.performance_counters.record_retile(started);
+
         Ok(())
     }
 
-    #[tracing::instrument(skip(self))]
-    pub fn manage_focused_window(&mut self) -> Result<()> {
-        let hwnd = WindowsApi::foreground_window()?;
-        let event = WindowManagerEvent::Manage(Window { hwnd });
+    /// Run any monitor updates which were deferred by retile throttling and are now due, and
+    /// position the next batch of windows on any workspace which is being updated time-sliced
+    pub fn flush_pending_retiles(&mut self) -> Result<()> {
+        let _guard = OperationGuard::start();
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+        let mut still_pending = false;
+
+        for monitor in self.monitors_mut() {
+            if monitor.pending_update() {
+                monitor.update_focused_workspace(offset, &invisible_borders)?;
+            } else if let Some(workspace) = monitor.focused_workspace_mut() {
+                workspace.apply_pending_positions(&invisible_borders);
+            }
+
+            still_pending |= monitor.pending_update() || monitor.has_pending_positions();
+        }
+
+        RETILE_PENDING.store(still_pending, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn prune_dead_windows(&mut self) -> Result<Vec<NotificationEvent>> {
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+        let mut events = vec![];
+
+        for (i, monitor) in self.monitors_mut().iter_mut().enumerate() {
+            let focused_workspace_idx = monitor.focused_workspace_idx();
+            let mut should_update = false;
+
+            for (j, workspace) in monitor.workspaces_mut().iter_mut().enumerate() {
+                for hwnd in workspace.prune_dead_windows(j == focused_workspace_idx) {
+                    tracing::info!(
+                        "pruned dead hwnd {} from monitor: {}, workspace: {}",
+                        hwnd,
+                        i,
+                        j
+                    );
+
+                    should_update = should_update || j == focused_workspace_idx;
+                    events.push(NotificationEvent::OrphanRemoved {
+                        hwnd,
+                        monitor_idx: i,
+                        workspace_idx: j,
+                    });
+                }
+            }
+
+            if should_update {
+                monitor.update_focused_workspace(offset, &invisible_borders)?;
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Evaluates whether a window is managed, and the rules that apply to it, again after its
+    /// application has changed its class or styles. Returns true if the event which revealed the
+    /// change should not be processed any further.
+    #[tracing::instrument(skip(self))]
+    pub fn reconcile_morphed_window(&mut self, window: Window) -> Result<bool> {
+        let signature = match window.morph_signature() {
+            Ok(signature) => signature,
+            Err(_) => return Ok(false),
+        };
+
+        let previous = WINDOW_SIGNATURES
+            .lock()
+            .insert(window.hwnd, signature.clone());
+
+        if previous.map_or(true, |previous| previous == signature) {
+            return Ok(false);
+        }
+
+        let (monitor_idx, workspace_idx) = match self.window_location(window.hwnd) {
+            Some(location) => location,
+            // A window which was refused, like a splash screen, can become the main window of
+            // its application without ever being shown again
+            None if window.should_manage(None)? => {
+                tracing::info!("managing window which is manageable after a class or style change");
+                WINEVENT_CALLBACK_CHANNEL
+                    .lock()
+                    .0
+                    .send(WindowManagerEvent::Manage(window))?;
+
+                return Ok(true);
+            }
+            None => return Ok(false),
+        };
+
+        let focused_location = self.focused_location();
+        let is_visible = self.monitors().get(monitor_idx).map_or(false, |monitor| {
+            monitor.focused_workspace_idx() == workspace_idx
+        });
+
+        let workspace = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .and_then(|monitor| monitor.workspaces_mut().get_mut(workspace_idx))
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        // Floating windows are not tiled, so there is nothing to unmanage or move
+        if workspace
+            .floating_windows()
+            .iter()
+            .any(|w| w.hwnd == window.hwnd)
+        {
+            return Ok(false);
+        }
+
+        let unmanage = !window.should_manage(None)?;
+        if unmanage {
+            tracing::info!("unmanaging window which is unmanageable after a class or style change");
+            workspace.remove_window(window.hwnd)?;
+        } else {
+            // The window may now belong to an app group, or to a different one
+            tracing::info!("applying rules again to window after a class or style change");
+            workspace.consolidate_app_groups(is_visible)?;
+        }
+
+        if focused_location == (monitor_idx, workspace_idx) {
+            self.update_focused_workspace(false)?;
+        }
+
+        if !unmanage {
+            self.enforce_workspace_rules()?;
+        }
+
+        Ok(unmanage)
+    }
+
+    pub fn window_location(&self, hwnd: isize) -> Option<(usize, usize)> {
+        for (i, monitor) in self.monitors().iter().enumerate() {
+            for (j, workspace) in monitor.workspaces().iter().enumerate() {
+                if workspace.contains_window(hwnd) {
+                    return Option::from((i, j));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn focused_location(&self) -> (usize, usize) {
+        let monitor_idx = self.focused_monitor_idx();
+        let workspace_idx = self
+            .focused_monitor()
+            .map_or(0, Monitor::focused_workspace_idx);
+
+        (monitor_idx, workspace_idx)
+    }
+
+    /// The notification for an event which has been handled, where `newly_managed` is whether
+    /// the window was not managed before the event was handled
+    pub fn notification_event(
+        &self,
+        event: WindowManagerEvent,
+        newly_managed: bool,
+    ) -> NotificationEvent {
+        let window = event.window();
+        let (monitor_idx, workspace_idx) = self
+            .window_location(window.hwnd)
+            .unwrap_or_else(|| self.focused_location());
+
+        match event {
+            WindowManagerEvent::FocusChange(..) | WindowManagerEvent::Raise(_) => {
+                NotificationEvent::FocusChange {
+                    window: window.into(),
+                    monitor_idx,
+                    workspace_idx,
+                }
+            }
+            WindowManagerEvent::Show(..) | WindowManagerEvent::Manage(_) if newly_managed => {
+                NotificationEvent::WindowManaged {
+                    window: window.into(),
+                    monitor_idx,
+                    workspace_idx,
+                }
+            }
+            WindowManagerEvent::Show(..) | WindowManagerEvent::Manage(_) => {
+                NotificationEvent::WindowShown {
+                    window: window.into(),
+                    monitor_idx,
+                    workspace_idx,
+                }
+            }
+            WindowManagerEvent::Destroy(..)
+            | WindowManagerEvent::Hide(..)
+            | WindowManagerEvent::Minimize(..)
+            | WindowManagerEvent::Unmanage(_) => NotificationEvent::WindowUnmanaged {
+                window: window.into(),
+            },
+            WindowManagerEvent::MoveResizeStart(..) | WindowManagerEvent::MouseCapture(..) => {
+                NotificationEvent::WindowMoveResizeStart {
+                    window: window.into(),
+                }
+            }
+            WindowManagerEvent::MoveResizeEnd(..) => NotificationEvent::WindowMoveResizeEnd {
+                window: window.into(),
+                monitor_idx,
+                workspace_idx,
+            },
+            WindowManagerEvent::TitleChange(..) => NotificationEvent::WindowTitleChange {
+                window: window.into(),
+                monitor_idx,
+                workspace_idx,
+            },
+            WindowManagerEvent::MonitorPoll(..) => NotificationEvent::DisplayChange,
+        }
+    }
+
+    pub fn command_notification_event(&self, message: SocketMessage) -> NotificationEvent {
+        let (monitor_idx, workspace_idx) = self.focused_location();
+
+        match message {
+            SocketMessage::FocusWindow(_)
+            | SocketMessage::CycleFocusWindow(_)
+            | SocketMessage::FocusContainerById(_) => match self.focused_window() {
+                Ok(window) => NotificationEvent::FocusChange {
+                    window: (*window).into(),
+                    monitor_idx,
+                    workspace_idx,
+                },
+                Err(_) => NotificationEvent::Command(message),
+            },
+            SocketMessage::FocusWorkspaceNumber(_)
+            | SocketMessage::FocusMonitorWorkspaceNumber(..)
+            | SocketMessage::CycleFocusWorkspace(_)
+            | SocketMessage::NewWorkspace => NotificationEvent::WorkspaceFocus {
+                monitor_idx,
+                workspace_idx,
+            },
+            SocketMessage::FocusMonitorNumber(_) | SocketMessage::CycleFocusMonitor(_) => {
+                NotificationEvent::MonitorFocus { monitor_idx }
+            }
+            SocketMessage::ChangeLayout(_)
+            | SocketMessage::ChangeLayoutCustom(_)
+            | SocketMessage::CycleLayout(_)
+            | SocketMessage::FlipLayout(_) => {
+                self.layout_change(monitor_idx, workspace_idx, message)
+            }
+            SocketMessage::WorkspaceLayout(monitor_idx, workspace_idx, _)
+            | SocketMessage::WorkspaceLayoutCustom(monitor_idx, workspace_idx, _) => {
+                self.layout_change(monitor_idx, workspace_idx, message)
+            }
+            SocketMessage::TogglePause => NotificationEvent::PauseToggle {
+                paused: self.is_paused,
+            },
+            _ => NotificationEvent::Command(message),
+        }
+    }
+
+    /// The monocle and maximized state of every workspace, as (monitor, workspace, monocle,
+    /// maximized), so that changes made by an event or command can be notified afterwards
+    pub fn maximized_states(&self) -> Vec<(usize, usize, bool, bool)> {
+        let mut states = vec![];
+        for (i, monitor) in self.monitors().iter().enumerate() {
+            for (j, workspace) in monitor.workspaces().iter().enumerate() {
+                states.push((
+                    i,
+                    j,
+                    workspace.has_monocle_container(),
+                    workspace.has_maximized_window(),
+                ));
+            }
+        }
+
+        states
+    }
+
+    pub fn notify_maximized_state_changes(
+        &self,
+        before: &[(usize, usize, bool, bool)],
+    ) -> Result<()> {
+        for (monitor_idx, workspace_idx, monocle, maximized) in self.maximized_states() {
+            let previous = before
+                .iter()
+                .find(|(i, j, ..)| *i == monitor_idx && *j == workspace_idx)
+                .map_or((false, false), |(.., monocle, maximized)| {
+                    (*monocle, *maximized)
+                });
+
+            if previous != (monocle, maximized) {
+                notify_subscribers(&serde_json::to_string(&Notification {
+                    event: NotificationEvent::MaximizedStateChange {
+                        monitor_idx,
+                        workspace_idx,
+                        monocle,
+                        maximized,
+                    },
+                    state: self.into(),
+                    stack: self.focused_stack_indicator(),
+                })?)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn layout_change(
+        &self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        message: SocketMessage,
+    ) -> NotificationEvent {
+        self.monitors()
+            .get(monitor_idx)
+            .and_then(|monitor| monitor.workspaces().get(workspace_idx))
+            .map_or(NotificationEvent::Command(message), |workspace| {
+                NotificationEvent::LayoutChange {
+                    monitor_idx,
+                    workspace_idx,
+                    layout: workspace.layout().clone(),
+                }
+            })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn reconcile(&mut self) -> Result<()> {
+        tracing::info!("reconciling state with the desktop");
+
+        self.reconcile_monitors()?;
+
+        for monitor in self.monitors_mut() {
+            for workspace in monitor.workspaces_mut() {
+                let reaped_orphans = workspace.reap_orphans()?;
+                if reaped_orphans.0 > 0 || reaped_orphans.1 > 0 {
+                    tracing::info!(
+                        "reaped {} orphan window(s) and {} orphaned container(s)",
+                        reaped_orphans.0,
+                        reaped_orphans.1,
+                    );
+                }
+            }
+        }
+
+        // Adopt any manageable windows that we have somehow lost track of
+        let untracked = WindowsApi::manageable_windows()?
+            .into_iter()
+            .filter(|window| {
+                !self.monitors().iter().any(|monitor| {
+                    monitor
+                        .workspaces()
+                        .iter()
+                        .any(|workspace| workspace.contains_window(window.hwnd))
+                })
+            })
+            .collect();
+
+        self.adopt_windows(untracked)?;
+
+        self.enforce_workspace_rules()?;
+        self.retile_all(true)
+    }
+
+    // EnumWindows returns windows in z-order, which changes all the time, so windows are sorted
+    // before being adopted to make sure that the same set of windows always ends up in the same
+    // place. Windows go to the workspace given by a workspace rule if there is one, and otherwise
+    // to the focused workspace of the monitor that they are physically on.
+    fn adopt_windows(&mut self, windows: Vec<Window>) -> Result<()> {
+        let mut windows = windows
+            .into_iter()
+            .map(|window| {
+                let exe = window.exe().unwrap_or_default();
+                let title = window.title().unwrap_or_default();
+                (exe, title, window)
+            })
+            .collect::<Vec<_>>();
+
+        // Looking up the exe of a window opens its process, so this is only done once per window
+        let mut exes: HashMap<isize, String> = windows
+            .iter()
+            .map(|(exe, _, window)| (window.hwnd, exe.clone()))
+            .collect();
+
+        windows.sort_by(|(a_exe, a_title, a), (b_exe, b_title, b)| {
+            (a_exe, a_title, a.hwnd).cmp(&(b_exe, b_title, b.hwnd))
+        });
+
+        let append = matches!(
+            self.window_container_behaviour,
+            WindowContainerBehaviour::Append
+        );
+
+        for (exe, title, window) in windows {
+            icons::extract(window);
+
+            let rule = {
+                let workspace_rules = WORKSPACE_RULES.lock();
+                workspace_rules
+                    .get(&exe)
+                    .or_else(|| workspace_rules.get(&title))
+                    .copied()
+                    .or_else(|| window.command_line_workspace_rule())
+            };
+
+            let (monitor_idx, workspace_idx) = match rule {
+                Some((monitor_idx, workspace_idx))
+                    if self.monitors().get(monitor_idx).is_some() =>
+                {
+                    (monitor_idx, workspace_idx)
+                }
+                _ => {
+                    let monitor_idx = self
+                        .monitor_idx_from_window(window)
+                        .unwrap_or_else(|| self.focused_monitor_idx());
+
+                    let workspace_idx = self
+                        .monitors()
+                        .get(monitor_idx)
+                        .map_or(0, Monitor::focused_workspace_idx);
+
+                    (monitor_idx, workspace_idx)
+                }
+            };
+
+            let monitor = self
+                .monitors_mut()
+                .get_mut(monitor_idx)
+                .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+            monitor.ensure_workspace_count(workspace_idx + 1);
+            let is_visible = monitor.focused_workspace_idx() == workspace_idx;
+
+            let workspace = monitor
+                .workspaces_mut()
+                .get_mut(workspace_idx)
+                .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+            let same_exe_container = if append {
+                workspace.containers_mut().iter_mut().find(|container| {
+                    container.windows().iter().any(|w| {
+                        exes.entry(w.hwnd)
+                            .or_insert_with(|| w.exe().unwrap_or_default())
+                            .as_str()
+                            == exe
+                    })
+                })
+            } else {
+                None
+            };
+
+            tracing::info!(
+                "adopting window {} on monitor {}, workspace {}",
+                window.hwnd,
+                monitor_idx,
+                workspace_idx
+            );
+
+            if let Some(container) = same_exe_container {
+                // The first window of the exe stays at the top of the stack
+                container.windows_mut().push_back(window);
+                window.hide();
+            } else {
+                workspace.new_container_for_window(window);
+                if !is_visible {
+                    window.hide();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn manage_focused_window(&mut self) -> Result<()> {
+        let hwnd = WindowsApi::foreground_window()?;
+
+        let is_managed = self.monitors().iter().any(|monitor| {
+            monitor
+                .workspaces()
+                .iter()
+                .any(|workspace| workspace.contains_window(hwnd))
+        });
+
+        if !is_managed {
+            learning::record(Window { hwnd }, SuggestionReason::ManagedManually);
+        }
+
+        let event = WindowManagerEvent::Manage(Window { hwnd });
         Ok(WINEVENT_CALLBACK_CHANNEL.lock().0.send(event)?)
     }
 
@@ -481,7 +1679,47 @@ impl WindowManager {
 
     #[tracing::instrument(skip(self))]
     pub fn raise_window_at_cursor_pos(&mut self) -> Result<()> {
-        let mut hwnd = WindowsApi::window_at_cursor_pos()?;
+        if operation_guard::settling() {
+            tracing::trace!("waiting for the focus events of the last retile to settle");
+            return Ok(());
+        }
+
+        let point = WindowsApi::cursor_pos()?;
+        let cursor = (point.x, point.y);
+
+        let (monitor_idx, work_area) = match self.monitor_work_area_at_point(cursor) {
+            None => {
+                tracing::trace!("cursor is not within the work area of any monitor");
+                return Ok(());
+            }
+            Some(found) => found,
+        };
+
+        if let Some(monitor) = self.monitors().get(monitor_idx) {
+            if monitor.fullscreen_window().is_some() {
+                return Ok(());
+            }
+
+            if monitor
+                .focused_workspace()
+                .and_then(Workspace::focus_follows_mouse)
+                == Some(false)
+            {
+                tracing::trace!("focus follows mouse is disabled on this workspace");
+                return Ok(());
+            }
+        }
+
+        if monitor_idx != self.focused_monitor_idx() {
+            let mut inner = work_area;
+            inner.add_padding(Option::from(FOCUS_FOLLOWS_MOUSE_MONITOR_HYSTERESIS));
+            if !inner.contains_point(cursor) {
+                tracing::trace!("cursor is too close to the edge of another monitor");
+                return Ok(());
+            }
+        }
+
+        let mut hwnd = WindowsApi::window_from_point(point)?;
 
         if self.has_pending_raise_op
             || self.focused_window()?.hwnd == hwnd
@@ -576,6 +1814,49 @@ impl WindowManager {
         Ok(())
     }
 
+    /// Move every window of a container into a container on another workspace, stacking them
+    #[tracing::instrument(skip(self))]
+    pub fn transfer_container_into_stack(
+        &mut self,
+        origin: (usize, usize, usize),
+        target: (usize, usize, usize),
+    ) -> Result<()> {
+        let (origin_monitor_idx, origin_workspace_idx, origin_container_idx) = origin;
+        let (target_monitor_idx, target_workspace_idx, target_container_idx) = target;
+
+        let origin_container = self
+            .monitors_mut()
+            .get_mut(origin_monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+            .workspaces_mut()
+            .get_mut(origin_workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace at this index"))?
+            .remove_container(origin_container_idx)
+            .ok_or_else(|| anyhow!("there is no container at this index"))?;
+
+        let target_workspace = self
+            .monitors_mut()
+            .get_mut(target_monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+            .workspaces_mut()
+            .get_mut(target_workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace at this index"))?;
+
+        let target_container = target_workspace
+            .containers_mut()
+            .get_mut(target_container_idx)
+            .ok_or_else(|| anyhow!("there is no container at this index"))?;
+
+        for window in origin_container.windows() {
+            target_container.add_window(*window);
+        }
+
+        target_container.load_focused_window();
+        target_workspace.focus_container(target_container_idx);
+
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn swap_containers(
         &mut self,
@@ -629,14 +1910,25 @@ impl WindowManager {
 
     #[tracing::instrument(skip(self))]
     pub fn update_focused_workspace(&mut self, follow_focus: bool) -> Result<()> {
+        if self.hydrating {
+            tracing::trace!("deferring update until startup events have been processed");
+            return Ok(());
+        }
+
         tracing::info!("updating");
 
+        let started = Instant::now();
         let invisible_borders = self.invisible_borders;
         let offset = self.work_area_offset;
 
-        self.focused_monitor_mut()
-            .ok_or_else(|| anyhow!("there is no monitor"))?
-            .update_focused_workspace(offset, &invisible_borders)?;
+        {
+            let _guard = OperationGuard::start();
+            self.focused_monitor_mut()
+                .ok_or_else(|| anyhow!("there is no monitor"))?
+                .update_focused_workspace(offset, &invisible_borders)?;
+        }
+
+        self.performance_counters.record_retile(started);
 
         if follow_focus {
             if let Some(window) = self.focused_workspace()?.maximized_window() {
@@ -701,9 +1993,10 @@ impl WindowManager {
                     let unaltered = layout.calculate(
                         &work_area,
                         len,
-                        workspace.container_padding(),
+                        workspace.layout_container_padding(),
                         workspace.layout_flip(),
                         &[],
+                        workspace.toggled_splits(),
                     );
 
                     let mut direction = direction;
@@ -758,6 +2051,82 @@ impl WindowManager {
         Ok(())
     }
 
+    /// Resize the focused container to the next preset share of the split it makes with its
+    /// neighbour, preferring horizontal splits over vertical ones
+    #[tracing::instrument(skip(self))]
+    pub fn cycle_split_ratio(&mut self, direction: CycleDirection) -> Result<()> {
+        tracing::info!("cycling split ratio");
+
+        let workspace = self.focused_workspace()?;
+        let len = NonZeroUsize::new(workspace.containers().len())
+            .ok_or_else(|| anyhow!("there must be at least one container"))?;
+        let focused_idx = workspace.focused_container_idx();
+
+        let (edge, neighbour_idx) = [
+            OperationDirection::Right,
+            OperationDirection::Left,
+            OperationDirection::Down,
+            OperationDirection::Up,
+        ]
+        .into_iter()
+        .find_map(|edge| {
+            edge.destination(
+                workspace.layout().as_boxed_direction().as_ref(),
+                workspace.layout_flip(),
+                focused_idx,
+                len,
+            )
+            .map(|idx| (edge, idx))
+        })
+        .ok_or_else(|| anyhow!("there is no split for this container"))?;
+
+        let (focused, neighbour) = match (
+            workspace.latest_layout().get(focused_idx),
+            workspace.latest_layout().get(neighbour_idx),
+        ) {
+            (Some(focused), Some(neighbour)) => (*focused, *neighbour),
+            _ => return Err(anyhow!("there is no last layout")),
+        };
+
+        let (size, total) = match edge {
+            OperationDirection::Left | OperationDirection::Right => {
+                (focused.right, focused.right + neighbour.right)
+            }
+            OperationDirection::Up | OperationDirection::Down => {
+                (focused.bottom, focused.bottom + neighbour.bottom)
+            }
+        };
+
+        if total <= 0 {
+            return Err(anyhow!("there is no space to split"));
+        }
+
+        let current = size * 100 / total;
+        let (closest_idx, closest) = SPLIT_RATIO_PRESETS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, preset)| (**preset - current).abs())
+            .ok_or_else(|| anyhow!("there are no split ratio presets"))?;
+
+        // Start from the beginning of the presets if the split has been resized by hand
+        let next_idx = if (closest - current).abs() > 2 {
+            0
+        } else {
+            let presets = NonZeroUsize::new(SPLIT_RATIO_PRESETS.len())
+                .ok_or_else(|| anyhow!("there are no split ratio presets"))?;
+            direction.next_idx(closest_idx, presets)
+        };
+
+        let delta = total * SPLIT_RATIO_PRESETS[next_idx] / 100 - size;
+        let sizing = if delta < 0 {
+            Sizing::Decrease
+        } else {
+            Sizing::Increase
+        };
+
+        self.resize_window(edge, sizing, delta.abs(), true)
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn restore_all_windows(&mut self) {
         tracing::info!("restoring all hidden windows");
@@ -769,11 +2138,298 @@ impl WindowManager {
                         window.restore();
                     }
                 }
+
+                for window in workspace.swallowed_windows().values() {
+                    window.restore();
+                }
             }
         }
+
+        // Taskbars hidden for a workspace would otherwise stay hidden after komorebi exits
+        for hwnd in WindowsApi::taskbar_windows() {
+            WindowsApi::show_window_without_activating(hwnd);
+        }
     }
 
-    #[tracing::instrument(skip(self))]
+    /// Put the window swallowed by `hwnd` back in its place, wherever either of them has been
+    /// moved since, returning whether `hwnd` had swallowed a window and has been replaced
+    pub fn restore_swallowed_window(&mut self, hwnd: isize) -> Result<bool> {
+        let swallowed = self
+            .monitors_mut()
+            .iter_mut()
+            .flat_map(|monitor| monitor.workspaces_mut().iter_mut())
+            .find_map(|workspace| workspace.swallowed_windows_mut().remove(&hwnd));
+
+        let swallowed = match swallowed {
+            Some(swallowed) if swallowed.is_window() => swallowed,
+            _ => return Ok(false),
+        };
+
+        for monitor in self.monitors_mut() {
+            let focused_workspace_idx = monitor.focused_workspace_idx();
+            for (workspace_idx, workspace) in monitor.workspaces_mut().iter_mut().enumerate() {
+                if workspace.replace_swallowing_window(hwnd, swallowed)? {
+                    // The windows of hidden workspaces are restored when they are next shown
+                    if workspace_idx == focused_workspace_idx {
+                        swallowed.restore();
+                    }
+
+                    return Ok(true);
+                }
+            }
+        }
+
+        // The window which swallowed it is not tiled anywhere any more
+        swallowed.restore();
+        self.focused_workspace_mut()?
+            .new_container_for_window(swallowed);
+
+        Ok(false)
+    }
+
+    fn restart_state(&self) -> RestartState {
+        let hwnds = |container: &Container| -> Vec<isize> {
+            container
+                .windows()
+                .iter()
+                .map(|window| window.hwnd)
+                .collect()
+        };
+
+        let mut monitors = vec![];
+        for monitor in self.monitors() {
+            let mut workspaces = vec![];
+            for workspace in monitor.workspaces() {
+                let mut containers: Vec<Vec<isize>> =
+                    workspace.containers().iter().map(hwnds).collect();
+
+                // Monocle and maximized windows are handed over as regular containers
+                if let Some(container) = workspace.monocle_container() {
+                    let idx = workspace
+                        .monocle_container_restore_idx()
+                        .unwrap_or(containers.len())
+                        .min(containers.len());
+
+                    containers.insert(idx, hwnds(container));
+                }
+
+                if let Some(window) = workspace.maximized_window() {
+                    let idx = workspace
+                        .maximized_window_restore_idx()
+                        .unwrap_or(containers.len())
+                        .min(containers.len());
+
+                    containers.insert(idx, vec![window.hwnd]);
+                }
+
+                workspaces.push(RestartWorkspace {
+                    name: workspace.name().clone(),
+                    layout: workspace.layout().clone(),
+                    layout_flip: workspace.layout_flip(),
+                    resize_dimensions: workspace.resize_dimensions().clone(),
+                    workspace_padding: workspace.workspace_padding(),
+                    container_padding: workspace.container_padding(),
+                    tile: *workspace.tile(),
+                    containers,
+                    floating_windows: workspace
+                        .floating_windows()
+                        .iter()
+                        .map(|window| window.hwnd)
+                        .collect(),
+                    swallowed_windows: workspace
+                        .swallowed_windows()
+                        .iter()
+                        .map(|(hwnd, window)| (*hwnd, window.hwnd))
+                        .collect(),
+                    focused_container_idx: workspace.focused_container_idx(),
+                });
+            }
+
+            monitors.push(RestartMonitor {
+                id: monitor.id(),
+                focused_workspace_idx: monitor.focused_workspace_idx(),
+                workspaces,
+            });
+        }
+
+        RestartState {
+            focused_monitor_idx: self.focused_monitor_idx(),
+            monitors,
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn restart(&mut self) -> Result<()> {
+        tracing::info!("restarting");
+
+        let path = RestartState::path()?;
+        self.restart_state().save(&path)?;
+
+        // Hidden windows have to be visible for the new process to be able to adopt them
+        self.restore_all_windows();
+        for monitor in self.monitors() {
+            for workspace in monitor.workspaces() {
+                let monocle = workspace
+                    .monocle_container()
+                    .iter()
+                    .flat_map(Container::windows);
+                for window in workspace
+                    .floating_windows()
+                    .iter()
+                    .chain(workspace.maximized_window())
+                    .chain(monocle)
+                {
+                    window.restore();
+                }
+            }
+        }
+
+        let mut command = Command::new(std::env::current_exe()?);
+        if CUSTOM_FFM.load(Ordering::SeqCst) {
+            command.arg("--ffm");
+        }
+
+        command
+            .arg("--restore-state")
+            .arg(&path)
+            .arg("--await-process")
+            .arg(WindowsApi::current_process_id().to_string())
+            .spawn()?;
+
+        std::process::exit(0)
+    }
+
+    /// Rebuild the arrangement saved by the restart command from the windows adopted on startup
+    #[tracing::instrument(skip(self))]
+    pub fn apply_restart_state(&mut self, path: &Path) -> Result<()> {
+        tracing::info!("applying restart state");
+
+        let state = RestartState::load(path)?;
+
+        let handed_over = state
+            .monitors
+            .iter()
+            .flat_map(|monitor| monitor.workspaces.iter())
+            .flat_map(|workspace| {
+                workspace
+                    .containers
+                    .iter()
+                    .flatten()
+                    .chain(workspace.floating_windows.iter())
+                    .chain(
+                        workspace
+                            .swallowed_windows
+                            .iter()
+                            .map(|(_, swallowed)| swallowed),
+                    )
+            })
+            .copied()
+            .collect::<Vec<_>>();
+
+        // Take the handed over windows out of wherever they were adopted
+        for monitor in self.monitors_mut() {
+            for workspace in monitor.workspaces_mut() {
+                for hwnd in &handed_over {
+                    if workspace.contains_window(*hwnd) {
+                        workspace.remove_window(*hwnd)?;
+                    }
+                }
+            }
+        }
+
+        let mouse_follows_focus = self.mouse_follows_focus;
+
+        for (idx, restored_monitor) in state.monitors.into_iter().enumerate() {
+            // Monitor handles don't change while the session is active, but fall back to the
+            // index in case the display configuration changed in between
+            let monitor_idx = self
+                .monitors()
+                .iter()
+                .position(|monitor| monitor.id() == restored_monitor.id)
+                .unwrap_or(idx);
+
+            let monitor = if let Some(monitor) = self.monitors_mut().get_mut(monitor_idx) {
+                monitor
+            } else {
+                tracing::warn!("there is no monitor to restore at index {}", monitor_idx);
+                continue;
+            };
+
+            monitor.ensure_workspace_count(restored_monitor.workspaces.len());
+
+            for (workspace, restored) in monitor
+                .workspaces_mut()
+                .iter_mut()
+                .zip(restored_monitor.workspaces)
+            {
+                workspace.set_name(restored.name);
+                workspace.set_layout(restored.layout);
+                workspace.set_layout_flip(restored.layout_flip);
+                workspace.set_workspace_padding(restored.workspace_padding);
+                workspace.set_container_padding(restored.container_padding);
+                workspace.set_tile(restored.tile);
+
+                let mut container_idx = 0;
+                for hwnds in restored.containers {
+                    let mut container = Container::default();
+                    for hwnd in hwnds {
+                        let window = Window { hwnd };
+                        if window.is_window() {
+                            container.add_window(window);
+                        }
+                    }
+
+                    if container.windows().is_empty() {
+                        continue;
+                    }
+
+                    container.focus_window(0);
+                    for window in container.windows().iter().skip(1) {
+                        window.hide();
+                    }
+
+                    workspace.insert_container(container_idx, container);
+                    container_idx += 1;
+                }
+
+                for hwnd in restored.floating_windows {
+                    let window = Window { hwnd };
+                    if window.is_window() {
+                        workspace.floating_windows_mut().push(window);
+                    }
+                }
+
+                for (hwnd, swallowed) in restored.swallowed_windows {
+                    let window = Window { hwnd: swallowed };
+                    if window.is_window() {
+                        window.hide();
+                        workspace.swallowed_windows_mut().insert(hwnd, window);
+                    }
+                }
+
+                let mut resize_dimensions = restored.resize_dimensions;
+                resize_dimensions.resize(workspace.containers().len(), None);
+                workspace.set_resize_dimensions(resize_dimensions);
+
+                if restored.focused_container_idx < workspace.containers().len() {
+                    workspace.focus_container(restored.focused_container_idx);
+                }
+            }
+
+            monitor.focus_workspace(restored_monitor.focused_workspace_idx)?;
+            monitor.load_focused_workspace(mouse_follows_focus)?;
+        }
+
+        if state.focused_monitor_idx < self.monitors().len() {
+            self.focus_monitor(state.focused_monitor_idx)?;
+        }
+
+        std::fs::remove_file(path)?;
+
+        self.retile_all(true)
+    }
+
+    #[tracing::instrument(skip(self))]
     pub fn move_container_to_monitor(&mut self, idx: usize, follow: bool) -> Result<()> {
         tracing::info!("moving container");
 
@@ -828,6 +2484,320 @@ impl WindowManager {
 
         self.update_focused_workspace(mouse_follows_focus)
     }
+
+    fn container_location_by_id(&self, id: &str) -> Option<(usize, usize, usize)> {
+        for (i, monitor) in self.monitors().iter().enumerate() {
+            for (j, workspace) in monitor.workspaces().iter().enumerate() {
+                for (k, container) in workspace.containers().iter().enumerate() {
+                    if container.id() == id {
+                        return Option::from((i, j, k));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn focus_container_by_id(&mut self, id: &str) -> Result<()> {
+        tracing::info!("focusing container");
+
+        let (monitor_idx, workspace_idx, container_idx) = self
+            .container_location_by_id(id)
+            .ok_or_else(|| anyhow!("there is no container with this id"))?;
+
+        if self.focused_monitor_idx() != monitor_idx {
+            self.focus_monitor(monitor_idx)?;
+        }
+
+        if self
+            .focused_monitor()
+            .ok_or_else(|| anyhow!("there is no monitor"))?
+            .focused_workspace_idx()
+            != workspace_idx
+        {
+            self.focus_workspace(workspace_idx)?;
+        }
+
+        self.focused_workspace_mut()?.focus_container(container_idx);
+        self.focused_window_mut()?.focus(self.mouse_follows_focus)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn show_container_hints(&mut self) -> Result<()> {
+        tracing::info!("showing container hints");
+
+        let mut containers = vec![];
+        for monitor in self.monitors() {
+            if let Some(workspace) = monitor.focused_workspace() {
+                for container in workspace.containers() {
+                    if let Some(window) = container.focused_window() {
+                        containers.push((
+                            container.id().clone(),
+                            WindowsApi::window_rect(window.hwnd())?,
+                        ));
+                    }
+                }
+            }
+        }
+
+        let labels = hints::labels(containers.len());
+        let mut hints = vec![];
+        let mut targets = HashMap::new();
+
+        for (label, (id, rect)) in labels.into_iter().zip(containers) {
+            hints.push((label.clone(), rect));
+            targets.insert(label, id);
+        }
+
+        hints::show(hints);
+        self.container_hints = Option::from((Instant::now(), targets));
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn focus_hint(&mut self, label: &str) -> Result<()> {
+        tracing::info!("focusing hinted container");
+
+        // Hint mode ends as soon as a label is given, whether or not it matches a container
+        let hints = self.container_hints.take();
+        hints::hide();
+
+        let (shown, targets) = hints.ok_or_else(|| anyhow!("there are no container hints"))?;
+        if shown.elapsed() > Duration::from_secs(hints::HINT_TIMEOUT_SECONDS) {
+            return Err(anyhow!("the container hints have expired"));
+        }
+
+        let id = targets
+            .get(&label.to_lowercase())
+            .ok_or_else(|| anyhow!("there is no container with the hint {}", label))?;
+
+        self.focus_container_by_id(id)
+    }
+
+    pub fn window_list(&self) -> Vec<WindowListEntry> {
+        let mut entries = vec![];
+
+        for (i, monitor) in self.monitors().iter().enumerate() {
+            for (j, workspace) in monitor.workspaces().iter().enumerate() {
+                for (k, container) in workspace.containers().iter().enumerate() {
+                    for (l, window) in container.windows().iter().enumerate() {
+                        entries.push(WindowListEntry::new(
+                            *window,
+                            i,
+                            j,
+                            Option::from(k),
+                            Option::from(l),
+                        ));
+                    }
+                }
+
+                if let Some(container) = workspace.monocle_container() {
+                    for (l, window) in container.windows().iter().enumerate() {
+                        entries.push(WindowListEntry::new(*window, i, j, None, Option::from(l)));
+                    }
+                }
+
+                if let Some(window) = workspace.maximized_window() {
+                    entries.push(WindowListEntry::new(*window, i, j, None, None));
+                }
+
+                for window in workspace.floating_windows() {
+                    entries.push(WindowListEntry::new(*window, i, j, None, None));
+                }
+            }
+        }
+
+        entries
+    }
+
+    /// The same as `window_list`, but ordered with the most recently focused windows first, for
+    /// building a workspace-aware alt-tab switcher
+    pub fn mru_window_list(&self) -> Vec<WindowListEntry> {
+        let mut entries = self.window_list();
+        entries.sort_by_key(|entry| {
+            self.window_focus_history
+                .iter()
+                .position(|hwnd| *hwnd == entry.hwnd)
+                .unwrap_or(usize::MAX)
+        });
+
+        entries
+    }
+
+    pub fn record_window_focus(&mut self, hwnd: isize) {
+        self.window_focus_history.retain(|h| *h != hwnd);
+        self.window_focus_history.push_front(hwnd);
+        self.window_focus_history
+            .truncate(WINDOW_FOCUS_HISTORY_LIMIT);
+    }
+
+    /// Flag a managed window which has flashed its taskbar button, unless it already has focus
+    #[tracing::instrument(skip(self))]
+    pub fn mark_window_urgent(&mut self, hwnd: isize) -> Result<()> {
+        if WindowsApi::foreground_window()? == hwnd {
+            return Ok(());
+        }
+
+        let (monitor_idx, workspace_idx) = match self.window_location(hwnd) {
+            None => return Ok(()),
+            Some(location) => location,
+        };
+
+        if self.urgent_hwnds.insert(hwnd) {
+            tracing::info!("window is requesting attention");
+
+            notify_subscribers(&serde_json::to_string(&Notification {
+                event: NotificationEvent::WindowUrgent {
+                    window: Window { hwnd }.into(),
+                    monitor_idx,
+                    workspace_idx,
+                },
+                state: (&*self).into(),
+                stack: self.focused_stack_indicator(),
+            })?)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn workspace_badges(&self) -> Vec<Vec<usize>> {
+        self.monitors()
+            .iter()
+            .map(|monitor| {
+                monitor
+                    .workspaces()
+                    .iter()
+                    .map(|workspace| {
+                        self.urgent_hwnds
+                            .iter()
+                            .filter(|hwnd| workspace.contains_window(**hwnd))
+                            .count()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn focus_window_by_hwnd(&mut self, hwnd: isize) -> Result<()> {
+        tracing::info!("focusing window");
+
+        let (monitor_idx, workspace_idx) = self
+            .window_location(hwnd)
+            .ok_or_else(|| anyhow!("there is no managed window with this hwnd"))?;
+
+        if self.focused_monitor_idx() != monitor_idx {
+            self.focus_monitor(monitor_idx)?;
+        }
+
+        if self
+            .focused_monitor()
+            .ok_or_else(|| anyhow!("there is no monitor"))?
+            .focused_workspace_idx()
+            != workspace_idx
+        {
+            self.focus_workspace(workspace_idx)?;
+        }
+
+        let workspace = self.focused_workspace_mut()?;
+        if let Some(container_idx) = workspace.container_idx_for_window(hwnd) {
+            workspace.focus_container(container_idx);
+
+            let container = workspace
+                .focused_container_mut()
+                .ok_or_else(|| anyhow!("there is no container"))?;
+
+            // Bring the window to the front of its stack if it is not already there
+            if let Some(window_idx) = container.idx_for_window(hwnd) {
+                if container.focused_window_idx() != window_idx {
+                    container.focus_window(window_idx);
+                    container.load_focused_window();
+                }
+            }
+        }
+
+        Window { hwnd }.focus(self.mouse_follows_focus)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn move_container_by_id(
+        &mut self,
+        id: &str,
+        target_monitor_idx: usize,
+        target_workspace_idx: usize,
+    ) -> Result<()> {
+        tracing::info!("moving container");
+
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+        let mouse_follows_focus = self.mouse_follows_focus;
+
+        let (monitor_idx, workspace_idx, container_idx) = self
+            .container_location_by_id(id)
+            .ok_or_else(|| anyhow!("there is no container with this id"))?;
+
+        if self.monitors().get(target_monitor_idx).is_none() {
+            return Err(anyhow!("there is no monitor"));
+        }
+
+        let origin_monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let origin_is_visible = origin_monitor.focused_workspace_idx() == workspace_idx;
+        let origin_workspace = origin_monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        if origin_workspace.maximized_window().is_some() {
+            return Err(anyhow!(
+                "cannot move native maximized window to another monitor or workspace"
+            ));
+        }
+
+        let container = origin_workspace
+            .remove_container(container_idx)
+            .ok_or_else(|| anyhow!("there is no container"))?;
+
+        if origin_is_visible {
+            origin_monitor.update_focused_workspace(offset, &invisible_borders)?;
+        }
+
+        let target_monitor = self
+            .monitors_mut()
+            .get_mut(target_monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        target_monitor.ensure_workspace_count(target_workspace_idx + 1);
+        let target_is_visible = target_monitor.focused_workspace_idx() == target_workspace_idx;
+
+        if !target_is_visible {
+            for window in container.windows() {
+                window.hide();
+            }
+        }
+
+        target_monitor
+            .workspaces_mut()
+            .get_mut(target_workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?
+            .add_container(container);
+
+        if target_is_visible {
+            target_monitor.load_focused_workspace(mouse_follows_focus)?;
+            target_monitor.update_focused_workspace(offset, &invisible_borders)?;
+        }
+
+        Ok(())
+    }
+
     pub fn remove_focused_workspace(&mut self) -> Option<Workspace> {
         let focused_monitor: &mut Monitor = self.focused_monitor_mut()?;
         let focused_workspace_idx = focused_monitor.focused_workspace_idx();
@@ -853,6 +2823,7 @@ impl WindowManager {
             target_monitor.load_focused_workspace(mouse_follows_focus)?;
         }
 
+        self.resolve_workspace_rule_bindings();
         self.focus_monitor(idx)?;
         self.update_focused_workspace(mouse_follows_focus)
     }
@@ -862,6 +2833,14 @@ impl WindowManager {
         tracing::info!("focusing container");
         let workspace = self.focused_workspace_mut()?;
 
+        // Move between the windows of a nested container before leaving it
+        if let Some(container) = workspace.focused_container_mut() {
+            if let Some(idx) = container.nested_idx_for_direction(direction) {
+                container.focus_window(idx);
+                return self.focused_window_mut()?.focus(self.mouse_follows_focus);
+            }
+        }
+
         let new_idx = workspace
             .new_idx_for_direction(direction)
             .ok_or_else(|| anyhow!("this is not a valid direction from the current position"))?;
@@ -883,8 +2862,15 @@ impl WindowManager {
             .new_idx_for_direction(direction)
             .ok_or_else(|| anyhow!("this is not a valid direction from the current position"))?;
 
-        workspace.swap_containers(current_idx, new_idx);
-        workspace.focus_container(new_idx);
+        // Every region of a multi-stack layout is a stack, so windows are moved between them
+        // rather than swapping the regions themselves
+        if workspace.multi_stack().is_some() {
+            workspace.move_window_to_container(new_idx)?;
+        } else {
+            workspace.swap_containers(current_idx, new_idx);
+            workspace.focus_container(new_idx);
+        }
+
         self.update_focused_workspace(self.mouse_follows_focus)
     }
 
@@ -942,29 +2928,54 @@ impl WindowManager {
     }
 
     #[tracing::instrument(skip(self))]
-    pub fn add_window_to_container(&mut self, direction: OperationDirection) -> Result<()> {
-        tracing::info!("adding window to container");
+    pub fn nest_container(&mut self, axis: Axis) -> Result<()> {
+        tracing::info!("nesting container");
 
-        let workspace = self.focused_workspace_mut()?;
-        let len = NonZeroUsize::new(workspace.containers_mut().len())
-            .ok_or_else(|| anyhow!("there must be at least one container"))?;
-        let current_container_idx = workspace.focused_container_idx();
+        if matches!(axis, Axis::HorizontalAndVertical) {
+            return Err(anyhow!(
+                "the windows in a nested container can only be tiled along one axis"
+            ));
+        }
 
-        let is_valid = direction
-            .destination(
-                workspace.layout().as_boxed_direction().as_ref(),
-                workspace.layout_flip(),
-                workspace.focused_container_idx(),
-                len,
-            )
-            .is_some();
+        let container = self.focused_container_mut()?;
+        container.set_nested_axis(Option::from(axis));
+        container.load_focused_window();
 
-        if is_valid {
-            let new_idx = workspace.new_idx_for_direction(direction).ok_or_else(|| {
-                anyhow!("this is not a valid direction from the current position")
-            })?;
+        self.update_focused_workspace(self.mouse_follows_focus)
+    }
 
-            let adjusted_new_index = if new_idx > current_container_idx {
+    #[tracing::instrument(skip(self))]
+    pub fn unnest_container(&mut self) -> Result<()> {
+        tracing::info!("unnesting container");
+
+        let container = self.focused_container_mut()?;
+        if container.nested_axis().is_none() {
+            return Err(anyhow!("the focused container is not nested"));
+        }
+
+        container.set_nested_axis(None);
+        container.load_focused_window();
+
+        self.update_focused_workspace(self.mouse_follows_focus)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn dissolve_container(&mut self) -> Result<()> {
+        tracing::info!("dissolving container");
+
+        self.focused_workspace_mut()?.dissolve_focused_container()?;
+        self.update_focused_workspace(self.mouse_follows_focus)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn add_window_to_container(&mut self, direction: OperationDirection) -> Result<()> {
+        tracing::info!("adding window to container");
+
+        let workspace = self.focused_workspace_mut()?;
+        let current_container_idx = workspace.focused_container_idx();
+
+        if let Some(new_idx) = workspace.new_idx_for_direction(direction) {
+            let adjusted_new_index = if new_idx > current_container_idx {
                 new_idx - 1
             } else {
                 new_idx
@@ -986,6 +2997,32 @@ impl WindowManager {
         self.update_focused_workspace(self.mouse_follows_focus)
     }
 
+    #[tracing::instrument(skip(self))]
+    pub fn balance_workspace(&mut self) -> Result<()> {
+        tracing::info!("balancing workspace");
+
+        let workspace = self.focused_workspace_mut()?;
+        let container_count = workspace.containers().len();
+        workspace.set_resize_dimensions(vec![None; container_count]);
+
+        self.update_focused_workspace(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn focus_primary_zone(&mut self) -> Result<()> {
+        tracing::info!("focusing primary zone");
+
+        let workspace = self.focused_workspace_mut()?;
+        let primary_idx = workspace.primary_container_idx()?;
+
+        if primary_idx >= workspace.containers().len() {
+            return Err(anyhow!("there is no container in the primary zone"));
+        }
+
+        workspace.focus_container(primary_idx);
+        self.focused_window_mut()?.focus(self.mouse_follows_focus)
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn remove_window_from_container(&mut self) -> Result<()> {
         tracing::info!("removing window");
@@ -1044,7 +3081,12 @@ impl WindowManager {
             .last_mut()
             .ok_or_else(|| anyhow!("there is no floating window"))?;
 
-        window.center(&work_area, &invisible_borders)?;
+        learning::record(*window, SuggestionReason::FloatedManually);
+
+        if !float_positions::restore(*window).unwrap_or_default() {
+            window.center(&work_area, &invisible_borders)?;
+        }
+
         window.focus(self.mouse_follows_focus)?;
 
         Ok(())
@@ -1055,13 +3097,65 @@ impl WindowManager {
         tracing::info!("unfloating window");
 
         let workspace = self.focused_workspace_mut()?;
+
+        let foreground = WindowsApi::foreground_window()?;
+        if let Some(window) = workspace
+            .floating_windows()
+            .iter()
+            .find(|w| w.hwnd == foreground)
+        {
+            if let Err(error) = float_positions::remember(*window) {
+                tracing::warn!("could not remember floating window position: {}", error);
+            }
+        }
+
         workspace.new_container_for_floating_window()
     }
 
+    #[tracing::instrument(skip(self))]
+    pub fn tile_float(&mut self, direction: OperationDirection) -> Result<()> {
+        tracing::info!("tiling floating window");
+
+        self.focused_workspace_mut()?
+            .tile_floating_window(direction)?;
+
+        self.update_focused_workspace(true)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn toggle_focus_mode(&mut self) -> Result<()> {
+        let focus_mode_width = self.focus_mode_width;
+        let workspace = self.focused_workspace_mut()?;
+
+        match (workspace.focus_mode(), workspace.monocle_container()) {
+            (Some(_), _) => {
+                tracing::info!("disabling focus mode");
+                workspace.disable_focus_mode()?;
+            }
+            (None, None) => {
+                tracing::info!("enabling focus mode");
+                workspace.enable_focus_mode(focus_mode_width)?;
+            }
+            (None, Some(_)) => {
+                return Err(anyhow!(
+                    "focus mode cannot be enabled while monocle is enabled"
+                ));
+            }
+        }
+
+        self.update_focused_workspace(false)
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn toggle_monocle(&mut self) -> Result<()> {
         let workspace = self.focused_workspace_mut()?;
 
+        if workspace.focus_mode().is_some() {
+            return Err(anyhow!(
+                "monocle cannot be toggled while focus mode is enabled"
+            ));
+        }
+
         match workspace.monocle_container() {
             None => self.monocle_on()?,
             Some(_) => self.monocle_off()?,
@@ -1086,6 +3180,85 @@ impl WindowManager {
         workspace.reintegrate_monocle_container()
     }
 
+    #[tracing::instrument(skip(self))]
+    pub fn toggle_monocle_pip(&mut self) -> Result<()> {
+        let hwnd = WindowsApi::foreground_window()?;
+        let workspace = self.focused_workspace_mut()?;
+
+        if !workspace.floating_windows().iter().any(|w| w.hwnd == hwnd) {
+            return Err(anyhow!("the focused window is not floating"));
+        }
+
+        let pip_hwnds = workspace.monocle_pip_hwnds_mut();
+        if let Some(idx) = pip_hwnds.iter().position(|h| *h == hwnd) {
+            tracing::info!("no longer keeping window on top of the monocle container");
+            pip_hwnds.remove(idx);
+
+            if !workspace.on_top_hwnds().contains(&hwnd) {
+                WindowsApi::set_z_order(Window { hwnd }.hwnd(), ZOrder::NotTopmost)?;
+            }
+        } else {
+            tracing::info!("keeping window on top of the monocle container");
+            pip_hwnds.push(hwnd);
+        }
+
+        self.update_focused_workspace(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn raise_window(&mut self) -> Result<()> {
+        tracing::info!("raising window");
+
+        let hwnd = WindowsApi::foreground_window()?;
+        let workspace = self.focused_workspace_mut()?;
+
+        if !workspace.contains_window(hwnd) {
+            return Err(anyhow!("the focused window is not on this workspace"));
+        }
+
+        workspace.raise_hwnd(hwnd);
+        workspace.apply_z_order();
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn lower_window(&mut self) -> Result<()> {
+        tracing::info!("lowering window");
+
+        let hwnd = WindowsApi::foreground_window()?;
+        let workspace = self.focused_workspace_mut()?;
+
+        if !workspace.contains_window(hwnd) {
+            return Err(anyhow!("the focused window is not on this workspace"));
+        }
+
+        workspace.lower_hwnd(hwnd);
+        workspace.apply_z_order();
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn toggle_window_on_top(&mut self) -> Result<()> {
+        let hwnd = WindowsApi::foreground_window()?;
+        let workspace = self.focused_workspace_mut()?;
+
+        if !workspace.contains_window(hwnd) {
+            return Err(anyhow!("the focused window is not on this workspace"));
+        }
+
+        if workspace.toggle_hwnd_on_top(hwnd)? {
+            tracing::info!("keeping window on top");
+        } else {
+            tracing::info!("no longer keeping window on top");
+        }
+
+        workspace.apply_z_order();
+
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn toggle_maximize(&mut self) -> Result<()> {
         let workspace = self.focused_workspace_mut()?;
@@ -1098,6 +3271,26 @@ impl WindowManager {
         self.update_focused_workspace(false)
     }
 
+    #[tracing::instrument(skip(self))]
+    pub fn toggle_container_maximize(&mut self) -> Result<()> {
+        tracing::info!("toggling container maximize");
+
+        let workspace = self.focused_workspace_mut()?;
+        let focused_id = workspace
+            .focused_container()
+            .ok_or_else(|| anyhow!("there is no container"))?
+            .id()
+            .clone();
+
+        if workspace.maximized_container().as_deref() == Some(focused_id.as_str()) {
+            workspace.set_maximized_container(None);
+        } else {
+            workspace.set_maximized_container(Option::from(focused_id));
+        }
+
+        self.update_focused_workspace(false)
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn maximize_window(&mut self) -> Result<()> {
         tracing::info!("maximizing windowj");
@@ -1158,30 +3351,56 @@ impl WindowManager {
     }
 
     #[tracing::instrument(skip(self))]
-    pub fn change_workspace_layout_default(&mut self, layout: DefaultLayout) -> Result<()> {
-        tracing::info!("changing layout");
+    pub fn toggle_split_orientation(&mut self) -> Result<()> {
+        tracing::info!("toggling split orientation");
 
         let workspace = self.focused_workspace_mut()?;
 
-        match workspace.layout() {
-            Layout::Default(_) => {}
-            Layout::Custom(layout) => {
-                let primary_idx =
-                    layout.first_container_idx(layout.primary_idx().ok_or_else(|| {
-                        anyhow!("this custom layout does not have a primary column")
-                    })?);
+        if !matches!(workspace.layout(), Layout::Default(DefaultLayout::BSP)) {
+            return Err(anyhow!(
+                "split orientation can only be toggled on workspaces using the bsp layout"
+            ));
+        }
 
-                if !workspace.containers().is_empty() && primary_idx < workspace.containers().len()
-                {
-                    workspace.swap_containers(0, primary_idx);
-                }
-            }
+        let len = workspace.containers().len();
+        if len < 2 {
+            return Err(anyhow!("there is no split to toggle"));
         }
 
-        workspace.set_layout(Layout::Default(layout));
+        // The last container shares the final split with the container before it
+        let split_idx = workspace.focused_container_idx().min(len - 2);
+        let toggled_splits = workspace.toggled_splits_mut();
+
+        if let Some(idx) = toggled_splits.iter().position(|split| *split == split_idx) {
+            toggled_splits.remove(idx);
+        } else {
+            toggled_splits.push(split_idx);
+        }
+
+        self.update_focused_workspace(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn change_workspace_layout_default(&mut self, layout: DefaultLayout) -> Result<()> {
+        tracing::info!("changing layout");
+
+        self.focused_workspace_mut()?
+            .change_layout_default(layout)?;
         self.update_focused_workspace(self.mouse_follows_focus)
     }
 
+    #[tracing::instrument(skip(self))]
+    pub fn cycle_workspace_layout(&mut self, direction: CycleDirection) -> Result<()> {
+        tracing::info!("cycling layout");
+
+        let layout = self
+            .focused_workspace()?
+            .next_rotation_layout(direction)
+            .ok_or_else(|| anyhow!("there are no layouts to cycle through on this workspace"))?;
+
+        self.change_workspace_layout_default(layout)
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn change_workspace_custom_layout(&mut self, path: PathBuf) -> Result<()> {
         tracing::info!("changing layout");
@@ -1238,6 +3457,28 @@ impl WindowManager {
         self.update_focused_workspace(false)
     }
 
+    #[tracing::instrument(skip(self))]
+    pub fn adjust_inner_gap(&mut self, sizing: Sizing, adjustment: i32) -> Result<()> {
+        tracing::info!("adjusting inner gap");
+
+        let workspace = self.focused_workspace_mut()?;
+        let gap = workspace.inner_gap().unwrap_or_default();
+        workspace.set_inner_gap(Option::from(sizing.adjust_by(gap, adjustment)));
+
+        self.update_focused_workspace(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn adjust_outer_gap(&mut self, sizing: Sizing, adjustment: i32) -> Result<()> {
+        tracing::info!("adjusting outer gap");
+
+        let workspace = self.focused_workspace_mut()?;
+        let gap = workspace.outer_gap().unwrap_or_default();
+        workspace.set_outer_gap(Option::from(sizing.adjust_by(gap, adjustment)));
+
+        self.update_focused_workspace(false)
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn set_workspace_tiling(
         &mut self,
@@ -1260,6 +3501,128 @@ impl WindowManager {
         self.update_focused_workspace(false)
     }
 
+    #[tracing::instrument(skip(self))]
+    pub fn set_workspace_focus_follows_mouse(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        enable: bool,
+    ) -> Result<()> {
+        tracing::info!("setting workspace focus follows mouse");
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        workspace.set_focus_follows_mouse(Option::from(enable));
+
+        self.sync_workspace_focus_follows_mouse()
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_workspace_logical_padding(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        enable: bool,
+    ) -> Result<()> {
+        tracing::info!("setting workspace logical padding");
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        workspace.set_logical_padding(enable);
+
+        self.update_focused_workspace(false)
+    }
+
+    /// The windows implementation of focus follows mouse is a system-wide setting, so it has to be
+    /// switched on and off to match the override (if any) of whichever workspace is focused
+    pub fn sync_workspace_focus_follows_mouse(&self) -> Result<()> {
+        if let Some(FocusFollowsMouseImplementation::Windows) = self.focus_follows_mouse {
+            let enable = self
+                .focused_workspace()?
+                .focus_follows_mouse()
+                .unwrap_or(true);
+
+            if WindowsApi::focus_follows_mouse()? != enable {
+                if enable {
+                    WindowsApi::enable_focus_follows_mouse()?;
+                } else {
+                    WindowsApi::disable_focus_follows_mouse()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_workspace_taskbar_hidden(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        hidden: bool,
+    ) -> Result<()> {
+        tracing::info!("setting workspace taskbar hidden");
+
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?
+            .set_taskbar_hidden(hidden);
+
+        if monitor.focused_workspace_idx() == workspace_idx {
+            monitor.update_focused_workspace(offset, &invisible_borders)?;
+        }
+
+        self.sync_workspace_taskbars();
+
+        Ok(())
+    }
+
+    /// Taskbars are not owned by any workspace, so they have to be shown and hidden to match
+    /// whichever workspace is focused on the monitor that each of them is on
+    pub fn sync_workspace_taskbars(&self) {
+        for hwnd in WindowsApi::taskbar_windows() {
+            let monitor_id = WindowsApi::monitor_from_window(hwnd);
+            let hidden = self
+                .monitors()
+                .iter()
+                .find(|monitor| monitor.id() == monitor_id)
+                .map_or(false, Monitor::taskbar_hidden);
+
+            if hidden == WindowsApi::is_window_visible(hwnd) {
+                if hidden {
+                    WindowsApi::hide_window(hwnd);
+                } else {
+                    WindowsApi::show_window_without_activating(hwnd);
+                }
+            }
+        }
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn set_workspace_layout_default(
         &mut self,
@@ -1278,7 +3641,8 @@ impl WindowManager {
             .get_mut(monitor_idx)
             .ok_or_else(|| anyhow!("there is no monitor"))?;
 
-        let work_area = *monitor.work_area_size();
+        let work_area = monitor.tiling_area();
+        let group = monitor.group();
         let focused_workspace_idx = monitor.focused_workspace_idx();
 
         let workspace = monitor
@@ -1290,7 +3654,7 @@ impl WindowManager {
 
         // If this is the focused workspace on a non-focused screen, let's update it
         if focused_monitor_idx != monitor_idx && focused_workspace_idx == workspace_idx {
-            workspace.update(&work_area, offset, &invisible_borders)?;
+            workspace.update(&work_area, offset, &invisible_borders, group)?;
             Ok(())
         } else {
             Ok(self.update_focused_workspace(false)?)
@@ -1315,7 +3679,8 @@ impl WindowManager {
             .get_mut(monitor_idx)
             .ok_or_else(|| anyhow!("there is no monitor"))?;
 
-        let work_area = *monitor.work_area_size();
+        let work_area = monitor.tiling_area();
+        let group = monitor.group();
         let focused_workspace_idx = monitor.focused_workspace_idx();
 
         let workspace = monitor
@@ -1327,7 +3692,7 @@ impl WindowManager {
 
         // If this is the focused workspace on a non-focused screen, let's update it
         if focused_monitor_idx != monitor_idx && focused_workspace_idx == workspace_idx {
-            workspace.update(&work_area, offset, &invisible_borders)?;
+            workspace.update(&work_area, offset, &invisible_borders, group)?;
             Ok(())
         } else {
             Ok(self.update_focused_workspace(false)?)
@@ -1377,13 +3742,13 @@ impl WindowManager {
     }
 
     #[tracing::instrument(skip(self))]
-    pub fn set_workspace_name(
+    pub fn set_inner_gap(
         &mut self,
         monitor_idx: usize,
         workspace_idx: usize,
-        name: String,
+        size: i32,
     ) -> Result<()> {
-        tracing::info!("setting workspace name");
+        tracing::info!("setting inner gap");
 
         let monitor = self
             .monitors_mut()
@@ -1393,22 +3758,705 @@ impl WindowManager {
         let workspace = monitor
             .workspaces_mut()
             .get_mut(workspace_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
 
-        workspace.set_name(Option::from(name.clone()));
-        monitor.workspace_names_mut().insert(workspace_idx, name);
+        workspace.set_inner_gap(Option::from(size));
+
+        self.update_focused_workspace(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_outer_gap(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        size: i32,
+    ) -> Result<()> {
+        tracing::info!("setting outer gap");
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        workspace.set_outer_gap(Option::from(size));
+
+        self.update_focused_workspace(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_workspace_layout_rotation(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        layouts: Vec<DefaultLayout>,
+    ) -> Result<()> {
+        tracing::info!("setting workspace layout rotation");
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        workspace.set_layout_rotation(layouts);
 
         Ok(())
     }
 
     #[tracing::instrument(skip(self))]
-    pub fn set_container_padding(
+    pub fn set_workspace_layout_cycle_interval(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        interval: u64,
+        layouts: Vec<DefaultLayout>,
+    ) -> Result<()> {
+        tracing::info!("setting workspace layout cycle interval");
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        // An empty list keeps the existing rotation, and an interval of 0 stops the cycling
+        if !layouts.is_empty() {
+            workspace.set_layout_rotation(layouts);
+        }
+
+        if interval == 0 {
+            workspace.set_layout_cycle_interval(None);
+            workspace.set_layout_cycled_at(None);
+        } else {
+            workspace.set_layout_cycle_interval(Option::from(interval));
+            workspace.set_layout_cycled_at(Option::from(Instant::now()));
+        }
+
+        Ok(())
+    }
+
+    /// Move every workspace whose layout cycle interval has elapsed on to the next layout in its
+    /// rotation; workspaces which are not visible will be laid out when they are next focused
+    #[tracing::instrument(skip(self))]
+    pub fn cycle_scheduled_layouts(&mut self) -> Result<()> {
+        let mut should_retile = false;
+
+        for monitor in self.monitors_mut() {
+            let focused_workspace_idx = monitor.focused_workspace_idx();
+            for (idx, workspace) in monitor.workspaces_mut().iter_mut().enumerate() {
+                let is_due = match (
+                    workspace.layout_cycle_interval(),
+                    workspace.layout_cycled_at(),
+                ) {
+                    (Some(interval), Some(cycled_at)) => {
+                        cycled_at.elapsed() >= Duration::from_secs(interval)
+                    }
+                    _ => false,
+                };
+
+                if !is_due {
+                    continue;
+                }
+
+                workspace.set_layout_cycled_at(Option::from(Instant::now()));
+                if let Some(layout) = workspace.next_rotation_layout(CycleDirection::Next) {
+                    tracing::info!("cycling scheduled layout");
+                    if let Err(error) = workspace.change_layout_default(layout) {
+                        tracing::error!("{}", error);
+                        continue;
+                    }
+
+                    should_retile |= idx == focused_workspace_idx;
+                }
+            }
+        }
+
+        if should_retile {
+            self.retile_all(true)?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn save_workspace_snapshot(&self, path: &Path) -> Result<()> {
+        tracing::info!("saving workspace snapshot");
+
+        let workspace = self.focused_workspace()?;
+        let mut containers = vec![];
+
+        for container in workspace.containers() {
+            let mut applications = vec![];
+            for window in container.windows() {
+                applications.push(ApplicationPlaceholder {
+                    exe: window.exe()?,
+                    class: window.class()?,
+                    title: window.title().ok(),
+                    path: window.path().ok(),
+                });
+            }
+
+            containers.push(SnapshotContainer { applications });
+        }
+
+        WorkspaceSnapshot::new(
+            workspace.layout().clone(),
+            workspace.layout_flip(),
+            workspace.resize_dimensions().clone(),
+            containers,
+        )
+        .save(path)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn generate_custom_layout_from_workspace(&self, path: &Path) -> Result<()> {
+        tracing::info!("generating custom layout from workspace");
+
+        let workspace = self.focused_workspace()?;
+        if workspace.monocle_container().is_some() || workspace.maximized_window().is_some() {
+            return Err(anyhow!(
+                "a custom layout cannot be generated while a container is in monocle or maximized"
+            ));
+        }
+
+        let rects = workspace
+            .latest_layout()
+            .iter()
+            .take(workspace.containers().len())
+            .copied()
+            .collect::<Vec<_>>();
+
+        CustomLayout::from_rects(&rects)?.save(path)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn load_workspace_snapshot(&mut self, path: &Path) -> Result<()> {
+        tracing::info!("loading workspace snapshot");
+
+        let snapshot = WorkspaceSnapshot::load(path)?;
+
+        let mut managed = vec![];
+        for monitor in self.monitors() {
+            for workspace in monitor.workspaces() {
+                for container in workspace.containers() {
+                    managed.extend(container.windows().iter().copied());
+                }
+
+                managed.extend(workspace.floating_windows().iter().copied());
+            }
+        }
+
+        let mut identities = managed
+            .iter()
+            .map(|window| window.identity())
+            .collect::<Vec<_>>();
+
+        // Adopt already managed windows which match the placeholders in each container, and
+        // launch anything which isn't already open
+        let mut adopted = vec![];
+        let mut to_launch = vec![];
+
+        for (idx, container) in snapshot.containers.iter().enumerate() {
+            let mut windows = vec![];
+            for application in &container.applications {
+                // Prefer the window which was saved with the same title, since several windows
+                // of the same application can be open, but titles can also change
+                let position = identities
+                    .iter()
+                    .position(|identity| {
+                        identity.as_ref().map_or(false, |(exe, class, title)| {
+                            application.matches(exe, class) && application.matches_title(title)
+                        })
+                    })
+                    .or_else(|| {
+                        identities.iter().position(|identity| {
+                            identity
+                                .as_ref()
+                                .map_or(false, |(exe, class, _)| application.matches(exe, class))
+                        })
+                    });
+
+                match position {
+                    Some(position) => {
+                        identities.remove(position);
+                        windows.push(managed.remove(position));
+                    }
+                    None => to_launch.push((idx, application.clone())),
+                }
+            }
+
+            adopted.push(windows);
+        }
+
+        for window in adopted.iter().flatten() {
+            for monitor in self.monitors_mut() {
+                for workspace in monitor.workspaces_mut() {
+                    if workspace.contains_window(window.hwnd) {
+                        workspace.remove_window(window.hwnd)?;
+                    }
+                }
+            }
+        }
+
+        let workspace = self.focused_workspace_mut()?;
+        workspace.set_layout(snapshot.layout);
+        workspace.set_layout_flip(snapshot.layout_flip);
+        workspace.snapshot_placements_mut().clear();
+
+        // A snapshot is merged into the workspace, with its containers taking the first positions
+        // and any other windows which were already on the workspace kept in order after them
+        let others = workspace.containers_mut().drain(..).collect::<Vec<_>>();
+        workspace.set_resize_dimensions(vec![]);
+
+        for (idx, windows) in adopted.into_iter().enumerate() {
+            if windows.is_empty() {
+                continue;
+            }
+
+            let mut container = Container::default();
+            for window in windows {
+                container.add_window(window);
+            }
+
+            for (i, window) in container.windows().iter().enumerate() {
+                if i == container.focused_window_idx() {
+                    window.restore();
+                } else {
+                    window.hide();
+                }
+            }
+
+            workspace.insert_container(idx, container);
+        }
+
+        let mut resize_dimensions = snapshot.resize_dimensions;
+        resize_dimensions.resize(workspace.containers().len(), None);
+        for container in others {
+            workspace.containers_mut().push_back(container);
+            resize_dimensions.push(None);
+        }
+
+        workspace.set_resize_dimensions(resize_dimensions);
+
+        for (idx, application) in to_launch {
+            match application.path.clone() {
+                Some(path) => match Command::new(&path).spawn() {
+                    Ok(_) => {
+                        // The window will be moved into place when it is shown
+                        workspace.snapshot_placements_mut().push((application, idx));
+                    }
+                    Err(error) => tracing::error!("could not launch {}: {}", path, error),
+                },
+                None => tracing::warn!(
+                    "{} is not open and there is no path to launch it from",
+                    application.exe
+                ),
+            }
+        }
+
+        self.update_focused_workspace(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn clone_workspace_layout_to_monitor(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        target_monitor_idx: usize,
+    ) -> Result<()> {
+        tracing::info!("cloning workspace layout to monitor");
+
+        if monitor_idx == target_monitor_idx {
+            return Err(anyhow!(
+                "a workspace layout cannot be cloned to the same monitor"
+            ));
+        }
+
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+        let focused_monitor_idx = self.focused_monitor_idx();
+
+        let source = self
+            .monitors()
+            .get(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?
+            .workspaces()
+            .get(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?
+            .clone();
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(target_monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        monitor.ensure_workspace_count(workspace_idx + 1);
+
+        let work_area = monitor.tiling_area();
+        let group = monitor.group();
+        let is_visible = monitor.focused_workspace_idx() == workspace_idx;
+
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        workspace.copy_layout_settings(&source);
+
+        if !is_visible {
+            Ok(())
+        } else if focused_monitor_idx == target_monitor_idx {
+            self.update_focused_workspace(false)
+        } else {
+            workspace.update(&work_area, offset, &invisible_borders, group)
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_workspace_name(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        name: String,
+    ) -> Result<()> {
+        tracing::info!("setting workspace name");
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        workspace.set_name(Option::from(name.clone()));
+        monitor.workspace_names_mut().insert(workspace_idx, name);
+
+        self.resolve_workspace_rule_bindings();
+
+        Ok(())
+    }
+
+    /// The monitor and workspace indices of the workspace with this id or name
+    pub fn workspace_location_by_name_or_id(&self, target: &str) -> Option<(usize, usize)> {
+        for (i, monitor) in self.monitors().iter().enumerate() {
+            for (j, workspace) in monitor.workspaces().iter().enumerate() {
+                if workspace.id() == target || workspace.name().as_deref() == Some(target) {
+                    return Option::from((i, j));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Point workspace rules which are bound to a workspace name or id at the current indices of
+    /// those workspaces
+    pub fn resolve_workspace_rule_bindings(&self) {
+        let bindings = WORKSPACE_RULE_BINDINGS.lock();
+        if bindings.is_empty() {
+            return;
+        }
+
+        let mut workspace_rules = WORKSPACE_RULES.lock();
+        let mut command_line_rules = COMMAND_LINE_WORKSPACE_RULES.lock();
+
+        for (identifier, id, target) in bindings.iter() {
+            let location = if let Some(location) = self.workspace_location_by_name_or_id(target) {
+                location
+            } else {
+                tracing::warn!("there is no workspace with the name or id {}", target);
+                continue;
+            };
+
+            if matches!(identifier, ApplicationIdentifier::CommandLine) {
+                command_line_rules.retain(|(existing, _)| existing != id);
+                command_line_rules.push((id.clone(), location));
+            } else {
+                workspace_rules.insert(id.clone(), location);
+            }
+        }
+    }
+
+    /// Enable or disable every rule tagged with the rule group `name`
+    #[tracing::instrument(skip(self))]
+    pub fn toggle_rule_group(&mut self, name: &str) -> Result<()> {
+        tracing::info!("toggling rule group");
+
+        let (enabled, rules) = rule_groups::toggle(name)?;
+        if enabled {
+            for rule in rules {
+                self.process_command(rule)?;
+            }
+        } else {
+            for rule in &rules {
+                rule_groups::remove(rule);
+            }
+
+            self.retile_all(true)?;
+        }
+
+        Ok(())
+    }
+
+    /// Move the focused workspace to `idx` on the same monitor, taking workspace rules for the
+    /// workspaces on this monitor along with them
+    #[tracing::instrument(skip(self))]
+    pub fn move_workspace_to_index(&mut self, idx: usize) -> Result<()> {
+        tracing::info!("moving workspace to index");
+
+        let mouse_follows_focus = self.mouse_follows_focus;
+        let monitor_idx = self.focused_monitor_idx();
+        let monitor = self
+            .focused_monitor_mut()
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        if idx >= monitor.workspaces().len() {
+            return Err(anyhow!("there is no workspace at index {}", idx));
+        }
+
+        let from = monitor.focused_workspace_idx();
+        let workspace = monitor
+            .workspaces_mut()
+            .remove(from)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        monitor.workspaces_mut().insert(idx, workspace);
+        monitor.focus_workspace(idx)?;
+
+        // Every workspace in between the two indices shifts by one towards the old index
+        let moved = |i: usize| {
+            if i == from {
+                idx
+            } else if from < idx && i > from && i <= idx {
+                i - 1
+            } else if idx < from && i >= idx && i < from {
+                i + 1
+            } else {
+                i
+            }
+        };
+
+        let names = std::mem::take(monitor.workspace_names_mut());
+        monitor
+            .workspace_names_mut()
+            .extend(names.into_iter().map(|(i, name)| (moved(i), name)));
+
+        let shift = |target: &mut (usize, usize)| {
+            if target.0 == monitor_idx {
+                target.1 = moved(target.1);
+            }
+        };
+
+        WORKSPACE_RULES.lock().values_mut().for_each(&shift);
+        for (_, target) in COMMAND_LINE_WORKSPACE_RULES.lock().iter_mut() {
+            shift(target);
+        }
+
+        for (_, _, target) in WORKSPACE_RULES_ONCE.lock().iter_mut() {
+            shift(target);
+        }
+
+        // Snapshot placements are kept by the workspace that they were made for, so they move
+        // along with it, but everything else which refers to a workspace by index has to follow
+        self.workspace_history.remap(|mut location| {
+            shift(&mut location);
+            location
+        });
+
+        for reservation in &mut self.spawn_reservations {
+            let mut target = (reservation.monitor_idx, reservation.workspace_idx);
+            shift(&mut target);
+            reservation.workspace_idx = target.1;
+        }
+
+        self.resolve_workspace_rule_bindings();
+        self.update_focused_workspace(mouse_follows_focus)
+    }
+
+    /// Set the resize adjustments of a container directly, as if it had been resized by hand.
+    /// An adjustment of zero on every edge clears the resize dimensions of the container.
+    #[tracing::instrument(skip(self))]
+    pub fn set_container_resize_dimensions(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        container_idx: usize,
+        rect: Rect,
+    ) -> Result<()> {
+        tracing::info!("setting container resize dimensions");
+
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let focused_workspace_idx = monitor.focused_workspace_idx();
+
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        let container_count = workspace.containers().len();
+        if container_idx >= container_count {
+            return Err(anyhow!("there is no container"));
+        }
+
+        let resize = if rect == Rect::default() {
+            None
+        } else {
+            Option::from(rect)
+        };
+
+        workspace
+            .resize_dimensions_mut()
+            .resize(container_count, None);
+
+        if let Some(dimensions) = workspace.resize_dimensions_mut().get_mut(container_idx) {
+            *dimensions = resize;
+        }
+
+        if focused_workspace_idx == workspace_idx {
+            monitor.update_focused_workspace(offset, &invisible_borders)?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_workspace_wallpaper(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        path: PathBuf,
+    ) -> Result<()> {
+        tracing::info!("setting workspace wallpaper");
+
+        let focused_monitor_idx = self.focused_monitor_idx();
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let focused_workspace_idx = monitor.focused_workspace_idx();
+
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        // The desktop wallpaper is shared by all monitors, so it is only changed straight away if
+        // this is the workspace that currently has focus
+        if focused_monitor_idx == monitor_idx && focused_workspace_idx == workspace_idx {
+            wallpaper::set(&path)?;
+        }
+
+        workspace.set_wallpaper(Option::from(path));
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_container_padding(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        size: i32,
+    ) -> Result<()> {
+        tracing::info!("setting container padding");
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        workspace.set_container_padding(Option::from(size));
+
+        self.update_focused_workspace(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_container_padding_scale(
         &mut self,
         monitor_idx: usize,
         workspace_idx: usize,
-        size: i32,
+        scale: Vec<i32>,
     ) -> Result<()> {
-        tracing::info!("setting container padding");
+        tracing::info!("setting container padding scale");
+
+        let workspace = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        workspace.set_container_padding_scale(scale);
+
+        self.update_focused_workspace(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_workspace_multi_stack(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        regions: usize,
+    ) -> Result<()> {
+        tracing::info!("setting workspace multi-stack regions");
+
+        let workspace = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        workspace.set_multi_stack(if regions == 0 {
+            None
+        } else {
+            Option::from(regions)
+        });
+
+        self.update_focused_workspace(false)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_workspace_resize_delta(
+        &mut self,
+        monitor_idx: usize,
+        workspace_idx: usize,
+        delta: i32,
+    ) -> Result<()> {
+        tracing::info!("setting workspace resize delta");
 
         let monitor = self
             .monitors_mut()
@@ -1418,11 +4466,24 @@ impl WindowManager {
         let workspace = monitor
             .workspaces_mut()
             .get_mut(workspace_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
 
-        workspace.set_container_padding(Option::from(size));
+        // A delta of 0 would make resizing do nothing, so it is used to remove the override
+        workspace.set_resize_delta(if delta == 0 {
+            None
+        } else {
+            Option::from(delta)
+        });
 
-        self.update_focused_workspace(false)
+        Ok(())
+    }
+
+    /// The resize delta of the focused workspace if it has one, or the global resize delta
+    pub fn focused_resize_delta(&self) -> Result<i32> {
+        Ok(self
+            .focused_workspace()?
+            .resize_delta()
+            .unwrap_or(self.resize_delta))
     }
 
     pub fn focused_monitor_work_area(&self) -> Result<Rect> {
@@ -1448,18 +4509,38 @@ impl WindowManager {
     pub fn monitor_idx_from_window(&mut self, window: Window) -> Option<usize> {
         let hmonitor = WindowsApi::monitor_from_window(window.hwnd());
 
-        for (i, monitor) in self.monitors().iter().enumerate() {
-            if monitor.id() == hmonitor {
-                return Option::from(i);
-            }
-        }
-
-        None
+        self.monitor_idx_from_hmonitor(hmonitor)
     }
 
     pub fn monitor_idx_from_current_pos(&mut self) -> Option<usize> {
         let hmonitor = WindowsApi::monitor_from_point(WindowsApi::cursor_pos().ok()?);
 
+        self.monitor_idx_from_hmonitor(hmonitor)
+    }
+
+    // Unlike monitor_idx_from_current_pos, this never falls back to the nearest monitor, so points
+    // in the dead zones between differently-sized monitors don't resolve to any monitor at all
+    fn monitor_work_area_at_point(&self, point: (i32, i32)) -> Option<(usize, Rect)> {
+        let monitor = self
+            .monitors()
+            .iter()
+            .find(|monitor| monitor.work_area_size().contains_point(point))?;
+
+        Option::from((
+            self.monitor_idx_from_hmonitor(monitor.id())?,
+            *monitor.work_area_size(),
+        ))
+    }
+
+    // Monitors which are the secondary monitor of a group resolve to their primary monitor
+    fn monitor_idx_from_hmonitor(&self, hmonitor: isize) -> Option<usize> {
+        let hmonitor = self
+            .monitors()
+            .iter()
+            .find(|monitor| monitor.id() == hmonitor)
+            .and_then(Monitor::grouped_into)
+            .unwrap_or(hmonitor);
+
         for (i, monitor) in self.monitors().iter().enumerate() {
             if monitor.id() == hmonitor {
                 return Option::from(i);
@@ -1487,6 +4568,11 @@ impl WindowManager {
     pub fn focus_workspace(&mut self, idx: usize) -> Result<()> {
         tracing::info!("focusing workspace");
 
+        // The other monitors are switched first so that focus ends up on the focused monitor
+        if self.workspace_sync {
+            self.sync_workspaces(idx)?;
+        }
+
         let mouse_follows_focus = self.mouse_follows_focus;
         let monitor = self
             .focused_monitor_mut()
@@ -1495,9 +4581,308 @@ impl WindowManager {
         monitor.focus_workspace(idx)?;
         monitor.load_focused_workspace(mouse_follows_focus)?;
 
+        if let Some(path) = self.focused_workspace()?.wallpaper() {
+            wallpaper::set(path)?;
+        }
+
+        self.sync_workspace_focus_follows_mouse()?;
+        self.sync_workspace_taskbars();
+
         self.update_focused_workspace(mouse_follows_focus)
     }
 
+    /// Switch every other monitor taking part in workspace sync to the workspace at `idx`
+    fn sync_workspaces(&mut self, idx: usize) -> Result<()> {
+        let focused_monitor_idx = self.focused_monitor_idx();
+        if !self
+            .focused_monitor()
+            .map_or(false, Monitor::workspace_sync)
+        {
+            return Ok(());
+        }
+
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+
+        for (i, monitor) in self.monitors_mut().iter_mut().enumerate() {
+            if i == focused_monitor_idx
+                || !monitor.workspace_sync()
+                || monitor.grouped_into().is_some()
+                || monitor.focused_workspace_idx() == idx
+            {
+                continue;
+            }
+
+            monitor.focus_workspace(idx)?;
+            monitor.load_focused_workspace(false)?;
+            monitor.update_focused_workspace(offset, &invisible_borders)?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn add_exclusion_zone(&mut self, monitor_idx: usize, zone: Rect) -> Result<()> {
+        tracing::info!("adding exclusion zone");
+
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        monitor.exclusion_zones_mut().push(zone);
+        monitor.update_focused_workspace(offset, &invisible_borders)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn clear_exclusion_zones(&mut self, monitor_idx: usize) -> Result<()> {
+        tracing::info!("clearing exclusion zones");
+
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        monitor.exclusion_zones_mut().clear();
+        monitor.update_focused_workspace(offset, &invisible_borders)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_work_area_override(
+        &mut self,
+        monitor_idx: usize,
+        work_area: Option<Rect>,
+    ) -> Result<()> {
+        tracing::info!("setting work area override");
+
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        monitor.set_work_area_override(work_area);
+        monitor.update_focused_workspace(offset, &invisible_borders)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn toggle_workspace_sync(&mut self) {
+        self.workspace_sync = !self.workspace_sync;
+        tracing::info!("workspace sync is now {}", self.workspace_sync);
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_monitor_workspace_sync(&mut self, monitor_idx: usize, enable: bool) -> Result<()> {
+        tracing::info!("setting monitor workspace sync");
+
+        self.monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?
+            .set_workspace_sync(enable);
+
+        Ok(())
+    }
+
+    pub fn record_workspace_history(&mut self) {
+        let monitor_idx = self.focused_monitor_idx();
+        if let Some(monitor) = self.focused_monitor() {
+            let workspace_idx = monitor.focused_workspace_idx();
+            self.workspace_history.record((monitor_idx, workspace_idx));
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn focus_workspace_back(&mut self) -> Result<()> {
+        tracing::info!("focusing previous workspace in history");
+
+        let (monitor_idx, workspace_idx) = self
+            .workspace_history
+            .back()
+            .ok_or_else(|| anyhow!("there is no previous workspace in the history"))?;
+
+        self.focus_monitor(monitor_idx)?;
+        self.focus_workspace(workspace_idx)
+    }
+
+    /// Move focus away from the focused workspace after its last window has been closed,
+    /// according to the configured `EmptyWorkspaceBehaviour`
+    #[tracing::instrument(skip(self))]
+    pub fn leave_empty_workspace(&mut self) -> Result<()> {
+        let monitor = self
+            .focused_monitor()
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let focused_idx = monitor.focused_workspace_idx();
+
+        match self.empty_workspace_behaviour {
+            EmptyWorkspaceBehaviour::Stay => Ok(()),
+            EmptyWorkspaceBehaviour::Previous => match self.workspace_history.back() {
+                None => Ok(()),
+                Some((monitor_idx, workspace_idx)) => {
+                    tracing::info!("leaving empty workspace for the previous workspace");
+                    self.focus_monitor(monitor_idx)?;
+                    self.focus_workspace(workspace_idx)
+                }
+            },
+            EmptyWorkspaceBehaviour::Nearest => {
+                let workspaces = monitor.workspaces();
+                let nearest = (1..workspaces.len())
+                    .flat_map(|distance| {
+                        [
+                            focused_idx.checked_sub(distance),
+                            Option::from(focused_idx + distance),
+                        ]
+                    })
+                    .flatten()
+                    .find(|idx| {
+                        workspaces
+                            .get(*idx)
+                            .map_or(false, |workspace| !workspace.is_empty())
+                    });
+
+                match nearest {
+                    None => Ok(()),
+                    Some(idx) => {
+                        tracing::info!("leaving empty workspace for the nearest workspace");
+                        self.focus_workspace(idx)
+                    }
+                }
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn focus_workspace_forward(&mut self) -> Result<()> {
+        tracing::info!("focusing next workspace in history");
+
+        let (monitor_idx, workspace_idx) = self
+            .workspace_history
+            .forward()
+            .ok_or_else(|| anyhow!("there is no next workspace in the history"))?;
+
+        self.focus_monitor(monitor_idx)?;
+        self.focus_workspace(workspace_idx)
+    }
+
+    /// Restore the windows of another workspace on the focused monitor on top of the focused
+    /// workspace without changing which workspace is focused, until `end_peek` is called
+    #[tracing::instrument(skip(self))]
+    pub fn peek_workspace(&mut self, idx: usize) -> Result<()> {
+        self.end_peek()?;
+
+        let monitor_idx = self.focused_monitor_idx();
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+
+        let monitor = self
+            .focused_monitor_mut()
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        if monitor.focused_workspace_idx() == idx {
+            return Ok(());
+        }
+
+        tracing::info!("peeking at workspace");
+
+        let work_area = monitor.tiling_area();
+        let group = monitor.group();
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        workspace.restore(false)?;
+        workspace.update(&work_area, offset, &invisible_borders, group)?;
+
+        self.peeked_workspace = Option::from((monitor_idx, idx));
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn end_peek(&mut self) -> Result<()> {
+        let (monitor_idx, workspace_idx) = match self.peeked_workspace.take() {
+            None => return Ok(()),
+            Some(peeked) => peeked,
+        };
+
+        tracing::info!("ending workspace peek");
+
+        let mouse_follows_focus = self.mouse_follows_focus;
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        if let Some(workspace) = monitor.workspaces_mut().get_mut(workspace_idx) {
+            workspace.hide();
+        }
+
+        // Restoring the focused workspace again puts its windows back on top and gives focus
+        // back to whichever window had it before the peek started
+        monitor
+            .focused_workspace_mut()
+            .ok_or_else(|| anyhow!("there is no workspace"))?
+            .restore(mouse_follows_focus)?;
+
+        monitor.update_focused_workspace(offset, &invisible_borders)
+    }
+
+    /// Remove a window which has been destroyed or unmanaged while peeking, from whichever
+    /// workspace it is on, without disturbing the peek
+    #[tracing::instrument(skip(self))]
+    pub fn remove_window_while_peeking(&mut self, hwnd: isize) -> Result<()> {
+        self.urgent_hwnds.remove(&hwnd);
+
+        let (monitor_idx, workspace_idx) = match self.window_location(hwnd) {
+            None => return Ok(()),
+            Some(location) => location,
+        };
+
+        tracing::info!("removing window while peeking");
+
+        let replaced = self.restore_swallowed_window(hwnd)?;
+
+        let invisible_borders = self.invisible_borders;
+        let offset = self.work_area_offset;
+        let peeked = self.peeked_workspace == Option::from((monitor_idx, workspace_idx));
+
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        let work_area = monitor.tiling_area();
+        let group = monitor.group();
+        let workspace = monitor
+            .workspaces_mut()
+            .get_mut(workspace_idx)
+            .ok_or_else(|| anyhow!("there is no workspace"))?;
+
+        if !replaced {
+            workspace.remove_window(hwnd)?;
+        }
+
+        // Any other workspace is laid out again when it is next shown, which for the focused
+        // workspace is when the peek ends
+        if peeked {
+            workspace.update(&work_area, offset, &invisible_borders, group)?;
+        }
+
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn new_workspace(&mut self) -> Result<()> {
         tracing::info!("adding new workspace");
@@ -1525,6 +4910,68 @@ impl WindowManager {
             .ok_or_else(|| anyhow!("there is no container"))
     }
 
+    /// A short description of what currently has focus, for keybindings which behave differently
+    /// depending on the context
+    pub fn focused_container_kind(&self) -> Result<String> {
+        let workspace = self.focused_workspace()?;
+
+        // Both natively maximized windows and containers maximized by komorebi cover the others
+        if workspace.has_maximized_window() {
+            return Ok("maximized".to_string());
+        }
+
+        if workspace.monocle_container().is_some() {
+            return Ok("monocle".to_string());
+        }
+
+        let foreground = WindowsApi::foreground_window()?;
+        if workspace
+            .floating_windows()
+            .iter()
+            .any(|window| window.hwnd == foreground)
+        {
+            return Ok("float".to_string());
+        }
+
+        Ok(match workspace.focused_container() {
+            None => "empty".to_string(),
+            Some(container) if container.nested_axis().is_some() => {
+                format!("nested:{}", container.windows().len())
+            }
+            Some(container) if container.windows().len() > 1 => {
+                format!("stack:{}", container.windows().len())
+            }
+            Some(_) => "tile".to_string(),
+        })
+    }
+
+    pub fn focused_stack_indicator(&self) -> Option<StackIndicator> {
+        if !self.stack_indicator {
+            return None;
+        }
+
+        let container = self.focused_container().ok()?;
+        let size = container.windows().len();
+
+        // A single window container is not a stack
+        if size < 2 {
+            return None;
+        }
+
+        let focused_window_idx = container.focused_window_idx();
+
+        Option::from(StackIndicator {
+            size,
+            focused_window_idx,
+            label: format!("[{}/{}]", focused_window_idx + 1, size),
+            titles: container
+                .windows()
+                .iter()
+                .map(|window| window.title().unwrap_or_default())
+                .collect(),
+        })
+    }
+
     pub fn focused_window(&self) -> Result<&Window> {
         self.focused_container()?
             .focused_window()
@@ -18,6 +18,7 @@ pub enum WindowManagerEvent {
     MoveResizeStart(WinEvent, Window),
     MoveResizeEnd(WinEvent, Window),
     MouseCapture(WinEvent, Window),
+    TitleChange(WinEvent, Window),
     Manage(Window),
     Unmanage(Window),
     Raise(Window),
@@ -73,6 +74,13 @@ impl Display for WindowManagerEvent {
                     winevent, window
                 )
             }
+            WindowManagerEvent::TitleChange(winevent, window) => {
+                write!(
+                    f,
+                    "TitleChange (WinEvent: {}, Window: {})",
+                    winevent, window
+                )
+            }
             WindowManagerEvent::Raise(window) => {
                 write!(f, "Raise (Window: {})", window)
             }
@@ -98,6 +106,7 @@ impl WindowManagerEvent {
             | WindowManagerEvent::MoveResizeStart(_, window)
             | WindowManagerEvent::MoveResizeEnd(_, window)
             | WindowManagerEvent::MouseCapture(_, window)
+            | WindowManagerEvent::TitleChange(_, window)
             | WindowManagerEvent::MonitorPoll(_, window)
             | WindowManagerEvent::Raise(window)
             | WindowManagerEvent::Manage(window)
@@ -140,7 +149,7 @@ impl WindowManagerEvent {
                 if object_name_change_on_launch.contains(&window.exe().ok()?) {
                     Option::from(Self::Show(winevent, window))
                 } else {
-                    None
+                    Option::from(Self::TitleChange(winevent, window))
                 }
             }
             WinEvent::ObjectCreate => {
@@ -2,11 +2,16 @@ use std::collections::VecDeque;
 use std::convert::TryFrom;
 use std::convert::TryInto;
 use std::ffi::c_void;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::atomic::Ordering;
 
 use color_eyre::eyre::anyhow;
 use color_eyre::eyre::Error;
 use color_eyre::Result;
 use windows::core::Result as WindowsCrateResult;
+use windows::Win32::Foundation::CloseHandle;
 use windows::Win32::Foundation::BOOL;
 use windows::Win32::Foundation::HANDLE;
 use windows::Win32::Foundation::HWND;
@@ -14,6 +19,7 @@ use windows::Win32::Foundation::LPARAM;
 use windows::Win32::Foundation::POINT;
 use windows::Win32::Foundation::PWSTR;
 use windows::Win32::Foundation::RECT;
+use windows::Win32::Foundation::WPARAM;
 use windows::Win32::Graphics::Dwm::DwmGetWindowAttribute;
 use windows::Win32::Graphics::Dwm::DWMWA_CLOAKED;
 use windows::Win32::Graphics::Dwm::DWMWA_EXTENDED_FRAME_BOUNDS;
@@ -21,29 +27,63 @@ use windows::Win32::Graphics::Dwm::DWMWINDOWATTRIBUTE;
 use windows::Win32::Graphics::Dwm::DWM_CLOAKED_APP;
 use windows::Win32::Graphics::Dwm::DWM_CLOAKED_INHERITED;
 use windows::Win32::Graphics::Dwm::DWM_CLOAKED_SHELL;
+use windows::Win32::Graphics::Gdi::CreateCompatibleDC;
+use windows::Win32::Graphics::Gdi::DeleteDC;
+use windows::Win32::Graphics::Gdi::DeleteObject;
+use windows::Win32::Graphics::Gdi::EnumDisplayDevicesW;
 use windows::Win32::Graphics::Gdi::EnumDisplayMonitors;
+use windows::Win32::Graphics::Gdi::EnumDisplaySettingsW;
+use windows::Win32::Graphics::Gdi::GetDIBits;
 use windows::Win32::Graphics::Gdi::GetMonitorInfoW;
+use windows::Win32::Graphics::Gdi::GetObjectW;
 use windows::Win32::Graphics::Gdi::MonitorFromPoint;
 use windows::Win32::Graphics::Gdi::MonitorFromWindow;
+use windows::Win32::Graphics::Gdi::BITMAP;
+use windows::Win32::Graphics::Gdi::BITMAPINFO;
+use windows::Win32::Graphics::Gdi::BITMAPINFOHEADER;
+use windows::Win32::Graphics::Gdi::BI_RGB;
+use windows::Win32::Graphics::Gdi::DEVMODEW;
+use windows::Win32::Graphics::Gdi::DIB_RGB_COLORS;
+use windows::Win32::Graphics::Gdi::DISPLAY_DEVICEW;
+use windows::Win32::Graphics::Gdi::ENUM_CURRENT_SETTINGS;
+use windows::Win32::Graphics::Gdi::HBITMAP;
 use windows::Win32::Graphics::Gdi::HDC;
 use windows::Win32::Graphics::Gdi::HMONITOR;
 use windows::Win32::Graphics::Gdi::MONITORENUMPROC;
-use windows::Win32::Graphics::Gdi::MONITORINFO;
+use windows::Win32::Graphics::Gdi::MONITORINFOEXW;
 use windows::Win32::Graphics::Gdi::MONITOR_DEFAULTTONEAREST;
+use windows::Win32::Security::GetTokenInformation;
+use windows::Win32::Security::TokenElevation;
+use windows::Win32::Security::TOKEN_ELEVATION;
+use windows::Win32::Security::TOKEN_QUERY;
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Power::RegisterPowerSettingNotification;
+use windows::Win32::System::Power::DEVICE_NOTIFY_WINDOW_HANDLE;
 use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+use windows::Win32::System::RemoteDesktop::WTSRegisterSessionNotification;
+use windows::Win32::System::RemoteDesktop::NOTIFY_FOR_THIS_SESSION;
 use windows::Win32::System::Threading::AttachThreadInput;
 use windows::Win32::System::Threading::GetCurrentProcessId;
 use windows::Win32::System::Threading::GetCurrentThreadId;
 use windows::Win32::System::Threading::OpenProcess;
+use windows::Win32::System::Threading::OpenProcessToken;
 use windows::Win32::System::Threading::QueryFullProcessImageNameW;
 use windows::Win32::System::Threading::PROCESS_ACCESS_RIGHTS;
-use windows::Win32::System::Threading::PROCESS_QUERY_INFORMATION;
+use windows::Win32::System::Threading::PROCESS_QUERY_LIMITED_INFORMATION;
+use windows::Win32::UI::HiDpi::GetDpiForMonitor;
+use windows::Win32::UI::HiDpi::MDT_EFFECTIVE_DPI;
 use windows::Win32::UI::Input::KeyboardAndMouse::SetFocus;
 use windows::Win32::UI::WindowsAndMessaging::AllowSetForegroundWindow;
+use windows::Win32::UI::WindowsAndMessaging::CreateWindowExW;
+use windows::Win32::UI::WindowsAndMessaging::DestroyIcon;
+use windows::Win32::UI::WindowsAndMessaging::DestroyWindow;
 use windows::Win32::UI::WindowsAndMessaging::EnumWindows;
+use windows::Win32::UI::WindowsAndMessaging::FindWindowExW;
+use windows::Win32::UI::WindowsAndMessaging::GetClassLongPtrW;
 use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
 use windows::Win32::UI::WindowsAndMessaging::GetDesktopWindow;
 use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+use windows::Win32::UI::WindowsAndMessaging::GetIconInfo;
 use windows::Win32::UI::WindowsAndMessaging::GetTopWindow;
 use windows::Win32::UI::WindowsAndMessaging::GetWindow;
 use windows::Win32::UI::WindowsAndMessaging::GetWindowLongPtrW;
@@ -53,7 +93,13 @@ use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
 use windows::Win32::UI::WindowsAndMessaging::IsIconic;
 use windows::Win32::UI::WindowsAndMessaging::IsWindow;
 use windows::Win32::UI::WindowsAndMessaging::IsWindowVisible;
+use windows::Win32::UI::WindowsAndMessaging::IsZoomed;
+use windows::Win32::UI::WindowsAndMessaging::PrivateExtractIconsW;
 use windows::Win32::UI::WindowsAndMessaging::RealGetWindowClassW;
+use windows::Win32::UI::WindowsAndMessaging::RegisterClassW;
+use windows::Win32::UI::WindowsAndMessaging::RegisterShellHookWindow;
+use windows::Win32::UI::WindowsAndMessaging::RegisterWindowMessageW;
+use windows::Win32::UI::WindowsAndMessaging::SendMessageTimeoutW;
 use windows::Win32::UI::WindowsAndMessaging::SetCursorPos;
 use windows::Win32::UI::WindowsAndMessaging::SetForegroundWindow;
 use windows::Win32::UI::WindowsAndMessaging::SetWindowLongPtrW;
@@ -61,23 +107,45 @@ use windows::Win32::UI::WindowsAndMessaging::SetWindowPos;
 use windows::Win32::UI::WindowsAndMessaging::ShowWindow;
 use windows::Win32::UI::WindowsAndMessaging::SystemParametersInfoW;
 use windows::Win32::UI::WindowsAndMessaging::WindowFromPoint;
+use windows::Win32::UI::WindowsAndMessaging::GCLP_HICON;
 use windows::Win32::UI::WindowsAndMessaging::GWL_EXSTYLE;
 use windows::Win32::UI::WindowsAndMessaging::GWL_STYLE;
 use windows::Win32::UI::WindowsAndMessaging::GW_HWNDNEXT;
+use windows::Win32::UI::WindowsAndMessaging::HICON;
+use windows::Win32::UI::WindowsAndMessaging::HMENU;
+use windows::Win32::UI::WindowsAndMessaging::HWND_BOTTOM;
+use windows::Win32::UI::WindowsAndMessaging::HWND_MESSAGE;
 use windows::Win32::UI::WindowsAndMessaging::HWND_NOTOPMOST;
+use windows::Win32::UI::WindowsAndMessaging::HWND_TOP;
 use windows::Win32::UI::WindowsAndMessaging::HWND_TOPMOST;
+use windows::Win32::UI::WindowsAndMessaging::ICONINFO;
+use windows::Win32::UI::WindowsAndMessaging::ICON_BIG;
+use windows::Win32::UI::WindowsAndMessaging::ICON_SMALL2;
 use windows::Win32::UI::WindowsAndMessaging::SHOW_WINDOW_CMD;
+use windows::Win32::UI::WindowsAndMessaging::SMTO_ABORTIFHUNG;
 use windows::Win32::UI::WindowsAndMessaging::SPIF_SENDCHANGE;
+use windows::Win32::UI::WindowsAndMessaging::SPIF_UPDATEINIFILE;
 use windows::Win32::UI::WindowsAndMessaging::SPI_GETACTIVEWINDOWTRACKING;
 use windows::Win32::UI::WindowsAndMessaging::SPI_SETACTIVEWINDOWTRACKING;
+use windows::Win32::UI::WindowsAndMessaging::SPI_SETDESKWALLPAPER;
 use windows::Win32::UI::WindowsAndMessaging::SW_HIDE;
 use windows::Win32::UI::WindowsAndMessaging::SW_MAXIMIZE;
 use windows::Win32::UI::WindowsAndMessaging::SW_MINIMIZE;
 use windows::Win32::UI::WindowsAndMessaging::SW_RESTORE;
+use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNOACTIVATE;
 use windows::Win32::UI::WindowsAndMessaging::SYSTEM_PARAMETERS_INFO_ACTION;
 use windows::Win32::UI::WindowsAndMessaging::SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS;
+use windows::Win32::UI::WindowsAndMessaging::WINDOW_EX_STYLE;
 use windows::Win32::UI::WindowsAndMessaging::WINDOW_LONG_PTR_INDEX;
+use windows::Win32::UI::WindowsAndMessaging::WINDOW_STYLE;
+use windows::Win32::UI::WindowsAndMessaging::WM_GETICON;
+use windows::Win32::UI::WindowsAndMessaging::WNDCLASSW;
 use windows::Win32::UI::WindowsAndMessaging::WNDENUMPROC;
+use windows::Win32::UI::WindowsAndMessaging::WNDPROC;
+use windows::Win32::UI::WindowsAndMessaging::WS_EX_NOACTIVATE;
+use windows::Win32::UI::WindowsAndMessaging::WS_EX_TOOLWINDOW;
+use windows::Win32::UI::WindowsAndMessaging::WS_EX_TOPMOST;
+use windows::Win32::UI::WindowsAndMessaging::WS_POPUP;
 
 use komorebi_core::Rect;
 
@@ -86,8 +154,13 @@ use crate::monitor;
 use crate::monitor::Monitor;
 use crate::ring::Ring;
 use crate::set_window_position::SetWindowPosition;
+use crate::urgency::SHELL_HOOK_MESSAGE;
+use crate::window::Window;
 use crate::windows_callbacks;
 
+// Not exposed by the windows crate
+const EDD_GET_DEVICE_INTERFACE_NAME: u32 = 0x1;
+
 pub enum WindowsResult<T, E> {
     Err(E),
     Ok(T),
@@ -153,6 +226,14 @@ impl<T> ProcessWindowsCrateResult<T> for WindowsCrateResult<T> {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub enum ZOrder {
+    Top,
+    Bottom,
+    Topmost,
+    NotTopmost,
+}
+
 pub struct WindowsApi;
 
 impl WindowsApi {
@@ -196,39 +277,20 @@ impl WindowsApi {
             .process()
     }
 
-    pub fn load_workspace_information(monitors: &mut Ring<Monitor>) -> Result<()> {
-        for monitor in monitors.elements_mut() {
-            let monitor_id = monitor.id();
-            if let Some(workspace) = monitor.workspaces_mut().front_mut() {
-                // EnumWindows will enumerate through windows on all monitors
-                Self::enum_windows(
-                    Option::Some(windows_callbacks::enum_window),
-                    workspace.containers_mut() as *mut VecDeque<Container> as isize,
-                )?;
-
-                // Ensure that the resize_dimensions Vec length matches the number of containers for
-                // the potential later calls to workspace.remove_window later in this fn
-                let len = workspace.containers().len();
-                workspace.resize_dimensions_mut().resize(len, None);
-
-                // We have to prune each monitor's primary workspace of undesired windows here
-                let mut windows_on_other_monitors = vec![];
-
-                for container in workspace.containers_mut() {
-                    for window in container.windows() {
-                        if Self::monitor_from_window(window.hwnd()) != monitor_id {
-                            windows_on_other_monitors.push(window.hwnd().0);
-                        }
-                    }
-                }
+    pub fn manageable_windows() -> Result<Vec<Window>> {
+        let mut containers: VecDeque<Container> = VecDeque::new();
+        let containers_ref: &mut VecDeque<Container> = &mut containers;
 
-                for hwnd in windows_on_other_monitors {
-                    workspace.remove_window(hwnd)?;
-                }
-            }
-        }
+        Self::enum_windows(
+            Option::Some(windows_callbacks::enum_window),
+            containers_ref as *mut VecDeque<Container> as isize,
+        )?;
 
-        Ok(())
+        Ok(containers
+            .iter()
+            .filter_map(Container::focused_window)
+            .copied()
+            .collect())
     }
 
     pub fn allow_set_foreground_window(process_id: u32) -> Result<()> {
@@ -249,6 +311,44 @@ impl WindowsApi {
         unsafe { MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST) }.0
     }
 
+    /// The DPI of the monitor containing the centre of `rect`, where 96 is 100% scaling. The
+    /// effective DPI is always reported as 96 to a process which is not DPI aware, which sees a
+    /// scaled monitor as smaller than the physical resolution of the display instead, so both are
+    /// taken into account.
+    pub fn dpi_for_rect(rect: &Rect) -> Result<u32> {
+        let hmonitor = Self::monitor_from_point(POINT {
+            x: rect.left + rect.right / 2,
+            y: rect.top + rect.bottom / 2,
+        });
+
+        let monitor_info = Self::monitor_info_w(HMONITOR(hmonitor))?;
+        let device = String::from_utf16_lossy(&monitor_info.szDevice)
+            .trim_end_matches('\0')
+            .to_string();
+
+        let physical_width = Self::display_settings(&device)?.dmPelsWidth;
+        let logical_width = u32::try_from(
+            monitor_info.monitorInfo.rcMonitor.right - monitor_info.monitorInfo.rcMonitor.left,
+        )?;
+
+        if logical_width == 0 {
+            return Err(anyhow!("the monitor has no width"));
+        }
+
+        let mut dpi_x = 0;
+        let mut dpi_y = 0;
+        unsafe {
+            GetDpiForMonitor(
+                HMONITOR(hmonitor),
+                MDT_EFFECTIVE_DPI,
+                &mut dpi_x,
+                &mut dpi_y,
+            )?;
+        }
+
+        Ok(dpi_x * physical_width / logical_width)
+    }
+
     pub fn position_window(hwnd: HWND, layout: &Rect, top: bool) -> Result<()> {
         let flags = SetWindowPosition::NO_ACTIVATE;
 
@@ -256,6 +356,32 @@ impl WindowsApi {
         Self::set_window_pos(hwnd, layout, position, flags.bits())
     }
 
+    /// Bring a window to the top of the topmost band without moving, resizing or activating it
+    pub fn raise_topmost(hwnd: HWND) -> Result<()> {
+        let flags = SetWindowPosition::NO_MOVE
+            | SetWindowPosition::NO_SIZE
+            | SetWindowPosition::NO_ACTIVATE;
+
+        Self::set_window_pos(hwnd, &Rect::default(), HWND_TOPMOST, flags.bits())
+    }
+
+    /// Move a window into or out of the topmost band, or to the top or bottom of the Z order
+    /// within its band, without moving, resizing or activating it
+    pub fn set_z_order(hwnd: HWND, position: ZOrder) -> Result<()> {
+        let flags = SetWindowPosition::NO_MOVE
+            | SetWindowPosition::NO_SIZE
+            | SetWindowPosition::NO_ACTIVATE;
+
+        let position = match position {
+            ZOrder::Top => HWND_TOP,
+            ZOrder::Bottom => HWND_BOTTOM,
+            ZOrder::Topmost => HWND_TOPMOST,
+            ZOrder::NotTopmost => HWND_NOTOPMOST,
+        };
+
+        Self::set_window_pos(hwnd, &Rect::default(), position, flags.bits())
+    }
+
     pub fn set_window_pos(hwnd: HWND, layout: &Rect, position: HWND, flags: u32) -> Result<()> {
         unsafe {
             SetWindowPos(
@@ -339,7 +465,7 @@ impl WindowsApi {
         Ok(Rect::from(rect))
     }
 
-    fn set_cursor_pos(x: i32, y: i32) -> Result<()> {
+    pub fn set_cursor_pos(x: i32, y: i32) -> Result<()> {
         unsafe { SetCursorPos(x, y) }.ok().process()
     }
 
@@ -354,10 +480,6 @@ impl WindowsApi {
         unsafe { WindowFromPoint(point) }.ok().process()
     }
 
-    pub fn window_at_cursor_pos() -> Result<isize> {
-        Self::window_from_point(Self::cursor_pos()?)
-    }
-
     pub fn center_cursor_in_rect(rect: &Rect) -> Result<()> {
         Self::set_cursor_pos(rect.left + (rect.right / 2), rect.top + (rect.bottom / 2))
     }
@@ -393,6 +515,290 @@ impl WindowsApi {
         }
     }
 
+    pub fn register_window_class(class_name: &str, window_proc: WNDPROC) -> Result<()> {
+        let mut class_name: Vec<u16> = OsStr::new(class_name)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        let class = WNDCLASSW {
+            lpfnWndProc: window_proc,
+            hInstance: unsafe { GetModuleHandleW(PWSTR::default()) },
+            lpszClassName: PWSTR(class_name.as_mut_ptr()),
+            ..WNDCLASSW::default()
+        };
+
+        Result::from(WindowsResult::from(i32::from(unsafe {
+            RegisterClassW(&class)
+        })))?;
+
+        Ok(())
+    }
+
+    /// Create a hidden, topmost popup window of a class registered with `register_window_class`
+    /// which cannot be activated and does not appear in the taskbar
+    pub fn create_overlay_window(class_name: &str, rect: &Rect) -> Result<HWND> {
+        let mut class_name: Vec<u16> = OsStr::new(class_name)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+                PWSTR(class_name.as_mut_ptr()),
+                PWSTR(class_name.as_mut_ptr()),
+                WS_POPUP,
+                rect.left,
+                rect.top,
+                rect.right,
+                rect.bottom,
+                HWND::default(),
+                HMENU::default(),
+                GetModuleHandleW(PWSTR::default()),
+                std::ptr::null(),
+            )
+        };
+
+        if hwnd.0 == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(hwnd)
+    }
+
+    pub fn show_window_without_activating(hwnd: HWND) {
+        Self::show_window(hwnd, SW_SHOWNOACTIVATE);
+    }
+
+    /// The taskbar on the primary monitor followed by the taskbars on any secondary monitors
+    pub fn taskbar_windows() -> Vec<HWND> {
+        let mut taskbars = vec![];
+
+        for class_name in ["Shell_TrayWnd", "Shell_SecondaryTrayWnd"] {
+            let mut class_name: Vec<u16> = OsStr::new(class_name)
+                .encode_wide()
+                .chain(std::iter::once(0))
+                .collect();
+
+            let mut hwnd = HWND::default();
+            loop {
+                hwnd = unsafe {
+                    FindWindowExW(
+                        HWND::default(),
+                        hwnd,
+                        PWSTR(class_name.as_mut_ptr()),
+                        PWSTR::default(),
+                    )
+                };
+
+                if hwnd.0 == 0 {
+                    break;
+                }
+
+                taskbars.push(hwnd);
+            }
+        }
+
+        taskbars
+    }
+
+    pub fn destroy_window(hwnd: HWND) -> Result<()> {
+        unsafe { DestroyWindow(hwnd) }.ok().process()
+    }
+
+    pub fn create_message_window(class_name: &str, window_proc: WNDPROC) -> Result<HWND> {
+        Self::register_window_class(class_name, window_proc)?;
+
+        let mut class_name: Vec<u16> = OsStr::new(class_name)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                PWSTR(class_name.as_mut_ptr()),
+                PWSTR(class_name.as_mut_ptr()),
+                WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                HMENU::default(),
+                GetModuleHandleW(PWSTR::default()),
+                std::ptr::null(),
+            )
+        };
+
+        if hwnd.0 == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(hwnd)
+    }
+
+    pub fn register_session_notifications(hwnd: HWND) -> Result<()> {
+        unsafe { WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) }
+            .ok()
+            .process()?;
+
+        Result::from(WindowsResult::from(
+            unsafe {
+                RegisterPowerSettingNotification(
+                    HANDLE(hwnd.0),
+                    &windows_callbacks::GUID_CONSOLE_DISPLAY_STATE,
+                    DEVICE_NOTIFY_WINDOW_HANDLE,
+                )
+            }
+            .0,
+        ))?;
+
+        Ok(())
+    }
+
+    pub fn register_shell_hook(hwnd: HWND) -> Result<()> {
+        let mut message_name: Vec<u16> = OsStr::new("SHELLHOOK")
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        let message = unsafe { RegisterWindowMessageW(PWSTR(message_name.as_mut_ptr())) };
+        if message == 0 {
+            return Err(anyhow!("could not register the shell hook message"));
+        }
+
+        SHELL_HOOK_MESSAGE.store(message, Ordering::SeqCst);
+
+        unsafe { RegisterShellHookWindow(hwnd) }.ok().process()
+    }
+
+    /// The large icon of a window, falling back to its small icon and then the icon of its class.
+    /// These icons belong to the window and must not be destroyed.
+    pub fn window_icon(hwnd: HWND) -> Option<HICON> {
+        for icon_type in [ICON_BIG, ICON_SMALL2] {
+            let mut icon = 0;
+            unsafe {
+                SendMessageTimeoutW(
+                    hwnd,
+                    WM_GETICON,
+                    WPARAM(usize::try_from(icon_type).ok()?),
+                    LPARAM(0),
+                    SMTO_ABORTIFHUNG,
+                    100,
+                    &mut icon,
+                );
+            }
+
+            if icon != 0 {
+                return Option::from(HICON(isize::try_from(icon).ok()?));
+            }
+        }
+
+        let icon = unsafe { GetClassLongPtrW(hwnd, GCLP_HICON) };
+        if icon == 0 {
+            None
+        } else {
+            Option::from(HICON(isize::try_from(icon).ok()?))
+        }
+    }
+
+    /// The first icon in an executable at the given size, which must be destroyed by the caller
+    pub fn exe_icon(path: &str, size: i32) -> Result<HICON> {
+        let mut file_name: Vec<u16> = OsStr::new(path)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        let mut icon = HICON::default();
+        let mut icon_id = 0;
+        let extracted = unsafe {
+            PrivateExtractIconsW(
+                PWSTR(file_name.as_mut_ptr()),
+                0,
+                size,
+                size,
+                &mut icon,
+                &mut icon_id,
+                1,
+                0,
+            )
+        };
+
+        if extracted == 0 || icon.is_invalid() {
+            return Err(anyhow!("there is no icon in {}", path));
+        }
+
+        Ok(icon)
+    }
+
+    pub fn destroy_icon(icon: HICON) -> Result<()> {
+        unsafe { DestroyIcon(icon) }.ok().process()
+    }
+
+    /// The width, height and 32-bit BGRA pixels of an icon, with rows stored from the bottom up
+    pub fn icon_pixels(icon: HICON) -> Result<(i32, i32, Vec<u8>)> {
+        let mut info = ICONINFO::default();
+        unsafe { GetIconInfo(icon, &mut info) }.ok().process()?;
+
+        let pixels = Self::bitmap_pixels(info.hbmColor);
+
+        unsafe {
+            DeleteObject(info.hbmColor);
+            DeleteObject(info.hbmMask);
+        }
+
+        pixels
+    }
+
+    fn bitmap_pixels(bitmap: HBITMAP) -> Result<(i32, i32, Vec<u8>)> {
+        let mut header = BITMAP::default();
+        let header_size = i32::try_from(std::mem::size_of::<BITMAP>())?;
+        if unsafe { GetObjectW(bitmap, header_size, std::ptr::addr_of_mut!(header).cast()) } == 0 {
+            return Err(anyhow!("there is no colour bitmap"));
+        }
+
+        let width = header.bmWidth;
+        let height = header.bmHeight;
+
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: u32::try_from(std::mem::size_of::<BITMAPINFOHEADER>())?,
+                biWidth: width,
+                biHeight: height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: u32::try_from(BI_RGB)?,
+                ..BITMAPINFOHEADER::default()
+            },
+            ..BITMAPINFO::default()
+        };
+
+        let mut pixels = vec![0_u8; usize::try_from(width * height * 4)?];
+
+        let hdc = unsafe { CreateCompatibleDC(HDC::default()) };
+        let lines = unsafe {
+            GetDIBits(
+                hdc,
+                bitmap,
+                0,
+                u32::try_from(height)?,
+                pixels.as_mut_ptr().cast(),
+                &mut info,
+                DIB_RGB_COLORS,
+            )
+        };
+
+        unsafe { DeleteDC(hdc) };
+
+        if lines == 0 {
+            return Err(anyhow!("could not read the pixels of the bitmap"));
+        }
+
+        Ok((width, height, pixels))
+    }
+
     pub fn attach_thread_input(thread_id: u32, target_thread_id: u32, attach: bool) -> Result<()> {
         unsafe { AttachThreadInput(thread_id, target_thread_id, attach) }
             .ok()
@@ -457,8 +863,49 @@ impl WindowsApi {
             .process()
     }
 
+    // Limited information is all that an unelevated process is allowed to query about an elevated
+    // process, and it is enough to get the exe path and the elevation of the process
     pub fn process_handle(process_id: u32) -> Result<HANDLE> {
-        Self::open_process(PROCESS_QUERY_INFORMATION, false, process_id)
+        Self::open_process(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id)
+    }
+
+    pub fn process_exists(process_id: u32) -> bool {
+        Self::process_handle(process_id).map_or(false, |process| {
+            unsafe { CloseHandle(process) };
+            true
+        })
+    }
+
+    /// Whether the process is running with an elevated (administrator) token
+    pub fn is_process_elevated(process_id: u32) -> Result<bool> {
+        let process = Self::process_handle(process_id)?;
+
+        let mut token = HANDLE::default();
+        let opened = unsafe { OpenProcessToken(process, TOKEN_QUERY, &mut token) }
+            .ok()
+            .process();
+
+        unsafe { CloseHandle(process) };
+        opened?;
+
+        let mut elevation = TOKEN_ELEVATION::default();
+        let mut size = 0;
+        let queried = unsafe {
+            GetTokenInformation(
+                token,
+                TokenElevation,
+                std::ptr::addr_of_mut!(elevation).cast::<c_void>(),
+                u32::try_from(std::mem::size_of::<TOKEN_ELEVATION>())?,
+                &mut size,
+            )
+        }
+        .ok()
+        .process();
+
+        unsafe { CloseHandle(token) };
+        queried?;
+
+        Ok(elevation.TokenIsElevated != 0)
     }
 
     pub fn exe_path(handle: HANDLE) -> Result<String> {
@@ -541,9 +988,13 @@ impl WindowsApi {
         unsafe { IsIconic(hwnd) }.into()
     }
 
-    pub fn monitor_info_w(hmonitor: HMONITOR) -> Result<MONITORINFO> {
-        let mut monitor_info: MONITORINFO = unsafe { std::mem::zeroed() };
-        monitor_info.cbSize = u32::try_from(std::mem::size_of::<MONITORINFO>())?;
+    pub fn is_zoomed(hwnd: HWND) -> bool {
+        unsafe { IsZoomed(hwnd) }.into()
+    }
+
+    pub fn monitor_info_w(hmonitor: HMONITOR) -> Result<MONITORINFOEXW> {
+        let mut monitor_info: MONITORINFOEXW = unsafe { std::mem::zeroed() };
+        monitor_info.monitorInfo.cbSize = u32::try_from(std::mem::size_of::<MONITORINFOEXW>())?;
 
         unsafe { GetMonitorInfoW(hmonitor, std::ptr::addr_of_mut!(monitor_info).cast()) }
             .ok()
@@ -554,12 +1005,82 @@ impl WindowsApi {
 
     pub fn monitor(hmonitor: isize) -> Result<Monitor> {
         let monitor_info = Self::monitor_info_w(HMONITOR(hmonitor))?;
+        let device = String::from_utf16_lossy(&monitor_info.szDevice)
+            .trim_end_matches('\0')
+            .to_string();
+
+        // Frequencies of 0 and 1 mean that the display is using the hardware's default rate
+        let refresh_rate = Self::display_frequency(&device)
+            .ok()
+            .filter(|frequency| *frequency > 1);
+        let device_id = Self::display_device_id(&device).ok();
 
-        Ok(monitor::new(
+        let mut monitor = monitor::new(
             hmonitor,
-            monitor_info.rcMonitor.into(),
-            monitor_info.rcWork.into(),
-        ))
+            monitor_info.monitorInfo.rcMonitor.into(),
+            monitor_info.monitorInfo.rcWork.into(),
+            device,
+        );
+
+        monitor.set_refresh_rate(refresh_rate);
+        monitor.set_device_id(device_id);
+
+        Ok(monitor)
+    }
+
+    pub fn display_frequency(device: &str) -> Result<u32> {
+        Ok(Self::display_settings(device)?.dmDisplayFrequency)
+    }
+
+    /// The current display mode of a display device, in physical pixels
+    pub fn display_settings(device: &str) -> Result<DEVMODEW> {
+        let mut device: Vec<u16> = OsStr::new(device)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        let mut mode: DEVMODEW = unsafe { std::mem::zeroed() };
+        mode.dmSize = u16::try_from(std::mem::size_of::<DEVMODEW>())?;
+
+        unsafe {
+            EnumDisplaySettingsW(PWSTR(device.as_mut_ptr()), ENUM_CURRENT_SETTINGS, &mut mode)
+        }
+        .ok()
+        .process()?;
+
+        Ok(mode)
+    }
+
+    pub fn display_device_id(device: &str) -> Result<String> {
+        let mut device: Vec<u16> = OsStr::new(device)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+
+        let mut display_device: DISPLAY_DEVICEW = unsafe { std::mem::zeroed() };
+        display_device.cb = u32::try_from(std::mem::size_of::<DISPLAY_DEVICEW>())?;
+
+        // The first display device attached to an adapter is the monitor connected to it
+        unsafe {
+            EnumDisplayDevicesW(
+                PWSTR(device.as_mut_ptr()),
+                0,
+                &mut display_device,
+                EDD_GET_DEVICE_INTERFACE_NAME,
+            )
+        }
+        .ok()
+        .process()?;
+
+        let device_id = String::from_utf16_lossy(&display_device.DeviceID)
+            .trim_end_matches('\0')
+            .to_string();
+
+        if device_id.is_empty() {
+            return Err(anyhow!("there is no device id for this display"));
+        }
+
+        Ok(device_id)
     }
 
     #[allow(dead_code)]
@@ -607,4 +1128,15 @@ impl WindowsApi {
             SPIF_SENDCHANGE,
         )
     }
+
+    pub fn set_wallpaper(path: &Path) -> Result<()> {
+        let mut path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+
+        Self::system_parameters_info_w(
+            SPI_SETDESKWALLPAPER,
+            0,
+            path.as_mut_ptr().cast(),
+            SPIF_UPDATEINIFILE | SPIF_SENDCHANGE,
+        )
+    }
 }
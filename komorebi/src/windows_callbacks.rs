@@ -1,20 +1,67 @@
 use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
+use windows::core::GUID;
 use windows::Win32::Foundation::BOOL;
 use windows::Win32::Foundation::HWND;
 use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::LRESULT;
+use windows::Win32::Foundation::PWSTR;
 use windows::Win32::Foundation::RECT;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::Graphics::Gdi::BeginPaint;
+use windows::Win32::Graphics::Gdi::CreateSolidBrush;
+use windows::Win32::Graphics::Gdi::DeleteObject;
+use windows::Win32::Graphics::Gdi::DrawTextW;
+use windows::Win32::Graphics::Gdi::EndPaint;
+use windows::Win32::Graphics::Gdi::FillRect;
+use windows::Win32::Graphics::Gdi::SetBkColor;
+use windows::Win32::Graphics::Gdi::SetTextColor;
+use windows::Win32::Graphics::Gdi::DT_CENTER;
+use windows::Win32::Graphics::Gdi::DT_SINGLELINE;
+use windows::Win32::Graphics::Gdi::DT_VCENTER;
 use windows::Win32::Graphics::Gdi::HDC;
 use windows::Win32::Graphics::Gdi::HMONITOR;
+use windows::Win32::Graphics::Gdi::PAINTSTRUCT;
+use windows::Win32::System::Power::POWERBROADCAST_SETTING;
 use windows::Win32::UI::Accessibility::HWINEVENTHOOK;
+use windows::Win32::UI::WindowsAndMessaging::DefWindowProcW;
+use windows::Win32::UI::WindowsAndMessaging::GetClientRect;
+use windows::Win32::UI::WindowsAndMessaging::WM_PAINT;
+use windows::Win32::UI::WindowsAndMessaging::WM_POWERBROADCAST;
+use windows::Win32::UI::WindowsAndMessaging::WM_WTSSESSION_CHANGE;
 
 use crate::container::Container;
+use crate::float_positions;
+use crate::hints::HINT_BACKGROUND_COLOUR;
+use crate::hints::HINT_LABELS;
+use crate::hints::HINT_TEXT_COLOUR;
+use crate::learning;
+use crate::learning::SuggestionReason;
 use crate::monitor::Monitor;
 use crate::ring::Ring;
+use crate::session::SessionEvent;
+use crate::session::SESSION_CALLBACK_CHANNEL;
+use crate::urgency::SHELL_HOOK_MESSAGE;
+use crate::urgency::URGENCY_CALLBACK_CHANNEL;
 use crate::window::Window;
 use crate::window_manager_event::WindowManagerEvent;
 use crate::windows_api::WindowsApi;
+use crate::winevent_listener::LAST_WINEVENT_TIMESTAMP;
 use crate::winevent_listener::WINEVENT_CALLBACK_CHANNEL;
+use crate::FLOATED_ONCE_HWNDS;
+use crate::LAST_CURSOR_POSITIONS;
+use crate::WINDOW_SIGNATURES;
+
+const WTS_SESSION_LOCK: usize = 0x7;
+const WTS_SESSION_UNLOCK: usize = 0x8;
+const PBT_POWERSETTINGCHANGE: usize = 0x8013;
+// HSHELL_REDRAW with HSHELL_HIGHBIT set, sent when a window flashes its taskbar button
+const HSHELL_FLASH: usize = 0x8006;
+pub const GUID_CONSOLE_DISPLAY_STATE: GUID =
+    GUID::from_u128(0x6fe6_9556_704a_47a0_8f24_c28d_936f_da47);
 
 pub extern "system" fn valid_display_monitors(
     hmonitor: HMONITOR,
@@ -80,6 +127,10 @@ pub extern "system" fn win_event_hook(
     _id_event_thread: u32,
     _dwms_event_time: u32,
 ) {
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        LAST_WINEVENT_TIMESTAMP.store(now.as_secs(), Ordering::SeqCst);
+    }
+
     // OBJID_WINDOW
     if id_object != 0 {
         return;
@@ -93,6 +144,53 @@ pub extern "system" fn win_event_hook(
         Some(event) => event,
     };
 
+    // Window handles can be reused once a window has been closed
+    if matches!(event_type, WindowManagerEvent::Destroy(..)) {
+        FLOATED_ONCE_HWNDS
+            .lock()
+            .retain(|floated| *floated != window.hwnd);
+        LAST_CURSOR_POSITIONS.lock().remove(&window.hwnd);
+        WINDOW_SIGNATURES.lock().remove(&window.hwnd);
+    }
+
+    // Borderless fullscreen windows are popups which would never be managed, but the window
+    // manager still has to know about them to stop tiling underneath them
+    if matches!(
+        event_type,
+        WindowManagerEvent::FocusChange(..)
+            | WindowManagerEvent::Show(..)
+            | WindowManagerEvent::MoveResizeEnd(..)
+    ) && window.is_fullscreen().unwrap_or_default()
+    {
+        WINEVENT_CALLBACK_CHANNEL
+            .lock()
+            .0
+            .send(event_type)
+            .expect("could not send message on WINEVENT_CALLBACK_CHANNEL");
+
+        return;
+    }
+
+    // A managed window which no longer passes the checks below after a change to its class or
+    // styles still has to reach the window manager so that it can be unmanaged, as does a refused
+    // window which may now pass them
+    if matches!(
+        event_type,
+        WindowManagerEvent::FocusChange(..)
+            | WindowManagerEvent::Show(..)
+            | WindowManagerEvent::TitleChange(..)
+            | WindowManagerEvent::MoveResizeEnd(..)
+    ) && window.has_morphed()
+    {
+        WINEVENT_CALLBACK_CHANNEL
+            .lock()
+            .0
+            .send(event_type)
+            .expect("could not send message on WINEVENT_CALLBACK_CHANNEL");
+
+        return;
+    }
+
     if let Ok(should_manage) = window.should_manage(Option::from(event_type)) {
         if should_manage {
             WINEVENT_CALLBACK_CHANNEL
@@ -100,6 +198,117 @@ pub extern "system" fn win_event_hook(
                 .0
                 .send(event_type)
                 .expect("could not send message on WINEVENT_CALLBACK_CHANNEL");
+        } else {
+            // Windows floated by rules never reach the window manager, so their positions are
+            // remembered and restored on another thread instead
+            if matches!(
+                event_type,
+                WindowManagerEvent::Show(..) | WindowManagerEvent::MoveResizeEnd(..)
+            ) {
+                float_positions::send(event_type);
+            }
+
+            if matches!(event_type, WindowManagerEvent::Show(..))
+                && WindowsApi::is_window_visible(window.hwnd())
+            {
+                learning::record(window, SuggestionReason::Refused);
+
+                if let Ok(signature) = window.morph_signature() {
+                    WINDOW_SIGNATURES.lock().insert(window.hwnd, signature);
+                }
+            }
         }
     }
 }
+
+pub extern "system" fn session_window_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    let event = match (message, wparam.0) {
+        (WM_WTSSESSION_CHANGE, WTS_SESSION_LOCK) => Some(SessionEvent::Locked),
+        (WM_WTSSESSION_CHANGE, WTS_SESSION_UNLOCK) => Some(SessionEvent::Unlocked),
+        (WM_POWERBROADCAST, PBT_POWERSETTINGCHANGE) => {
+            let setting = unsafe { &*(lparam.0 as *const POWERBROADCAST_SETTING) };
+
+            // The display state is 0 when off, 1 when on and 2 when dimmed
+            if setting.PowerSetting == GUID_CONSOLE_DISPLAY_STATE {
+                match setting.Data[0] {
+                    0 => Some(SessionEvent::DisplayOff),
+                    _ => Some(SessionEvent::DisplayOn),
+                }
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+
+    if let Some(event) = event {
+        if let Err(error) = SESSION_CALLBACK_CHANNEL.lock().0.send(event) {
+            tracing::error!("could not send session event: {}", error);
+        }
+    }
+
+    unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+}
+
+pub extern "system" fn urgency_window_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if message == SHELL_HOOK_MESSAGE.load(Ordering::SeqCst) && wparam.0 == HSHELL_FLASH {
+        if let Err(error) = URGENCY_CALLBACK_CHANNEL.lock().0.send(lparam.0) {
+            tracing::error!("could not send urgent window: {}", error);
+        }
+    }
+
+    unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+}
+
+pub extern "system" fn hint_window_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if message == WM_PAINT {
+        let mut label: Vec<u16> = HINT_LABELS
+            .lock()
+            .get(&hwnd.0)
+            .map(|label| label.encode_utf16().collect())
+            .unwrap_or_default();
+
+        unsafe {
+            let mut paint = PAINTSTRUCT::default();
+            let hdc = BeginPaint(hwnd, &mut paint);
+
+            let mut rect = RECT::default();
+            GetClientRect(hwnd, &mut rect);
+
+            let brush = CreateSolidBrush(HINT_BACKGROUND_COLOUR);
+            FillRect(hdc, &rect, brush);
+            DeleteObject(brush);
+
+            SetBkColor(hdc, HINT_BACKGROUND_COLOUR);
+            SetTextColor(hdc, HINT_TEXT_COLOUR);
+            DrawTextW(
+                hdc,
+                PWSTR(label.as_mut_ptr()),
+                i32::try_from(label.len()).unwrap_or_default(),
+                &mut rect,
+                DT_CENTER | DT_VCENTER | DT_SINGLELINE,
+            );
+
+            EndPaint(hwnd, &paint);
+        }
+
+        return LRESULT(0);
+    }
+
+    unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+}
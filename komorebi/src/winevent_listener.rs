@@ -1,4 +1,6 @@
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicIsize;
+use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::thread;
@@ -10,6 +12,8 @@ use lazy_static::lazy_static;
 use parking_lot::Mutex;
 use windows::Win32::Foundation::HWND;
 use windows::Win32::UI::Accessibility::SetWinEventHook;
+use windows::Win32::UI::Accessibility::UnhookWinEvent;
+use windows::Win32::UI::Accessibility::HWINEVENTHOOK;
 use windows::Win32::UI::WindowsAndMessaging::DispatchMessageW;
 use windows::Win32::UI::WindowsAndMessaging::PeekMessageW;
 use windows::Win32::UI::WindowsAndMessaging::TranslateMessage;
@@ -26,40 +30,38 @@ lazy_static! {
         Arc::new(Mutex::new(crossbeam_channel::unbounded()));
 }
 
+pub static WINEVENT_HOOK: AtomicIsize = AtomicIsize::new(0);
+pub static LAST_WINEVENT_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+pub static REREGISTER_WINEVENT_HOOK: AtomicBool = AtomicBool::new(false);
+
 #[derive(Debug, Clone)]
 pub struct WinEventListener {
-    hook: Arc<AtomicIsize>,
     outgoing_events: Arc<Mutex<Sender<WindowManagerEvent>>>,
 }
 
 pub fn new(outgoing: Arc<Mutex<Sender<WindowManagerEvent>>>) -> WinEventListener {
     WinEventListener {
-        hook: Arc::new(AtomicIsize::new(0)),
         outgoing_events: outgoing,
     }
 }
 
 impl WinEventListener {
     pub fn start(self) {
-        let hook = self.hook.clone();
         let outgoing = self.outgoing_events.lock().clone();
 
-        thread::spawn(move || unsafe {
-            let hook_ref = SetWinEventHook(
-                EVENT_MIN as u32,
-                EVENT_MAX as u32,
-                None,
-                Some(windows_callbacks::win_event_hook),
-                0,
-                0,
-                0,
-            );
-
-            hook.store(hook_ref.0, Ordering::SeqCst);
+        thread::spawn(move || {
+            register_hook();
 
             // The code in the callback doesn't work in its own loop, needs to be within
             // the MessageLoop callback for the winevent callback to even fire
             MessageLoop::start(10, |_msg| {
+                // Hooks are owned by the thread that registered them, so any request to
+                // re-register them has to be picked up and handled here
+                if REREGISTER_WINEVENT_HOOK.swap(false, Ordering::SeqCst) {
+                    unregister_hook();
+                    register_hook();
+                }
+
                 if let Ok(event) = WINEVENT_CALLBACK_CHANNEL.lock().1.try_recv() {
                     match outgoing.send(event) {
                         Ok(_) => {}
@@ -75,6 +77,31 @@ impl WinEventListener {
     }
 }
 
+fn register_hook() {
+    let hook_ref = unsafe {
+        SetWinEventHook(
+            EVENT_MIN as u32,
+            EVENT_MAX as u32,
+            None,
+            Some(windows_callbacks::win_event_hook),
+            0,
+            0,
+            0,
+        )
+    };
+
+    tracing::info!("registered winevent hook: {}", hook_ref.0);
+    WINEVENT_HOOK.store(hook_ref.0, Ordering::SeqCst);
+}
+
+fn unregister_hook() {
+    let hook = WINEVENT_HOOK.swap(0, Ordering::SeqCst);
+    if hook != 0 {
+        tracing::info!("unregistering winevent hook: {}", hook);
+        unsafe { UnhookWinEvent(HWINEVENTHOOK(hook)) };
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct MessageLoop;
 
@@ -1,5 +1,10 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
 
 use color_eyre::eyre::anyhow;
 use color_eyre::Result;
@@ -7,8 +12,12 @@ use getset::CopyGetters;
 use getset::Getters;
 use getset::MutGetters;
 use getset::Setters;
+use nanoid::nanoid;
 use serde::Serialize;
+use sysinfo::Pid;
+use windows::Win32::Foundation::HWND;
 
+use komorebi_core::ApplicationPlaceholder;
 use komorebi_core::Axis;
 use komorebi_core::CycleDirection;
 use komorebi_core::DefaultLayout;
@@ -17,13 +26,21 @@ use komorebi_core::OperationDirection;
 use komorebi_core::Rect;
 
 use crate::container::Container;
+use crate::monitor::MonitorGroup;
+use crate::operation_guard::OperationGuard;
+use crate::process_event;
 use crate::ring::Ring;
 use crate::window::Window;
 use crate::windows_api::WindowsApi;
+use crate::windows_api::ZOrder;
+use crate::RETILE_BATCH_SIZE;
 
 #[derive(Debug, Clone, Serialize, Getters, CopyGetters, MutGetters, Setters)]
 pub struct Workspace {
-    #[getset(set = "pub")]
+    /// Stays the same when the workspace is renamed or reordered, so that rules can be bound to it
+    #[getset(get = "pub")]
+    id: String,
+    #[getset(get = "pub", set = "pub")]
     name: Option<String>,
     containers: Ring<Container>,
     #[getset(get = "pub", get_mut = "pub", set = "pub")]
@@ -36,50 +53,213 @@ pub struct Workspace {
     #[serde(skip_serializing)]
     #[getset(get_copy = "pub", set = "pub")]
     maximized_window_restore_idx: Option<usize>,
+    #[getset(get = "pub", set = "pub")]
+    maximized_container: Option<String>,
+    /// While focus mode is enabled, the monocle container is drawn as a centred column of this
+    /// percentage of the work area's width and every other container is hidden
+    #[getset(get_copy = "pub", set = "pub")]
+    focus_mode: Option<u8>,
+    /// Floating windows, such as picture-in-picture video, which are kept on top of the monocle
+    /// container instead of being covered by it
+    #[getset(get = "pub", get_mut = "pub")]
+    monocle_pip_hwnds: Vec<isize>,
+    /// Windows which have been raised by hand, from lowest to highest, which are restacked in
+    /// this order whenever the workspace is updated or restored
+    #[getset(get = "pub")]
+    raised_hwnds: Vec<isize>,
+    /// Windows which have been lowered by hand, from highest to lowest
+    #[getset(get = "pub")]
+    lowered_hwnds: Vec<isize>,
+    /// Windows which are kept above every other window
+    #[getset(get = "pub")]
+    on_top_hwnds: Vec<isize>,
     #[getset(get = "pub", get_mut = "pub")]
     floating_windows: Vec<Window>,
+    #[serde(skip_serializing)]
+    float_restore_positions: HashMap<isize, TilePosition>,
+    #[serde(skip_serializing)]
+    natively_maximized_hwnds: HashSet<isize>,
+    /// Windows hidden by the windows which swallowed them, keyed by the swallowing window
+    #[serde(skip_serializing)]
+    #[getset(get = "pub", get_mut = "pub")]
+    swallowed_windows: HashMap<isize, Window>,
+    #[serde(skip_serializing)]
+    #[getset(get_mut = "pub")]
+    snapshot_placements: Vec<(ApplicationPlaceholder, usize)>,
     #[getset(get = "pub", get_mut = "pub", set = "pub")]
     layout: Layout,
+    #[getset(get = "pub", set = "pub")]
+    layout_rotation: Vec<DefaultLayout>,
+    /// Move on to the next layout in the rotation every this many seconds
+    #[getset(get_copy = "pub", set = "pub")]
+    layout_cycle_interval: Option<u64>,
+    #[serde(skip_serializing)]
+    #[getset(get_copy = "pub", set = "pub")]
+    layout_cycled_at: Option<Instant>,
     #[getset(get_copy = "pub", set = "pub")]
     layout_flip: Option<Axis>,
+    #[getset(get = "pub", get_mut = "pub")]
+    toggled_splits: Vec<usize>,
     #[getset(get_copy = "pub", set = "pub")]
     workspace_padding: Option<i32>,
     #[getset(get_copy = "pub", set = "pub")]
     container_padding: Option<i32>,
+    /// The container padding for 1, 2, 3... containers, where the last value is also used for
+    /// any number of containers beyond that
+    #[getset(get = "pub", set = "pub")]
+    container_padding_scale: Vec<i32>,
+    /// Limits the layout to this many regions, each of which is a stack that windows are added
+    /// to instead of creating new containers once every region is occupied
+    #[getset(get_copy = "pub", set = "pub")]
+    multi_stack: Option<usize>,
+    #[getset(get_copy = "pub", set = "pub")]
+    inner_gap: Option<i32>,
+    /// Paddings and gaps are given in logical pixels and scaled to the DPI of the monitor that
+    /// the workspace is laid out on
+    #[getset(get_copy = "pub", set = "pub")]
+    logical_padding: bool,
+    #[getset(get_copy = "pub", set = "pub")]
+    outer_gap: Option<i32>,
     #[serde(skip_serializing)]
     #[getset(get = "pub", set = "pub")]
     latest_layout: Vec<Rect>,
+    /// Window positions left over from a time-sliced update, which are applied a batch at a
+    /// time by the event loop
+    #[serde(skip_serializing)]
+    pending_positions: VecDeque<(Window, Rect, bool)>,
     #[getset(get = "pub", get_mut = "pub", set = "pub")]
     resize_dimensions: Vec<Option<Rect>>,
     #[getset(get = "pub", set = "pub")]
     tile: bool,
+    #[getset(get_copy = "pub", set = "pub")]
+    focus_follows_mouse: Option<bool>,
+    #[getset(get_copy = "pub", set = "pub")]
+    taskbar_hidden: bool,
+    /// Overrides the global resize delta for containers on this workspace
+    #[getset(get_copy = "pub", set = "pub")]
+    resize_delta: Option<i32>,
+    #[getset(get = "pub", set = "pub")]
+    wallpaper: Option<PathBuf>,
 }
 
 impl_ring_elements!(Workspace, Container);
 
+/// The DPI of a monitor at 100% scaling, where one logical pixel is one physical pixel
+const BASE_DPI: i32 = 96;
+
+/// Workspaces with at least this many containers have their windows positioned over several
+/// iterations of the event loop instead of in a single pass
+const TIME_SLICED_CONTAINERS: usize = 20;
+
+/// Where a window was tiled before it was floated, so that it can be put back in the same place
+#[derive(Debug, Clone)]
+enum TilePosition {
+    /// The window had a container to itself at this index
+    Container(usize),
+    /// The window was stacked at this index in the container with this id
+    Stack(String, usize),
+}
+
 impl Default for Workspace {
     fn default() -> Self {
         Self {
+            id: nanoid!(),
             name: None,
             containers: Ring::default(),
             monocle_container: None,
             maximized_window: None,
             maximized_window_restore_idx: None,
+            maximized_container: None,
+            focus_mode: None,
+            monocle_pip_hwnds: vec![],
+            raised_hwnds: vec![],
+            lowered_hwnds: vec![],
+            on_top_hwnds: vec![],
             monocle_container_restore_idx: None,
             floating_windows: Vec::default(),
+            float_restore_positions: HashMap::new(),
+            natively_maximized_hwnds: HashSet::new(),
+            swallowed_windows: HashMap::new(),
+            snapshot_placements: vec![],
             layout: Layout::Default(DefaultLayout::BSP),
+            layout_rotation: vec![
+                DefaultLayout::BSP,
+                DefaultLayout::Columns,
+                DefaultLayout::Rows,
+                DefaultLayout::VerticalStack,
+                DefaultLayout::HorizontalStack,
+                DefaultLayout::UltrawideVerticalStack,
+            ],
+            layout_cycle_interval: None,
+            layout_cycled_at: None,
             layout_flip: None,
+            toggled_splits: vec![],
             workspace_padding: Option::from(10),
             container_padding: Option::from(10),
+            container_padding_scale: vec![],
+            multi_stack: None,
+            inner_gap: None,
+            logical_padding: false,
+            outer_gap: None,
             latest_layout: vec![],
+            pending_positions: VecDeque::new(),
             resize_dimensions: vec![],
             tile: true,
+            focus_follows_mouse: None,
+            taskbar_hidden: false,
+            resize_delta: None,
+            wallpaper: None,
         }
     }
 }
 
 impl Workspace {
+    /// Copy the layout, padding, gap and tiling settings of another workspace without touching
+    /// any of the containers or windows on this workspace
+    pub fn copy_layout_settings(&mut self, other: &Self) {
+        self.layout = other.layout.clone();
+        self.layout_rotation.clone_from(&other.layout_rotation);
+        self.layout_flip = other.layout_flip;
+        self.toggled_splits.clone_from(&other.toggled_splits);
+        self.workspace_padding = other.workspace_padding;
+        self.container_padding = other.container_padding;
+        self.multi_stack = other.multi_stack;
+        self.inner_gap = other.inner_gap;
+        self.logical_padding = other.logical_padding;
+        self.outer_gap = other.outer_gap;
+        self.tile = other.tile;
+    }
+
+    /// The layout which follows the current layout in the rotation, starting again from the
+    /// beginning of the rotation if the current layout is a custom layout or is not part of it
+    pub fn next_rotation_layout(&self, direction: CycleDirection) -> Option<DefaultLayout> {
+        let len = NonZeroUsize::new(self.layout_rotation.len())?;
+
+        let current_idx = match &self.layout {
+            Layout::Default(current) => self
+                .layout_rotation
+                .iter()
+                .position(|layout| layout == current),
+            Layout::Custom(_) => None,
+        };
+
+        let next_idx = current_idx.map_or(0, |idx| direction.next_idx(idx, len));
+        self.layout_rotation.get(next_idx).copied()
+    }
+
     pub fn hide(&mut self) {
+        self.pending_positions.clear();
+
+        // Floating windows which were maximized by the user rather than by komorebi lose their
+        // maximized state when they are hidden, so they need to be remembered and maximized again
+        // on restore. Tiled windows are left out, since the layout would only un-maximize them.
+        self.natively_maximized_hwnds = self
+            .floating_windows()
+            .iter()
+            .filter(|window| window.is_maximized())
+            .map(|window| window.hwnd)
+            .collect();
+
         for container in self.containers_mut() {
             for window in container.windows_mut() {
                 window.hide();
@@ -99,12 +279,29 @@ impl Workspace {
         for window in self.floating_windows() {
             window.hide();
         }
+
+        self.lower_monocle_pip_windows();
     }
 
     pub fn restore(&mut self, mouse_follows_focus: bool) -> Result<()> {
+        // Every window being restored fires focus events of its own, which are only settled once
+        // the window that should actually be focused has been focused
+        let guard = OperationGuard::start();
         let idx = self.focused_container_idx();
         let mut to_focus = None;
+        // Containers hidden by focus mode stay hidden until it is disabled
+        let focus_mode = self.focus_mode.is_some();
         for (i, container) in self.containers_mut().iter_mut().enumerate() {
+            if focus_mode {
+                continue;
+            }
+
+            if container.nested_axis().is_some() {
+                for window in container.windows_mut() {
+                    window.restore();
+                }
+            }
+
             if let Some(window) = container.focused_window_mut() {
                 window.restore();
 
@@ -122,12 +319,24 @@ impl Workspace {
             for window in container.windows_mut() {
                 window.restore();
             }
+
+            if focus_mode {
+                to_focus = container.focused_window().copied();
+            }
         }
 
         for window in self.floating_windows() {
             window.restore();
         }
 
+        for window in self.floating_windows() {
+            if self.natively_maximized_hwnds.contains(&window.hwnd) && !window.is_maximized() {
+                window.maximize();
+            }
+        }
+
+        drop(guard);
+
         // Do this here to make sure that an error doesn't stop the restoration of other windows
         // Maximised windows should always be drawn at the top of the Z order
         if let Some(window) = to_focus {
@@ -136,6 +345,8 @@ impl Workspace {
             }
         }
 
+        self.apply_z_order();
+
         Ok(())
     }
 
@@ -144,8 +355,19 @@ impl Workspace {
         work_area: &Rect,
         offset: Option<Rect>,
         invisible_borders: &Rect,
+        group: Option<MonitorGroup>,
     ) -> Result<()> {
-        let container_padding = self.container_padding();
+        let dpi = if self.logical_padding {
+            i32::try_from(WindowsApi::dpi_for_rect(work_area)?)?
+        } else {
+            BASE_DPI
+        };
+
+        let scale = |padding: i32| padding * dpi / BASE_DPI;
+
+        let uses_gaps = self.uses_gaps();
+        let inner_gap = scale(self.inner_gap().unwrap_or_default());
+        let container_padding = self.layout_container_padding().map(scale);
         let mut adjusted_work_area = offset.map_or_else(
             || *work_area,
             |offset| {
@@ -159,42 +381,104 @@ impl Workspace {
             },
         );
 
-        adjusted_work_area.add_padding(self.workspace_padding());
+        if uses_gaps {
+            adjusted_work_area
+                .add_padding(Option::from(scale(self.outer_gap().unwrap_or_default())));
+        } else {
+            adjusted_work_area.add_padding(self.workspace_padding().map(scale));
+        }
+
+        // Positions from a previous time-sliced update would undo the layout calculated now
+        self.pending_positions.clear();
 
+        self.enforce_multi_stack()?;
         self.enforce_resize_constraints();
 
+        let focus_mode = self.focus_mode;
+
         if *self.tile() {
             if let Some(container) = self.monocle_container_mut() {
                 if let Some(window) = container.focused_window_mut() {
                     adjusted_work_area.add_padding(container_padding);
+                    if let Some(percentage) = focus_mode {
+                        let width = adjusted_work_area.right * i32::from(percentage) / 100;
+                        adjusted_work_area.left += (adjusted_work_area.right - width) / 2;
+                        adjusted_work_area.right = width;
+                    }
+
+                    if let Some(group) = group {
+                        adjusted_work_area = group.compensate(&adjusted_work_area);
+                    }
+
                     window.set_position(&adjusted_work_area, invisible_borders, true)?;
                 };
             } else if let Some(window) = self.maximized_window_mut() {
                 window.maximize();
             } else if !self.containers().is_empty() {
-                let layouts = self.layout().as_boxed_arrangement().calculate(
+                let mut maximized_area = adjusted_work_area;
+                maximized_area.add_padding(container_padding);
+
+                if uses_gaps {
+                    adjusted_work_area.remove_inner_gap(inner_gap);
+                }
+
+                // Paddings and gaps set on the columns of a custom layout are scaled like those of
+                // the workspace
+                let layout = match self.layout() {
+                    Layout::Custom(custom) if dpi != BASE_DPI => {
+                        Layout::Custom(custom.with_scaled_spacing(scale))
+                    }
+                    layout => layout.clone(),
+                };
+
+                let mut layouts = layout.as_boxed_arrangement().calculate(
                     &adjusted_work_area,
                     NonZeroUsize::new(self.containers().len()).ok_or_else(|| {
                         anyhow!(
                             "there must be at least one container to calculate a workspace layout"
                         )
                     })?,
-                    self.container_padding(),
+                    container_padding,
                     self.layout_flip(),
                     self.resize_dimensions(),
+                    self.toggled_splits(),
                 );
 
-                let windows = self.visible_windows_mut();
-                for (i, window) in windows.into_iter().enumerate() {
-                    if let (Some(window), Some(layout)) = (window, layouts.get(i)) {
-                        window.set_position(layout, invisible_borders, false)?;
+                if uses_gaps {
+                    for layout in &mut layouts {
+                        layout.add_inner_gap(inner_gap);
                     }
                 }
 
+                for (i, window) in self.visible_windows().into_iter().enumerate() {
+                    if let Some(constraints) = window.and_then(|window| window.size_constraints()) {
+                        constraints.apply(&mut layouts, i);
+                    }
+                }
+
+                if let Some(group) = group {
+                    for layout in &mut layouts {
+                        *layout = group.compensate(layout);
+                    }
+
+                    maximized_area = group.compensate(&maximized_area);
+                }
+
+                let positions =
+                    self.container_positions(&layouts, maximized_area, uses_gaps, inner_gap);
+
+                self.set_positions(positions, invisible_borders)?;
+
                 self.set_latest_layout(layouts);
             }
         }
 
+        if self.monocle_container().is_some() {
+            self.raise_monocle_pip_windows()?;
+        }
+
+        self.apply_z_order();
+
         // Always make sure that the length of the resize dimensions vec is the same as the
         // number of layouts / containers. This should never actually truncate as the remove_window
         // function takes care of cleaning up resize dimensions when destroying empty containers
@@ -204,6 +488,259 @@ impl Workspace {
         Ok(())
     }
 
+    // The position of each visible window in the layout, along with whether it should be kept on
+    // top of the other windows
+    fn container_positions(
+        &mut self,
+        layouts: &[Rect],
+        maximized_area: Rect,
+        uses_gaps: bool,
+        inner_gap: i32,
+    ) -> Vec<(Window, Rect, bool)> {
+        let maximized_idx = self.maximized_container_idx();
+        if maximized_idx.is_none() {
+            self.maximized_container = None;
+        }
+
+        // The focused container is positioned first, so that it settles before the
+        // rest of a time-sliced update
+        let focused_idx = self.focused_container_idx();
+        let mut positions = vec![];
+        for (i, container) in self.containers().iter().enumerate() {
+            let layout = match layouts.get(i) {
+                None => continue,
+                Some(layout) => layout,
+            };
+
+            let focused = i == focused_idx;
+
+            // A maximized container keeps its tile in the layout, but is drawn over
+            // the other containers using the whole work area
+            if maximized_idx == Some(i) {
+                if let Some(window) = container.focused_window() {
+                    positions.push((focused, (*window, maximized_area, true)));
+                }
+            } else if container.nested_axis().is_some() {
+                let mut nested_layouts = container.nested_layouts(layout);
+                if uses_gaps {
+                    for nested_layout in &mut nested_layouts {
+                        nested_layout.add_inner_gap(inner_gap);
+                    }
+                }
+
+                for (window, nested_layout) in container.windows().iter().zip(nested_layouts) {
+                    positions.push((focused, (*window, nested_layout, false)));
+                }
+            } else if let Some(window) = container.focused_window() {
+                positions.push((focused, (*window, *layout, false)));
+            }
+        }
+
+        positions.sort_by_key(|(focused, _)| !*focused);
+
+        positions
+            .into_iter()
+            .map(|(_, position)| position)
+            .collect()
+    }
+
+    // Very large workspaces have their windows positioned in batches, with the remainder left to
+    // the event loop
+    fn set_positions(
+        &mut self,
+        positions: Vec<(Window, Rect, bool)>,
+        invisible_borders: &Rect,
+    ) -> Result<()> {
+        let batch_size = RETILE_BATCH_SIZE.load(Ordering::SeqCst);
+        if batch_size > 0 && self.containers().len() >= TIME_SLICED_CONTAINERS {
+            self.pending_positions = positions.into_iter().collect();
+
+            self.apply_pending_positions(invisible_borders);
+            if self.has_pending_positions() {
+                tracing::trace!("time slicing the update of a large workspace");
+                process_event::defer_retile();
+            }
+        } else {
+            for (mut window, layout, top) in positions {
+                window.set_position(&layout, invisible_borders, top)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Position the next batch of windows left over from a time-sliced update
+    pub fn apply_pending_positions(&mut self, invisible_borders: &Rect) {
+        let batch_size = RETILE_BATCH_SIZE
+            .load(Ordering::SeqCst)
+            .max(1)
+            .min(self.pending_positions.len());
+
+        for (mut window, layout, top) in self.pending_positions.drain(..batch_size) {
+            if let Err(error) = window.set_position(&layout, invisible_borders, top) {
+                tracing::warn!("could not position window {}: {}", window.hwnd, error);
+            }
+        }
+
+        // Positioning a window moves it to the top of its band, so the Z order adjustments
+        // have to be made again once the last batch has been positioned
+        if self.pending_positions.is_empty() {
+            self.apply_z_order();
+        }
+    }
+
+    pub fn has_pending_positions(&self) -> bool {
+        !self.pending_positions.is_empty()
+    }
+
+    /// The monocle window is positioned as topmost, so any picture-in-picture windows have to be
+    /// raised again afterwards to stay visible
+    fn raise_monocle_pip_windows(&mut self) -> Result<()> {
+        let floating_hwnds: Vec<isize> = self.floating_windows().iter().map(|w| w.hwnd).collect();
+        let (kept, removed) = std::mem::take(&mut self.monocle_pip_hwnds)
+            .into_iter()
+            .partition(|hwnd| floating_hwnds.contains(hwnd));
+
+        self.monocle_pip_hwnds = kept;
+        self.lower_monocle_pip_hwnds(&removed);
+
+        for window in self.floating_windows() {
+            if self.monocle_pip_hwnds.contains(&window.hwnd) {
+                WindowsApi::raise_topmost(window.hwnd())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Take picture-in-picture windows back out of the topmost band once the monocle container
+    /// that they were kept above is gone or hidden, unless they are kept on top by hand
+    pub fn lower_monocle_pip_windows(&self) {
+        self.lower_monocle_pip_hwnds(&self.monocle_pip_hwnds);
+    }
+
+    fn lower_monocle_pip_hwnds(&self, hwnds: &[isize]) {
+        for hwnd in hwnds {
+            if self.on_top_hwnds.contains(hwnd) {
+                continue;
+            }
+
+            if let Err(error) = WindowsApi::set_z_order(HWND(*hwnd), ZOrder::NotTopmost) {
+                tracing::warn!(
+                    "could not lower picture-in-picture window {}: {}",
+                    hwnd,
+                    error
+                );
+            }
+        }
+    }
+
+    pub fn raise_hwnd(&mut self, hwnd: isize) {
+        self.lowered_hwnds.retain(|h| *h != hwnd);
+        self.raised_hwnds.retain(|h| *h != hwnd);
+        self.raised_hwnds.push(hwnd);
+    }
+
+    pub fn lower_hwnd(&mut self, hwnd: isize) {
+        self.raised_hwnds.retain(|h| *h != hwnd);
+        self.lowered_hwnds.retain(|h| *h != hwnd);
+        self.lowered_hwnds.push(hwnd);
+    }
+
+    /// Returns whether the window is now kept on top
+    pub fn toggle_hwnd_on_top(&mut self, hwnd: isize) -> Result<bool> {
+        if let Some(idx) = self.on_top_hwnds.iter().position(|h| *h == hwnd) {
+            self.on_top_hwnds.remove(idx);
+            WindowsApi::set_z_order(HWND(hwnd), ZOrder::NotTopmost)?;
+            Ok(false)
+        } else {
+            self.on_top_hwnds.push(hwnd);
+            Ok(true)
+        }
+    }
+
+    /// Restack the windows that have been raised, lowered or kept on top by hand, forgetting
+    /// any which are no longer on this workspace
+    pub fn apply_z_order(&mut self) {
+        let mut z_order = std::mem::take(&mut self.raised_hwnds);
+        z_order.retain(|hwnd| self.contains_window(*hwnd));
+        self.raised_hwnds = z_order;
+
+        let mut z_order = std::mem::take(&mut self.lowered_hwnds);
+        z_order.retain(|hwnd| self.contains_window(*hwnd));
+        self.lowered_hwnds = z_order;
+
+        let (on_top, pruned): (Vec<isize>, Vec<isize>) = std::mem::take(&mut self.on_top_hwnds)
+            .into_iter()
+            .partition(|hwnd| self.contains_window(*hwnd));
+        self.on_top_hwnds = on_top;
+
+        // Windows which have left the workspace would otherwise be kept above everything else
+        for hwnd in pruned {
+            if let Err(error) = WindowsApi::set_z_order(HWND(hwnd), ZOrder::NotTopmost) {
+                tracing::debug!("could not take window {} off the top: {}", hwnd, error);
+            }
+        }
+
+        // A window which has gone away must not stop the rest from being restacked
+        let z_order = self
+            .lowered_hwnds
+            .iter()
+            .map(|hwnd| (hwnd, ZOrder::Bottom))
+            .chain(self.raised_hwnds.iter().map(|hwnd| (hwnd, ZOrder::Top)))
+            .chain(self.on_top_hwnds.iter().map(|hwnd| (hwnd, ZOrder::Topmost)));
+
+        for (hwnd, position) in z_order {
+            if let Err(error) = WindowsApi::set_z_order(HWND(*hwnd), position) {
+                tracing::warn!("could not restack window {}: {}", hwnd, error);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.containers().is_empty()
+            && self.monocle_container().is_none()
+            && self.maximized_window().is_none()
+            && self.floating_windows().is_empty()
+    }
+
+    pub const fn has_monocle_container(&self) -> bool {
+        self.monocle_container.is_some()
+    }
+
+    pub const fn has_maximized_window(&self) -> bool {
+        self.maximized_window.is_some() || self.maximized_container.is_some()
+    }
+
+    pub fn maximized_container_idx(&self) -> Option<usize> {
+        let id = self.maximized_container.as_ref()?;
+        self.containers()
+            .iter()
+            .position(|container| container.id() == id)
+    }
+
+    /// Once either gap has been set, gaps are used in place of the workspace and container
+    /// padding, with an unset gap counting as zero
+    pub const fn uses_gaps(&self) -> bool {
+        self.inner_gap.is_some() || self.outer_gap.is_some()
+    }
+
+    /// The container padding that should be passed to layout calculations, which is none when
+    /// gaps are in use because the inner gap is applied separately, and otherwise follows the
+    /// container padding scale for the current number of containers if one has been set
+    pub fn layout_container_padding(&self) -> Option<i32> {
+        if self.uses_gaps() {
+            return None;
+        }
+
+        let count = self.containers().len().max(1);
+        self.container_padding_scale
+            .get(count - 1)
+            .or_else(|| self.container_padding_scale.last())
+            .copied()
+            .or(self.container_padding)
+    }
+
     pub fn reap_orphans(&mut self) -> Result<(usize, usize)> {
         let mut hwnds = vec![];
         let mut floating_hwnds = vec![];
@@ -244,6 +781,58 @@ impl Workspace {
         Ok((hwnds.len() + floating_hwnds.len(), container_ids.len()))
     }
 
+    /// Unlike `reap_orphans`, this also checks windows in the background of stacks. Windows are
+    /// only restored when `visible` is set, in which case the window taking the place of a dead
+    /// window at the top of a stack is shown, so it is safe to call on workspaces which are not
+    /// visible
+    pub fn prune_dead_windows(&mut self, visible: bool) -> Vec<isize> {
+        let mut pruned = vec![];
+
+        for container in self.containers_mut() {
+            pruned.extend(prune_dead_windows_from_container(container, visible));
+        }
+
+        if let Some(container) = self.monocle_container_mut() {
+            pruned.extend(prune_dead_windows_from_container(container, visible));
+
+            if container.windows().is_empty() {
+                self.set_monocle_container(None);
+                self.set_monocle_container_restore_idx(None);
+            }
+        }
+
+        if let Some(window) = self.maximized_window() {
+            if !window.is_window() {
+                pruned.push(window.hwnd);
+                self.set_maximized_window(None);
+                self.set_maximized_window_restore_idx(None);
+            }
+        }
+
+        for window in self.floating_windows() {
+            if !window.is_window() {
+                pruned.push(window.hwnd);
+            }
+        }
+
+        self.floating_windows_mut()
+            .retain(|window| window.is_window());
+
+        let floating_windows = &self.floating_windows;
+        self.float_restore_positions
+            .retain(|hwnd, _| floating_windows.iter().any(|window| window.hwnd == *hwnd));
+
+        while let Some(idx) = self
+            .containers()
+            .iter()
+            .position(|container| container.windows().is_empty())
+        {
+            self.remove_container(idx);
+        }
+
+        pruned
+    }
+
     pub fn container_for_window(&self, hwnd: isize) -> Option<&Container> {
         self.containers().get(self.container_idx_for_window(hwnd)?)
     }
@@ -284,6 +873,42 @@ impl Workspace {
         idx
     }
 
+    /// Where a container dropped at the cursor should be inserted, which is next to whichever
+    /// edge of the nearest container the cursor is closest to
+    pub fn drop_idx_from_current_point(&self) -> usize {
+        let point = match WindowsApi::cursor_pos() {
+            Ok(point) => (point.x, point.y),
+            Err(_) => return 0,
+        };
+
+        let nearest = self
+            .latest_layout()
+            .iter()
+            .take(self.containers().len())
+            .enumerate()
+            .min_by_key(|(_, rect)| {
+                let dx = (rect.left - point.0)
+                    .max(point.0 - (rect.left + rect.right))
+                    .max(0);
+                let dy = (rect.top - point.1)
+                    .max(point.1 - (rect.top + rect.bottom))
+                    .max(0);
+
+                i64::from(dx).pow(2) + i64::from(dy).pow(2)
+            });
+
+        nearest.map_or(0, |(idx, rect)| {
+            let before = (point.0 - rect.left).min(point.1 - rect.top);
+            let after = (rect.left + rect.right - point.0).min(rect.top + rect.bottom - point.1);
+
+            if after < before {
+                idx + 1
+            } else {
+                idx
+            }
+        })
+    }
+
     pub fn hwnd_from_exe(&self, exe: &str) -> Option<isize> {
         for container in self.containers() {
             if let Some(hwnd) = container.hwnd_from_exe(exe) {
@@ -350,14 +975,9 @@ impl Workspace {
             .remove_focused_container()
             .ok_or_else(|| anyhow!("there is no container"))?;
 
-        let primary_idx = match self.layout() {
-            Layout::Default(_) => 0,
-            Layout::Custom(layout) => layout.first_container_idx(
-                layout
-                    .primary_idx()
-                    .ok_or_else(|| anyhow!("this custom layout does not have a primary column"))?,
-            ),
-        };
+        // There may be fewer containers than are needed to reach the primary column once the
+        // promoted container has been removed
+        let primary_idx = self.primary_container_idx()?.min(self.containers().len());
 
         self.containers_mut().insert(primary_idx, container);
         self.resize_dimensions_mut().insert(primary_idx, resize);
@@ -367,11 +987,130 @@ impl Workspace {
         Ok(())
     }
 
+    /// The index of the container in the primary zone of the layout, which is always the first
+    /// container for default layouts and the first container of the primary column for custom
+    /// layouts, regardless of where that column is positioned
+    pub fn primary_container_idx(&self) -> Result<usize> {
+        Ok(match self.layout() {
+            Layout::Default(_) => 0,
+            Layout::Custom(layout) => layout.first_container_idx(
+                layout
+                    .primary_idx()
+                    .ok_or_else(|| anyhow!("this custom layout does not have a primary column"))?,
+            ),
+        })
+    }
+
     pub fn add_container(&mut self, container: Container) {
         self.containers_mut().push_back(container);
         self.focus_last_container();
     }
 
+    pub fn insert_container(&mut self, idx: usize, container: Container) {
+        let idx = idx.min(self.containers().len());
+        self.containers_mut().insert(idx, container);
+
+        let resize_idx = idx.min(self.resize_dimensions().len());
+        self.resize_dimensions_mut().insert(resize_idx, None);
+
+        self.focus_container(idx);
+    }
+
+    /// Move the container of a window which was launched by loading a workspace snapshot to the
+    /// position it was saved in, returning whether it was moved
+    pub fn apply_snapshot_placement(&mut self, window: Window) -> Result<bool> {
+        if self.snapshot_placements.is_empty() {
+            return Ok(false);
+        }
+
+        let (exe, class, title) = match window.identity() {
+            None => return Ok(false),
+            Some(identity) => identity,
+        };
+
+        // Prefer the placeholder which was saved with the same title
+        let placement_idx = match self
+            .snapshot_placements
+            .iter()
+            .position(|(placeholder, _)| {
+                placeholder.matches(&exe, &class) && placeholder.matches_title(&title)
+            })
+            .or_else(|| {
+                self.snapshot_placements
+                    .iter()
+                    .position(|(placeholder, _)| placeholder.matches(&exe, &class))
+            }) {
+            None => return Ok(false),
+            Some(idx) => idx,
+        };
+
+        let container_idx = match self.container_idx_for_window(window.hwnd) {
+            None => return Ok(false),
+            Some(idx) => idx,
+        };
+
+        // Windows which have been stacked with others are left where they are
+        if self
+            .containers()
+            .get(container_idx)
+            .map_or(true, |container| container.windows().len() > 1)
+        {
+            return Ok(false);
+        }
+
+        let (_, target_idx) = self.snapshot_placements.remove(placement_idx);
+        let container = self
+            .remove_container_by_idx(container_idx)
+            .ok_or_else(|| anyhow!("there is no container"))?;
+
+        self.insert_container(target_idx, container);
+
+        Ok(true)
+    }
+
+    /// Give each window in the focused container a container of its own, placed directly after it
+    pub fn dissolve_focused_container(&mut self) -> Result<()> {
+        let focused_idx = self.focused_container_idx();
+        let container = self
+            .focused_container_mut()
+            .ok_or_else(|| anyhow!("there is no container"))?;
+
+        if container.windows().len() < 2 {
+            return Err(anyhow!("there is only one window in this container"));
+        }
+
+        let focused_window = *container
+            .focused_window()
+            .ok_or_else(|| anyhow!("there is no window"))?;
+
+        container.set_nested_axis(None);
+
+        let mut windows = vec![];
+        while container.windows().len() > 1 {
+            if let Some(window) = container.remove_window_by_idx(container.windows().len() - 1) {
+                windows.push(window);
+            }
+        }
+
+        // The window left behind may have been hidden in a stack
+        container.load_focused_window();
+
+        for (offset, window) in windows.into_iter().rev().enumerate() {
+            window.restore();
+
+            let mut container = Container::default();
+            container.add_window(window);
+            self.insert_container(focused_idx + 1 + offset, container);
+        }
+
+        // Keep focus on whichever window was focused in the container before it was dissolved
+        if let Some(idx) = self.container_idx_for_window(focused_window.hwnd) {
+            self.focus_container(idx);
+        }
+
+        Ok(())
+    }
+
     fn remove_container_by_idx(&mut self, idx: usize) -> Option<Container> {
         if idx < self.resize_dimensions().len() {
             self.resize_dimensions_mut().remove(idx);
@@ -384,7 +1123,7 @@ impl Workspace {
         None
     }
 
-    fn container_idx_for_window(&self, hwnd: isize) -> Option<usize> {
+    pub fn container_idx_for_window(&self, hwnd: isize) -> Option<usize> {
         let mut idx = None;
         for (i, x) in self.containers().iter().enumerate() {
             if x.contains_window(hwnd) {
@@ -483,6 +1222,14 @@ impl Workspace {
     pub fn new_idx_for_direction(&self, direction: OperationDirection) -> Option<usize> {
         let len = NonZeroUsize::new(self.containers().len())?;
 
+        // The rects from the last layout pass include any resizing and toggled splits, so when
+        // they are up to date they are used instead of the layout's own indexing rules
+        let rects = self.latest_layout();
+        if rects.len() == len.get() {
+            let from = rects.get(self.focused_container_idx())?;
+            return direction.nearest(from, rects);
+        }
+
         direction.destination(
             self.layout().as_boxed_direction().as_ref(),
             self.layout_flip(),
@@ -490,6 +1237,7 @@ impl Workspace {
             len,
         )
     }
+
     pub fn new_idx_for_cycle_direction(&self, direction: CycleDirection) -> Option<usize> {
         Option::from(direction.next_idx(
             self.focused_container_idx(),
@@ -564,15 +1312,103 @@ impl Workspace {
     }
 
     pub fn new_container_for_floating_window(&mut self) -> Result<()> {
-        let focused_idx = self.focused_container_idx();
         let window = self
             .remove_focused_floating_window()
             .ok_or_else(|| anyhow!("there is no floating window"))?;
 
+        let container_idx = match self.float_restore_positions.remove(&window.hwnd) {
+            Some(TilePosition::Stack(id, window_idx)) => {
+                // The rest of the stack may have been closed while the window was floating
+                let stack_idx = self.containers().iter().position(|c| *c.id() == id);
+                if let Some(container_idx) = stack_idx {
+                    let container = self
+                        .containers_mut()
+                        .get_mut(container_idx)
+                        .ok_or_else(|| anyhow!("there is no container"))?;
+
+                    let window_idx = window_idx.min(container.windows().len());
+                    container.windows_mut().insert(window_idx, window);
+                    container.focus_window(window_idx);
+                    container.load_focused_window();
+
+                    self.focus_container(container_idx);
+                    return Ok(());
+                }
+
+                self.focused_container_idx()
+            }
+            Some(TilePosition::Container(idx)) => idx.min(self.containers().len()),
+            None => self.focused_container_idx(),
+        };
+
         let mut container = Container::default();
         container.add_window(window);
-        self.containers_mut().insert(focused_idx, container);
-        self.resize_dimensions_mut().insert(focused_idx, None);
+        self.containers_mut().insert(container_idx, container);
+
+        let resize_idx = container_idx.min(self.resize_dimensions().len());
+        self.resize_dimensions_mut().insert(resize_idx, None);
+        self.focus_container(container_idx);
+
+        Ok(())
+    }
+
+    /// Tile the focused floating window next to the nearest container in `direction` from it, or at
+    /// the edge of the layout in that direction if there is no container there
+    pub fn tile_floating_window(&mut self, direction: OperationDirection) -> Result<()> {
+        let hwnd = WindowsApi::foreground_window()?;
+        let window = *self
+            .floating_windows()
+            .iter()
+            .find(|window| window.hwnd == hwnd)
+            .ok_or_else(|| anyhow!("there is no floating window"))?;
+
+        // The window is only taken out of the floating windows once nothing else can fail
+        let rect = WindowsApi::window_rect(window.hwnd())?;
+        let centre = (rect.left + rect.right / 2, rect.top + rect.bottom / 2);
+
+        let nearest = self
+            .latest_layout()
+            .iter()
+            .take(self.containers().len())
+            .enumerate()
+            .filter_map(|(idx, layout)| {
+                let x = layout.left + layout.right / 2;
+                let y = layout.top + layout.bottom / 2;
+
+                let distance = match direction {
+                    OperationDirection::Left => centre.0 - x,
+                    OperationDirection::Right => x - centre.0,
+                    OperationDirection::Up => centre.1 - y,
+                    OperationDirection::Down => y - centre.1,
+                };
+
+                // Containers are ranked by how far away they are in the direction of travel, and
+                // then by how far they are off that line
+                let deviation = match direction {
+                    OperationDirection::Left | OperationDirection::Right => (y - centre.1).abs(),
+                    OperationDirection::Up | OperationDirection::Down => (x - centre.0).abs(),
+                };
+
+                (distance > 0).then_some((idx, distance, deviation))
+            })
+            .min_by_key(|(_, distance, deviation)| (*distance, *deviation))
+            .map(|(idx, _, _)| idx);
+
+        // The window ends up on the opposite side of the container that it was moved towards
+        let container_idx = match (direction, nearest) {
+            (OperationDirection::Left | OperationDirection::Up, Some(idx)) => idx + 1,
+            (OperationDirection::Right | OperationDirection::Down, Some(idx)) => idx,
+            (OperationDirection::Left | OperationDirection::Up, None) => 0,
+            (OperationDirection::Right | OperationDirection::Down, None) => self.containers().len(),
+        };
+
+        self.floating_windows_mut()
+            .retain(|floating| floating.hwnd != hwnd);
+        self.float_restore_positions.remove(&hwnd);
+
+        let mut container = Container::default();
+        container.add_window(window);
+        self.insert_container(container_idx, container);
 
         Ok(())
     }
@@ -602,6 +1438,106 @@ impl Workspace {
         self.focus_container(next_idx);
     }
 
+    pub fn new_container_for_window_in_direction(
+        &mut self,
+        window: Window,
+        direction: OperationDirection,
+    ) {
+        if self.containers().is_empty() {
+            self.new_container_for_window(window);
+            return;
+        }
+
+        // The container in the requested direction from the focused container is found in the
+        // current layout, and the new container is inserted at its index so that the container
+        // which was there is placed right after the new one
+        let origin_idx = self.focused_container_idx();
+        match self.new_idx_for_direction(direction) {
+            Some(target_idx) if target_idx != origin_idx => {
+                let mut container = Container::default();
+                container.add_window(window);
+                self.insert_container(target_idx, container);
+            }
+            _ => self.new_container_for_window(window),
+        }
+    }
+
+    /// Find the visible window of an application with a swallow rule which launched `window`
+    pub fn swallower_for_window(&self, window: Window) -> Option<Window> {
+        let candidates: Vec<Window> = self
+            .containers()
+            .iter()
+            .filter_map(Container::focused_window)
+            .copied()
+            .filter(|candidate| candidate.hwnd != window.hwnd && candidate.can_swallow())
+            .collect();
+
+        // Walking the process tree is expensive, so only do it if something could swallow
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let ancestors = window.ancestor_process_ids();
+        candidates.into_iter().find(|candidate| {
+            Pid::try_from(candidate.process_id()).map_or(false, |pid| ancestors.contains(&pid))
+        })
+    }
+
+    /// Replace `swallower` with `window` in its container, keeping `swallower` hidden until
+    /// `window` is closed
+    pub fn swallow_window(&mut self, swallower: Window, window: Window) -> Result<()> {
+        let container_idx = self
+            .container_idx_for_window(swallower.hwnd)
+            .ok_or_else(|| anyhow!("there is no container for this window"))?;
+
+        let container = self
+            .containers_mut()
+            .get_mut(container_idx)
+            .ok_or_else(|| anyhow!("there is no container"))?;
+
+        let window_idx = container
+            .idx_for_window(swallower.hwnd)
+            .ok_or_else(|| anyhow!("there is no window"))?;
+
+        *container
+            .windows_mut()
+            .get_mut(window_idx)
+            .ok_or_else(|| anyhow!("there is no window"))? = window;
+
+        swallower.hide();
+        self.swallowed_windows.insert(window.hwnd, swallower);
+        self.focus_container(container_idx);
+
+        Ok(())
+    }
+
+    /// Put `swallowed` back in the place of the window `hwnd` which swallowed it, returning
+    /// false if `hwnd` is not tiled on this workspace
+    pub fn replace_swallowing_window(&mut self, hwnd: isize, swallowed: Window) -> Result<bool> {
+        let container_idx = match self.container_idx_for_window(hwnd) {
+            None => return Ok(false),
+            Some(idx) => idx,
+        };
+
+        let container = self
+            .containers_mut()
+            .get_mut(container_idx)
+            .ok_or_else(|| anyhow!("there is no container"))?;
+
+        let window_idx = container
+            .idx_for_window(hwnd)
+            .ok_or_else(|| anyhow!("there is no window"))?;
+
+        *container
+            .windows_mut()
+            .get_mut(window_idx)
+            .ok_or_else(|| anyhow!("there is no window"))? = swallowed;
+
+        self.focus_container(container_idx);
+
+        Ok(true)
+    }
+
     pub fn new_floating_window(&mut self) -> Result<()> {
         let focused_idx = self.focused_container_idx();
 
@@ -609,22 +1545,146 @@ impl Workspace {
             .focused_container_mut()
             .ok_or_else(|| anyhow!("there is no container"))?;
 
+        let window_idx = container.focused_window_idx();
+        let container_id = container.id().clone();
         let window = container
             .remove_focused_window()
             .ok_or_else(|| anyhow!("there is no window"))?;
 
-        if container.windows().is_empty() {
+        let position = if container.windows().is_empty() {
             self.containers_mut().remove(focused_idx);
             self.resize_dimensions_mut().remove(focused_idx);
+            TilePosition::Container(focused_idx)
         } else {
             container.load_focused_window();
-        }
+            TilePosition::Stack(container_id, window_idx)
+        };
 
+        self.float_restore_positions.insert(window.hwnd, position);
         self.floating_windows_mut().push(window);
 
         Ok(())
     }
 
+    /// Whether a multi-stack layout has been set and every one of its regions is occupied
+    pub fn multi_stack_full(&self) -> bool {
+        self.multi_stack
+            .map_or(false, |regions| self.containers().len() >= regions)
+    }
+
+    /// Stack a new window in the last region of a multi-stack layout
+    pub fn add_window_to_multi_stack(&mut self, window: Window) -> Result<()> {
+        let last_idx = self
+            .containers()
+            .len()
+            .checked_sub(1)
+            .ok_or_else(|| anyhow!("there is no container"))?;
+
+        self.add_window_to_container(last_idx, window)
+    }
+
+    pub fn add_window_to_container(&mut self, idx: usize, window: Window) -> Result<()> {
+        self.containers_mut()
+            .get_mut(idx)
+            .ok_or_else(|| anyhow!("there is no container"))?
+            .add_window(window);
+
+        self.focus_container(idx);
+
+        Ok(())
+    }
+
+    /// The container holding the windows of the same app group as `window`, if it has one
+    pub fn app_group_container_idx(&self, window: Window) -> Option<usize> {
+        let group = window.app_group()?;
+        self.containers().iter().position(|container| {
+            container
+                .windows()
+                .iter()
+                .any(|w| w.app_group().as_ref() == Option::from(&group))
+        })
+    }
+
+    /// Stack the windows of every app group in the first container holding a window of that
+    /// group, for when a rule is added after its windows have already been tiled. The windows of
+    /// a workspace which is not `visible` are all hidden already, and are left that way.
+    pub fn consolidate_app_groups(&mut self, visible: bool) -> Result<()> {
+        let groups: Vec<Option<String>> = self
+            .containers()
+            .iter()
+            .map(|container| container.windows().iter().find_map(|w| w.app_group()))
+            .collect();
+
+        for (idx, group) in groups.iter().enumerate().rev() {
+            let target_idx = match group {
+                None => continue,
+                Some(group) => match groups[..idx]
+                    .iter()
+                    .position(|g| g.as_ref() == Option::from(group))
+                {
+                    None => continue,
+                    Some(target_idx) => target_idx,
+                },
+            };
+
+            let container = self
+                .remove_container_by_idx(idx)
+                .ok_or_else(|| anyhow!("there is no container"))?;
+
+            let target = self
+                .containers_mut()
+                .get_mut(target_idx)
+                .ok_or_else(|| anyhow!("there is no container"))?;
+
+            for window in container.windows() {
+                target.add_window(*window);
+            }
+
+            if visible {
+                target.load_focused_window();
+            }
+        }
+
+        let focused_idx = self.focused_container_idx();
+        self.focus_container(focused_idx.min(self.containers().len().saturating_sub(1)));
+
+        Ok(())
+    }
+
+    // Containers can end up beyond the last region of a multi-stack layout when they are moved
+    // here from another workspace or the number of regions is reduced, so they are stacked in
+    // the last region
+    fn enforce_multi_stack(&mut self) -> Result<()> {
+        let regions = match self.multi_stack {
+            None => return Ok(()),
+            Some(regions) => regions.max(1),
+        };
+
+        if self.containers().len() <= regions {
+            return Ok(());
+        }
+
+        let focused_idx = self.focused_container_idx();
+        let extra = self.containers_mut().split_off(regions);
+        self.resize_dimensions_mut().truncate(regions);
+
+        let last = self
+            .containers_mut()
+            .back_mut()
+            .ok_or_else(|| anyhow!("there is no container"))?;
+
+        for container in extra {
+            for window in container.windows() {
+                last.add_window(*window);
+            }
+        }
+
+        last.load_focused_window();
+        self.focus_container(focused_idx.min(regions - 1));
+
+        Ok(())
+    }
+
     fn enforce_resize_constraints(&mut self) {
         for (i, rect) in self.resize_dimensions_mut().iter_mut().enumerate() {
             if let Some(rect) = rect {
@@ -698,6 +1758,31 @@ impl Workspace {
 
         self.set_monocle_container(None);
         self.set_monocle_container_restore_idx(None);
+        self.lower_monocle_pip_windows();
+
+        Ok(())
+    }
+
+    pub fn enable_focus_mode(&mut self, percentage: u8) -> Result<()> {
+        self.new_monocle_container()?;
+        self.set_focus_mode(Option::from(percentage));
+
+        for container in self.containers() {
+            for window in container.windows() {
+                window.hide();
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn disable_focus_mode(&mut self) -> Result<()> {
+        self.reintegrate_monocle_container()?;
+        self.set_focus_mode(None);
+
+        for container in self.containers_mut() {
+            container.load_focused_window();
+        }
 
         Ok(())
     }
@@ -771,6 +1856,26 @@ impl Workspace {
         self.containers.focus(idx);
     }
 
+    /// Switch to a default layout, moving the container in the primary column of a custom layout
+    /// to the front so that it stays the primary container
+    pub fn change_layout_default(&mut self, layout: DefaultLayout) -> Result<()> {
+        if let Layout::Custom(custom) = self.layout() {
+            let primary_idx = custom.first_container_idx(
+                custom
+                    .primary_idx()
+                    .ok_or_else(|| anyhow!("this custom layout does not have a primary column"))?,
+            );
+
+            if !self.containers().is_empty() && primary_idx < self.containers().len() {
+                self.swap_containers(0, primary_idx);
+            }
+        }
+
+        self.set_layout(Layout::Default(layout));
+
+        Ok(())
+    }
+
     pub fn swap_containers(&mut self, i: usize, j: usize) {
         self.containers.swap(i, j);
         self.focus_container(j);
@@ -828,3 +1933,27 @@ impl Workspace {
         self.focus_container(self.containers().len() - 1);
     }
 }
+
+fn prune_dead_windows_from_container(container: &mut Container, visible: bool) -> Vec<isize> {
+    let focused_hwnd = container.focused_window().map(|window| window.hwnd);
+    let mut pruned = vec![];
+
+    while let Some(idx) = container
+        .windows()
+        .iter()
+        .position(|window| !window.is_window())
+    {
+        if let Some(window) = container.remove_window_by_idx(idx) {
+            pruned.push(window.hwnd);
+        }
+    }
+
+    // When the visible window of a stack has died, the window which has taken its place would
+    // otherwise stay hidden until the stack is cycled
+    let focused_pruned = focused_hwnd.map_or(false, |hwnd| pruned.contains(&hwnd));
+    if visible && focused_pruned && !container.windows().is_empty() {
+        container.load_focused_window();
+    }
+
+    pruned
+}
@@ -0,0 +1,63 @@
+use std::collections::VecDeque;
+
+/// The maximum number of (monitor, workspace) locations that are remembered
+const MAX_WORKSPACE_HISTORY: usize = 50;
+
+/// A navigable history of focused (monitor, workspace) locations which works like the history of
+/// a web browser: going back and then focusing a new location discards the forward history
+#[derive(Debug, Default)]
+pub struct WorkspaceHistory {
+    locations: VecDeque<(usize, usize)>,
+    position: usize,
+}
+
+impl WorkspaceHistory {
+    pub fn current(&self) -> Option<(usize, usize)> {
+        self.locations.get(self.position).copied()
+    }
+
+    pub fn record(&mut self, location: (usize, usize)) {
+        if self.current() == Some(location) {
+            return;
+        }
+
+        if !self.locations.is_empty() {
+            self.locations.truncate(self.position + 1);
+        }
+
+        // Each location is only kept once, at the point where it was most recently visited
+        self.locations.retain(|existing| *existing != location);
+        self.locations.push_back(location);
+
+        if self.locations.len() > MAX_WORKSPACE_HISTORY {
+            self.locations.pop_front();
+        }
+
+        self.position = self.locations.len() - 1;
+    }
+
+    /// Update every remembered location after workspaces have been reordered
+    pub fn remap(&mut self, remap: impl Fn((usize, usize)) -> (usize, usize)) {
+        for location in &mut self.locations {
+            *location = remap(*location);
+        }
+    }
+
+    pub fn back(&mut self) -> Option<(usize, usize)> {
+        if self.position == 0 {
+            return None;
+        }
+
+        self.position -= 1;
+        self.current()
+    }
+
+    pub fn forward(&mut self) -> Option<(usize, usize)> {
+        if self.position + 1 >= self.locations.len() {
+            return None;
+        }
+
+        self.position += 1;
+        self.current()
+    }
+}
@@ -9,6 +9,9 @@ use std::io::ErrorKind;
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::Command;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 
 use clap::AppSettings;
 use clap::ArgEnum;
@@ -29,12 +32,16 @@ use derive_ahk::AhkFunction;
 use derive_ahk::AhkLibrary;
 use komorebi_core::ApplicationIdentifier;
 use komorebi_core::Axis;
+use komorebi_core::CursorPlacement;
 use komorebi_core::CycleDirection;
 use komorebi_core::DefaultLayout;
+use komorebi_core::EmptyWorkspaceBehaviour;
 use komorebi_core::FocusFollowsMouseImplementation;
 use komorebi_core::HidingBehaviour;
+use komorebi_core::ImportedConfig;
 use komorebi_core::OperationDirection;
 use komorebi_core::Rect;
+use komorebi_core::SizeConstraint;
 use komorebi_core::Sizing;
 use komorebi_core::SocketMessage;
 use komorebi_core::StateQuery;
@@ -85,13 +92,24 @@ gen_enum_subcommand_args! {
     CycleMonitor: CycleDirection,
     CycleWorkspace: CycleDirection,
     Stack: OperationDirection,
+    TileFloat: OperationDirection,
     CycleStack: CycleDirection,
+    NestContainer: Axis,
+    NextWindowDirection: OperationDirection,
     FlipLayout: Axis,
     ChangeLayout: DefaultLayout,
+    CycleLayout: CycleDirection,
+    CycleSplitRatio: CycleDirection,
     WatchConfiguration: BooleanState,
     MouseFollowsFocus: BooleanState,
+    StackIndicator: BooleanState,
+    FloatElevatedWindows: BooleanState,
+    PerformanceCounters: BooleanState,
+    WorkspaceRulesOnTitleChange: BooleanState,
+    ThrottleRetiles: BooleanState,
     Query: StateQuery,
     WindowHidingBehaviour: HidingBehaviour,
+    OnEmptyWorkspace: EmptyWorkspaceBehaviour,
 }
 
 macro_rules! gen_target_subcommand_args {
@@ -114,7 +132,19 @@ gen_target_subcommand_args! {
     SendToWorkspace,
     FocusMonitor,
     FocusWorkspace,
+    PeekWorkspace,
     MoveWorkspaceToMonitor,
+    MoveWorkspaceToIndex,
+}
+
+#[derive(Parser, AhkFunction)]
+pub struct MoveToWorkspaceAndFollow {
+    /// Target index (zero-indexed)
+    target: usize,
+
+    /// Whether focus should follow the window to the target workspace
+    #[clap(arg_enum)]
+    follow: BooleanState,
 }
 
 // Thanks to @danielhenrymantilla for showing me how to use cfg_attr with an optional argument like
@@ -149,6 +179,10 @@ gen_workspace_subcommand_args! {
     Name: String,
     Layout: #[enum] DefaultLayout,
     Tiling: #[enum] BooleanState,
+    FocusFollowsMouse: #[enum] BooleanState,
+    TaskbarHidden: #[enum] BooleanState,
+    LogicalPadding: #[enum] BooleanState,
+    ResizeDelta: i32,
 }
 
 #[derive(Parser, AhkFunction)]
@@ -163,6 +197,96 @@ pub struct WorkspaceCustomLayout {
     path: String,
 }
 
+#[derive(Parser, AhkFunction)]
+struct CloneWorkspaceLayoutToMonitor {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+
+    /// Workspace index on the specified monitor (zero-indexed)
+    workspace: usize,
+
+    /// Target monitor index (zero-indexed)
+    target_monitor: usize,
+}
+
+#[derive(Parser, AhkFunction)]
+pub struct WorkspaceLayoutRotation {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+
+    /// Workspace index on the specified monitor (zero-indexed)
+    workspace: usize,
+
+    /// Layouts to cycle through on the specified workspace, in order
+    #[clap(arg_enum, required = true)]
+    layouts: Vec<DefaultLayout>,
+}
+
+#[derive(Parser, AhkFunction)]
+pub struct WorkspaceLayoutCycleInterval {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+
+    /// Workspace index on the specified monitor (zero-indexed)
+    workspace: usize,
+
+    /// Number of seconds between layout changes (0 to stop cycling)
+    interval: u64,
+
+    /// Layouts to cycle through, in order (defaults to the existing layout rotation)
+    #[clap(arg_enum)]
+    layouts: Vec<DefaultLayout>,
+}
+
+#[derive(Parser, AhkFunction)]
+struct WorkspaceMultiStack {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+
+    /// Workspace index on the specified monitor (zero-indexed)
+    workspace: usize,
+
+    /// Number of layout regions, each of which stacks the windows moved into it (0 to disable)
+    regions: usize,
+}
+
+#[derive(Parser, AhkFunction)]
+pub struct WorkspaceWallpaper {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+
+    /// Workspace index on the specified monitor (zero-indexed)
+    workspace: usize,
+
+    /// Image file to use as the desktop wallpaper when the specified workspace is focused
+    path: String,
+}
+
+#[derive(Parser, AhkFunction)]
+#[clap(setting = AppSettings::AllowNegativeNumbers)]
+pub struct ContainerResizeDimensions {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+
+    /// Workspace index on the specified monitor (zero-indexed)
+    workspace: usize,
+
+    /// Container index on the specified workspace (zero-indexed)
+    container: usize,
+
+    /// Resize adjustment of the left edge in pixels
+    left: i32,
+
+    /// Resize adjustment of the top edge in pixels
+    top: i32,
+
+    /// Resize adjustment of the right edge in pixels
+    right: i32,
+
+    /// Resize adjustment of the bottom edge in pixels
+    bottom: i32,
+}
+
 #[derive(Parser, AhkFunction)]
 struct Resize {
     #[clap(arg_enum)]
@@ -185,6 +309,35 @@ struct ResizeDelta {
     pixels: i32,
 }
 
+#[derive(Parser, AhkFunction)]
+struct FocusModeWidth {
+    /// Width of the focus mode column as a percentage of the work area (1-100)
+    percentage: u8,
+}
+
+#[derive(Parser, AhkFunction)]
+struct RetileFpsCap {
+    /// The maximum number of times per second to retile each monitor when throttling (0 to only
+    /// use the refresh rate of each monitor)
+    fps: u32,
+}
+
+#[derive(Parser, AhkFunction)]
+struct RetileBatchSize {
+    /// The number of windows to position in each pass when updating a workspace with 20 or more
+    /// containers (0 to position every window in a single pass)
+    windows: usize,
+}
+
+#[derive(Parser, AhkFunction)]
+struct MessageRateLimit {
+    /// The number of messages per second a client can send once its burst allowance has been used
+    /// up (0 to disable rate limiting)
+    per_second: u32,
+    /// The number of messages a client can send in quick succession
+    burst: u32,
+}
+
 #[derive(Parser, AhkFunction)]
 struct InvisibleBorders {
     /// Size of the left invisible border
@@ -217,6 +370,76 @@ struct EnsureWorkspaces {
     workspace_count: usize,
 }
 
+#[derive(Parser, AhkFunction)]
+struct MonitorGroup {
+    /// Primary monitor index (zero-indexed), which will manage the windows of the group
+    primary: usize,
+    /// Secondary monitor index (zero-indexed), which must be beside the primary monitor
+    secondary: usize,
+    /// Physical gap between the two displays in pixels
+    bezel_gap: i32,
+}
+
+#[derive(Parser, AhkFunction)]
+struct RemoveMonitorGroup {
+    /// Primary monitor index (zero-indexed) of the monitor group
+    monitor: usize,
+}
+
+#[derive(Parser, AhkFunction)]
+struct AddExclusionZone {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+    /// Left edge of the zone, relative to the left edge of the monitor
+    left: i32,
+    /// Top edge of the zone, relative to the top edge of the monitor
+    top: i32,
+    /// Width of the zone
+    width: i32,
+    /// Height of the zone
+    height: i32,
+}
+
+#[derive(Parser, AhkFunction)]
+struct ClearExclusionZones {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+}
+
+#[derive(Parser, AhkFunction)]
+struct LearningMode {
+    /// Number of seconds to record suggestions for
+    seconds: u64,
+}
+
+#[derive(Parser, AhkFunction)]
+struct MonitorWorkAreaSize {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+    /// Left edge of the work area
+    left: i32,
+    /// Top edge of the work area
+    top: i32,
+    /// Width of the work area
+    width: i32,
+    /// Height of the work area
+    height: i32,
+}
+
+#[derive(Parser, AhkFunction)]
+struct ClearMonitorWorkAreaSize {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+}
+
+#[derive(Parser, AhkFunction)]
+struct MonitorWorkspaceSync {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+    #[clap(arg_enum)]
+    value: BooleanState,
+}
+
 #[derive(Parser, AhkFunction)]
 struct FocusMonitorWorkspace {
     /// Target monitor index (zero-indexed)
@@ -225,6 +448,34 @@ struct FocusMonitorWorkspace {
     target_workspace: usize,
 }
 
+#[derive(Parser, AhkFunction)]
+struct FocusWindowByHwnd {
+    /// Window hwnd, as shown in the output of the window-list command
+    hwnd: isize,
+}
+
+#[derive(Parser, AhkFunction)]
+struct FocusHint {
+    /// Label of the container hint to focus
+    label: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct FocusContainer {
+    /// Container id, as shown in the output of the state command
+    id: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct MoveContainer {
+    /// Container id, as shown in the output of the state command
+    id: String,
+    /// Target monitor index (zero-indexed)
+    target_monitor: usize,
+    /// Workspace index on the target monitor (zero-indexed)
+    target_workspace: usize,
+}
+
 macro_rules! gen_padding_subcommand_args {
     // SubCommand Pattern
     ( $( $name:ident ),+ $(,)? ) => {
@@ -245,6 +496,19 @@ macro_rules! gen_padding_subcommand_args {
 gen_padding_subcommand_args! {
     ContainerPadding,
     WorkspacePadding,
+    InnerGap,
+    OuterGap,
+}
+
+#[derive(Parser, AhkFunction)]
+struct ContainerPaddingScale {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+    /// Workspace index on the specified monitor (zero-indexed)
+    workspace: usize,
+    /// Pixels of padding for 1, 2, 3... containers, where the last value is also used for any
+    /// number of containers beyond that (none to always use the container padding)
+    sizes: Vec<i32>,
 }
 
 macro_rules! gen_padding_adjustment_subcommand_args {
@@ -265,6 +529,8 @@ macro_rules! gen_padding_adjustment_subcommand_args {
 gen_padding_adjustment_subcommand_args! {
     AdjustContainerPadding,
     AdjustWorkspacePadding,
+    AdjustInnerGap,
+    AdjustOuterGap,
 }
 
 macro_rules! gen_application_target_subcommand_args {
@@ -284,9 +550,14 @@ macro_rules! gen_application_target_subcommand_args {
 
 gen_application_target_subcommand_args! {
     FloatRule,
+    FloatRuleOnce,
     ManageRule,
+    FollowRule,
+    SwallowRule,
+    AppGroupRule,
     IdentifyTrayApplication,
     IdentifyBorderOverflow,
+    IdentifyNoBorderOverflow,
 }
 
 #[derive(Parser, AhkFunction)]
@@ -301,6 +572,94 @@ struct WorkspaceRule {
     workspace: usize,
 }
 
+#[derive(Parser, AhkFunction)]
+struct ApplyWorkspaceRule {
+    #[clap(arg_enum)]
+    identifier: ApplicationIdentifier,
+    /// Identifier as a string
+    id: String,
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+    /// Workspace index on the specified monitor (zero-indexed)
+    workspace: usize,
+}
+
+#[derive(Parser, AhkFunction)]
+struct BoundWorkspaceRule {
+    #[clap(arg_enum)]
+    identifier: ApplicationIdentifier,
+    /// Identifier as a string
+    id: String,
+    /// Name or id of the target workspace
+    workspace: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct WorkspaceRuleOnce {
+    #[clap(arg_enum)]
+    identifier: ApplicationIdentifier,
+    /// Identifier as a string
+    id: String,
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+    /// Workspace index on the specified monitor (zero-indexed)
+    workspace: usize,
+}
+
+#[derive(Parser, AhkFunction)]
+struct ImportConfig {
+    /// File from which the GlazeWM (.yaml) or workspacer (.cs) configuration should be read
+    path: String,
+    /// Monitor index (zero-indexed) that imported workspace rules should target
+    monitor: usize,
+}
+
+#[derive(Parser, AhkFunction)]
+struct SizeConstraintRule {
+    #[clap(arg_enum)]
+    identifier: ApplicationIdentifier,
+    /// Identifier as a string
+    id: String,
+    #[clap(arg_enum)]
+    constraint: SizeConstraint,
+    /// Constraint value in pixels (0 to remove the constraint)
+    value: i32,
+}
+
+#[derive(Parser, AhkFunction)]
+struct AddRuleGroup {
+    /// Name of the rule group to tag the rules that follow with
+    name: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct ToggleRuleGroup {
+    /// Name of the rule group to enable or disable
+    name: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct HidingBehaviourRule {
+    #[clap(arg_enum)]
+    identifier: ApplicationIdentifier,
+    /// Identifier as a string
+    id: String,
+    #[clap(arg_enum)]
+    hiding_behaviour: HidingBehaviour,
+}
+
+#[derive(Parser, AhkFunction)]
+struct AddClassBlocklistPattern {
+    /// Window class, where * matches any run of characters and ? matches any single character
+    pattern: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct RemoveClassBlocklistPattern {
+    /// Window class pattern exactly as it was added to the blocklist
+    pattern: String,
+}
+
 #[derive(Parser, AhkFunction)]
 struct ToggleFocusFollowsMouse {
     #[clap(arg_enum, short, long, default_value = "windows")]
@@ -315,6 +674,15 @@ struct FocusFollowsMouse {
     boolean_state: BooleanState,
 }
 
+#[derive(Parser, AhkFunction)]
+struct MouseFollowsFocusPlacement {
+    #[clap(arg_enum)]
+    placement: CursorPlacement,
+    /// Distance in pixels to keep the cursor from the window edges
+    #[clap(short, long, default_value = "0")]
+    offset: i32,
+}
+
 #[derive(Parser, AhkFunction)]
 struct Start {
     /// Allow the use of komorebi's custom focus-follows-mouse implementation
@@ -334,12 +702,42 @@ struct LoadResize {
     path: String,
 }
 
+#[derive(Parser, AhkFunction)]
+struct SaveWorkspaceSnapshot {
+    /// File to which the workspace snapshot should be saved
+    path: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct LoadWorkspaceSnapshot {
+    /// File from which the workspace snapshot should be loaded
+    path: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct SpawnIntoWorkspace {
+    /// Command to launch
+    command: String,
+
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+
+    /// Workspace index on the specified monitor (zero-indexed)
+    workspace: usize,
+}
+
 #[derive(Parser, AhkFunction)]
 struct LoadCustomLayout {
     /// JSON or YAML file from which the custom layout definition should be loaded
     path: String,
 }
 
+#[derive(Parser, AhkFunction)]
+struct GenerateCustomLayout {
+    /// JSON or YAML file to which the custom layout definition should be written
+    path: String,
+}
+
 #[derive(Parser, AhkFunction)]
 struct Subscribe {
     /// Name of the pipe to send event notifications to (without "\\.\pipe\" prepended)
@@ -352,32 +750,146 @@ struct Unsubscribe {
     named_pipe: String,
 }
 
+#[derive(Parser, AhkFunction)]
+struct WebsocketServer {
+    /// Port on localhost to accept WebSocket connections on
+    port: u16,
+    /// Secret which clients must give in the query string of the URL that they connect to
+    secret: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct WebsocketAllowOrigin {
+    /// Origin of the web page to allow (ie. http://localhost:8080)
+    origin: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct MirrorListen {
+    /// Port to accept a mirroring peer on, on every network interface
+    port: u16,
+    /// Secret which the mirroring peer must also have been given
+    secret: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct MirrorConnect {
+    /// Address of the mirroring peer as host:port
+    address: String,
+    /// Secret which the mirroring peer must also have been given
+    secret: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct SendToPeer {
+    /// JSON representation of the socket message for the mirroring peer to run
+    message: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct DefineAlias {
+    /// Name of the alias
+    name: String,
+    /// JSON representations of the socket messages to run when the alias is invoked, in order
+    #[clap(required = true)]
+    messages: Vec<String>,
+}
+
+#[derive(Parser, AhkFunction)]
+struct InvokeAlias {
+    /// Name of the alias to invoke
+    name: String,
+}
+
+#[derive(Parser, AhkFunction)]
+struct LogLevel {
+    /// Filter directives in RUST_LOG syntax, which may also target the events, layout, socket and
+    /// ffm subsystems (ie. info,events=debug)
+    directives: String,
+}
+
 #[derive(Parser)]
 #[clap(author, about, version, setting = AppSettings::DeriveDisplayOrder)]
 struct Opts {
+    /// Print a JSON response envelope ({status, data, error}) for every command
+    #[clap(long, global = true)]
+    json: bool,
     #[clap(subcommand)]
     subcmd: SubCommand,
 }
 
+static JSON_RESPONSE: AtomicBool = AtomicBool::new(false);
+
 #[derive(Parser, AhkLibrary)]
 enum SubCommand {
     /// Start komorebi.exe as a background process
     Start(Start),
     /// Stop the komorebi.exe process and restore all hidden windows
     Stop,
+    /// Restart komorebi.exe in a new process, keeping the current arrangement of windows
+    Restart,
+    /// Discard any messages which komorebi has received but not yet processed, and stop any alias
+    /// which is currently being invoked
+    CancelPendingOperations,
     /// Show a JSON representation of the current window manager state
     State,
     /// Query the current window manager state
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     Query(Query),
+    /// Show a JSON representation of the health of the komorebi.exe process
+    Health,
+    /// Show a JSON list of every managed window and its location
+    WindowList,
+    /// Show a JSON list of every managed window and its location, most recently focused first
+    MruWindowList,
+    /// Record suggested rules for windows which are not managed or are floated or managed by hand
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    LearningMode(LearningMode),
+    /// Show a JSON list of the rules suggested by the last learning mode session
+    RuleSuggestions,
+    /// Show the settings which have been changed from their defaults as JSON
+    ConfigurationDiff,
+    /// Show the socket protocol version of komorebi.exe and the messages that it supports
+    ProtocolVersion,
     /// Subscribe to komorebi events
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     Subscribe(Subscribe),
     /// Unsubscribe from komorebi events
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     Unsubscribe(Unsubscribe),
+    /// Start a WebSocket server on localhost which streams events and accepts queries
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WebsocketServer(WebsocketServer),
+    /// Allow a web page to connect to the WebSocket server
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WebsocketAllowOrigin(WebsocketAllowOrigin),
+    /// Accept a komorebi instance on another machine as a mirroring peer
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    MirrorListen(MirrorListen),
+    /// Connect to a komorebi instance on another machine as a mirroring peer
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    MirrorConnect(MirrorConnect),
+    /// Send a socket message for the mirroring peer to run
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    SendToPeer(SendToPeer),
+    /// Show a JSON representation of the state last published by the mirroring peer
+    PeerState,
+    /// Define an alias which runs a sequence of socket messages in a single command
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    DefineAlias(DefineAlias),
+    /// Invoke an alias which has previously been defined
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    InvokeAlias(InvokeAlias),
     /// Tail komorebi.exe's process logs (cancel with Ctrl-C)
     Log,
+    /// Set komorebi.exe's log level at runtime, optionally per subsystem
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    LogLevel(LogLevel),
+    /// Enable or disable the collection of event, command and retile timings in the window manager state
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    PerformanceCounters(PerformanceCounters),
+    /// Reset all collected event, command and retile timings
+    ResetPerformanceCounters,
     /// Quicksave the current resize layout dimensions
     #[clap(alias = "quick-save")]
     QuickSaveResize,
@@ -392,6 +904,15 @@ enum SubCommand {
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     #[clap(alias = "load")]
     LoadResize(LoadResize),
+    /// Save the focused workspace's layout and the applications in each container to a file
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    SaveWorkspaceSnapshot(SaveWorkspaceSnapshot),
+    /// Load a workspace snapshot into the focused workspace, ahead of any windows already on it, launching any applications not already open
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    LoadWorkspaceSnapshot(LoadWorkspaceSnapshot),
+    /// Launch a command and place its first window on the specified workspace
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    SpawnIntoWorkspace(SpawnIntoWorkspace),
     /// Change focus to the window in the specified direction
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     Focus(Focus),
@@ -414,17 +935,33 @@ enum SubCommand {
     /// Resize the focused window or primary column along the specified axis
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     ResizeAxis(ResizeAxis),
+    /// Cycle the focused window's share of its split through 50%, 66%, 75% and 33%
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    CycleSplitRatio(CycleSplitRatio),
     /// Unstack the focused window
     Unstack,
     /// Cycle the focused stack in the specified cycle direction
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     CycleStack(CycleStack),
+    /// Tile the windows in the focused container along an axis within its tile, one level deep
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    NestContainer(NestContainer),
+    /// Stack the windows in the focused nested container on top of each other again
+    UnnestContainer,
+    /// Give each window in the focused container a container of its own
+    DissolveContainer,
+    /// Place the next managed window in the specified direction from the focused window
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    NextWindowDirection(NextWindowDirection),
     /// Move the focused window to the specified monitor
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     MoveToMonitor(MoveToMonitor),
     /// Move the focused window to the specified workspace
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     MoveToWorkspace(MoveToWorkspace),
+    /// Move the focused window to the specified workspace, optionally following it there
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    MoveToWorkspaceAndFollow(MoveToWorkspaceAndFollow),
     /// Send the focused window to the specified monitor
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     SendToMonitor(SendToMonitor),
@@ -437,9 +974,32 @@ enum SubCommand {
     /// Focus the specified workspace on the focused monitor
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     FocusWorkspace(FocusWorkspace),
+    /// Temporarily show the specified workspace on the focused monitor without focusing it
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    PeekWorkspace(PeekWorkspace),
+    /// Hide the workspace being peeked at and restore the focused workspace
+    EndPeek,
     /// Focus the specified workspace on the target monitor
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     FocusMonitorWorkspace(FocusMonitorWorkspace),
+    /// Focus the previous workspace in the workspace focus history
+    FocusWorkspaceBack,
+    /// Focus the next workspace in the workspace focus history
+    FocusWorkspaceForward,
+    /// Focus the container with the specified id
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    FocusContainer(FocusContainer),
+    /// Focus the managed window with the specified hwnd, switching monitor and workspace if needed
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    FocusWindowByHwnd(FocusWindowByHwnd),
+    /// Show a label on each container on the focused workspace of every monitor
+    ShowContainerHints,
+    /// Focus the container with the specified label from show-container-hints
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    FocusHint(FocusHint),
+    /// Move the container with the specified id to the workspace on the target monitor
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    MoveContainer(MoveContainer),
     /// Focus the monitor in the given cycle direction
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     CycleMonitor(CycleMonitor),
@@ -449,11 +1009,32 @@ enum SubCommand {
     /// Move the focused workspace to the specified monitor
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     MoveWorkspaceToMonitor(MoveWorkspaceToMonitor),
+    /// Move the focused workspace to the specified index on the same monitor, keeping workspace rules pointed at the same workspaces
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    MoveWorkspaceToIndex(MoveWorkspaceToIndex),
     /// Create and append a new workspace on the focused monitor
     NewWorkspace,
     /// Set the resize delta (used by resize-edge and resize-axis)
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     ResizeDelta(ResizeDelta),
+    /// Set the resize delta for the specified workspace, overriding the global setting (0 to remove the override)
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WorkspaceResizeDelta(WorkspaceResizeDelta),
+    /// Set the width of the centred column used by focus mode
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    FocusModeWidth(FocusModeWidth),
+    /// Enable or disable throttling retiles of each monitor to its refresh rate
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    ThrottleRetiles(ThrottleRetiles),
+    /// Set a cap on the number of times per second each monitor is retiled when throttling
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    RetileFpsCap(RetileFpsCap),
+    /// Set the number of windows positioned in each pass of the event loop on very large workspaces
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    RetileBatchSize(RetileBatchSize),
+    /// Limit the rate at which komorebi accepts messages from each client
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    MessageRateLimit(MessageRateLimit),
     /// Set the invisible border dimensions around each window
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     InvisibleBorders(InvisibleBorders),
@@ -466,37 +1047,116 @@ enum SubCommand {
     /// Adjust workspace padding on the focused workspace
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     AdjustWorkspacePadding(AdjustWorkspacePadding),
+    /// Adjust the gap between windows on the focused workspace
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    AdjustInnerGap(AdjustInnerGap),
+    /// Adjust the gap between windows and the edges of the focused workspace
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    AdjustOuterGap(AdjustOuterGap),
     /// Set the layout on the focused workspace
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     ChangeLayout(ChangeLayout),
+    /// Cycle between the layouts in the rotation of the focused workspace
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    CycleLayout(CycleLayout),
     /// Load a custom layout from file for the focused workspace
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     LoadCustomLayout(LoadCustomLayout),
+    /// Write the arrangement of containers on the focused workspace to file as a custom layout
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    GenerateCustomLayout(GenerateCustomLayout),
     /// Flip the layout on the focused workspace (BSP only)
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     FlipLayout(FlipLayout),
+    /// Toggle the orientation of the split containing the focused container (BSP only)
+    ToggleSplitOrientation,
     /// Promote the focused window to the top of the tree
     Promote,
+    /// Clear all resize adjustments on the focused workspace
+    BalanceWorkspace,
+    /// Focus the container in the primary zone of the layout (the primary column of custom layouts)
+    FocusPrimaryZone,
     /// Force the retiling of all managed windows
     Retile,
+    /// Reconcile the window manager state with all of the windows on the desktop
+    Reconcile,
     /// Create at least this many workspaces for the specified monitor
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     EnsureWorkspaces(EnsureWorkspaces),
+    /// Tile two side-by-side monitors as a single work area
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    MonitorGroup(MonitorGroup),
+    /// Split a monitor group back into individual monitors
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    RemoveMonitorGroup(RemoveMonitorGroup),
+    /// Toggle switching every monitor to the same workspace index whenever a workspace is focused
+    ToggleWorkspaceSync,
+    /// Enable or disable workspace sync for the specified monitor
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    MonitorWorkspaceSync(MonitorWorkspaceSync),
+    /// Reserve an area of the specified monitor which windows will not be tiled over
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    AddExclusionZone(AddExclusionZone),
+    /// Remove all reserved areas from the specified monitor
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    ClearExclusionZones(ClearExclusionZones),
+    /// Replace the work area reported by the OS for the specified monitor
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    MonitorWorkAreaSize(MonitorWorkAreaSize),
+    /// Use the work area reported by the OS for the specified monitor again
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    ClearMonitorWorkAreaSize(ClearMonitorWorkAreaSize),
     /// Set the container padding for the specified workspace
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     ContainerPadding(ContainerPadding),
+    /// Shrink the container padding of the specified workspace as the number of containers grows
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    ContainerPaddingScale(ContainerPaddingScale),
     /// Set the workspace padding for the specified workspace
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     WorkspacePadding(WorkspacePadding),
+    /// Set the gap between windows for the specified workspace (replaces padding)
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    InnerGap(InnerGap),
+    /// Set the gap between windows and the workspace edges for the specified workspace (replaces padding)
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    OuterGap(OuterGap),
     /// Set the layout for the specified workspace
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     WorkspaceLayout(WorkspaceLayout),
     /// Set a custom layout for the specified workspace
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     WorkspaceCustomLayout(WorkspaceCustomLayout),
+    /// Set the layouts to cycle between for the specified workspace
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WorkspaceLayoutRotation(WorkspaceLayoutRotation),
+    /// Cycle through the layout rotation of the specified workspace on a timer
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WorkspaceLayoutCycleInterval(WorkspaceLayoutCycleInterval),
+    /// Limit the layout of the specified workspace to a number of regions which each act as a stack
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WorkspaceMultiStack(WorkspaceMultiStack),
+    /// Copy the layout settings of a workspace to the same workspace on another monitor
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    CloneWorkspaceLayoutToMonitor(CloneWorkspaceLayoutToMonitor),
+    /// Set the desktop wallpaper to use when the specified workspace is focused
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WorkspaceWallpaper(WorkspaceWallpaper),
+    /// Set the resize dimensions of the specified container (all zeros to clear them)
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    ContainerResizeDimensions(ContainerResizeDimensions),
     /// Enable or disable window tiling for the specified workspace
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     WorkspaceTiling(WorkspaceTiling),
+    /// Enable or disable focus follows mouse for the specified workspace, overriding the global setting
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WorkspaceFocusFollowsMouse(WorkspaceFocusFollowsMouse),
+    /// Treat the paddings and gaps of the specified workspace as logical pixels, scaling them to the DPI of its monitor
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WorkspaceLogicalPadding(WorkspaceLogicalPadding),
+    /// Hide the taskbar while the specified workspace is focused, tiling over the space it occupied
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WorkspaceTaskbarHidden(WorkspaceTaskbarHidden),
     /// Set the workspace name for the specified workspace
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     WorkspaceName(WorkspaceName),
@@ -508,10 +1168,25 @@ enum SubCommand {
     ToggleTiling,
     /// Toggle floating mode for the focused window
     ToggleFloat,
+    /// Tile the focused floating window next to the nearest container in the specified direction
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    TileFloat(TileFloat),
     /// Toggle monocle mode for the focused container
     ToggleMonocle,
+    /// Toggle whether the focused floating window is kept on top of the monocle container
+    ToggleMonoclePip,
+    /// Raise the focused window above the other windows on the focused workspace
+    RaiseWindow,
+    /// Lower the focused window below the other windows on the focused workspace
+    LowerWindow,
+    /// Toggle whether the focused window is kept on top of every other window
+    ToggleWindowOnTop,
+    /// Toggle focus mode, which hides every other container and centres the focused container
+    ToggleFocusMode,
     /// Toggle native maximization for the focused window
     ToggleMaximize,
+    /// Toggle expanding the focused container over the work area without changing the layout
+    ToggleContainerMaximize,
     /// Restore all hidden windows (debugging command)
     RestoreWindows,
     /// Force komorebi to manage the focused window
@@ -526,21 +1201,76 @@ enum SubCommand {
     /// Set the window behaviour when switching workspaces / cycling stacks
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     WindowHidingBehaviour(WindowHidingBehaviour),
+    /// Set where focus goes when the last window on the focused workspace is closed
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    OnEmptyWorkspace(OnEmptyWorkspace),
+    /// Set the window behaviour when switching workspaces / cycling stacks for the specified application
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    HidingBehaviourRule(HidingBehaviourRule),
     /// Add a rule to always float the specified application
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     FloatRule(FloatRule),
+    /// Add a rule to float the next window of the specified application, which expires once used
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    FloatRuleOnce(FloatRuleOnce),
     /// Add a rule to always manage the specified application
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     ManageRule(ManageRule),
+    /// Add a rule to follow new windows of the specified application to their workspace
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    FollowRule(FollowRule),
+    /// Add a rule for the specified application to be replaced by GUI windows launched from it
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    SwallowRule(SwallowRule),
+    /// Add a rule to stack every window of the specified application in a single container on each workspace
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    AppGroupRule(AppGroupRule),
     /// Add a rule to associate an application with a workspace
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     WorkspaceRule(WorkspaceRule),
+    /// Add a rule to associate an application with a workspace and move any matching windows that are already open
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    ApplyWorkspaceRule(ApplyWorkspaceRule),
+    /// Add a rule to send the next window of the specified application to a workspace, which expires once used
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WorkspaceRuleOnce(WorkspaceRuleOnce),
+    /// Add a rule to associate an application with a workspace by its name or id, which keeps working when workspaces are reordered
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    BoundWorkspaceRule(BoundWorkspaceRule),
+    /// Import float and workspace rules from a GlazeWM or workspacer configuration
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    ImportConfig(ImportConfig),
+    /// Move all managed windows to the workspaces defined for them by workspace rules
+    ApplyWorkspaceRules,
+    /// Enable or disable the enforcement of workspace rules when window titles change
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    WorkspaceRulesOnTitleChange(WorkspaceRulesOnTitleChange),
     /// Identify an application that closes to the system tray
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     IdentifyTrayApplication(IdentifyTrayApplication),
     /// Identify an application that has overflowing borders
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     IdentifyBorderOverflow(IdentifyBorderOverflow),
+    /// Identify a Chromium-based application that does not have overflowing borders
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    IdentifyNoBorderOverflow(IdentifyNoBorderOverflow),
+    /// Never manage windows with a class matching the specified pattern
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    AddClassBlocklistPattern(AddClassBlocklistPattern),
+    /// Remove a pattern, including any of the built-in patterns, from the window class blocklist
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    RemoveClassBlocklistPattern(RemoveClassBlocklistPattern),
+    /// Add a size constraint rule for the specified application
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    SizeConstraintRule(SizeConstraintRule),
+    /// Tag every rule added after this command with a named rule group
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    AddRuleGroup(AddRuleGroup),
+    /// Stop tagging the rules that follow with the current rule group
+    EndRuleGroup,
+    /// Enable or disable every rule tagged with a rule group
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    ToggleRuleGroup(ToggleRuleGroup),
     /// Enable or disable focus follows mouse for the operating system
     #[clap(setting = AppSettings::ArgRequiredElseHelp)]
     FocusFollowsMouse(FocusFollowsMouse),
@@ -552,22 +1282,96 @@ enum SubCommand {
     MouseFollowsFocus(MouseFollowsFocus),
     /// Toggle mouse follows focus on all workspaces
     ToggleMouseFollowsFocus,
+    /// Set where mouse follows focus places the cursor in the focused window
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    MouseFollowsFocusPlacement(MouseFollowsFocusPlacement),
+    /// Enable or disable the stack indicator in event notifications
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    StackIndicator(StackIndicator),
+    /// Enable or disable floating windows of elevated processes when komorebi is not elevated
+    #[clap(setting = AppSettings::ArgRequiredElseHelp)]
+    FloatElevatedWindows(FloatElevatedWindows),
     /// Generate a library of AutoHotKey helper functions
     AhkLibrary,
 }
 
 pub fn send_message(bytes: &[u8]) -> Result<()> {
+    if JSON_RESPONSE.load(Ordering::SeqCst) {
+        return send_query(bytes);
+    }
+
+    write_message(bytes)
+}
+
+/// Messages sent this way are handled ahead of any messages already queued by komorebi
+pub fn send_priority_message(bytes: &[u8]) -> Result<()> {
+    if JSON_RESPONSE.load(Ordering::SeqCst) {
+        return send_query(bytes);
+    }
+
+    write_to_socket("komorebi-priority.sock", bytes)
+}
+
+fn write_message(bytes: &[u8]) -> Result<()> {
+    write_to_socket("komorebi.sock", bytes)
+}
+
+fn write_to_socket(name: &str, bytes: &[u8]) -> Result<()> {
     let mut socket = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
-    socket.push("komorebi.sock");
+    socket.push(name);
     let socket = socket.as_path();
 
     let mut stream = UnixStream::connect(&socket)?;
     Ok(stream.write_all(&*bytes)?)
 }
 
+/// # Panics
+///
+/// Panics if the connection that komorebi sends the response over cannot be accepted
+pub fn send_query(bytes: &[u8]) -> Result<()> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
+    let mut socket = home;
+    socket.push("komorebic.sock");
+    let socket = socket.as_path();
+
+    match std::fs::remove_file(&socket) {
+        Ok(_) => {}
+        Err(error) => match error.kind() {
+            // Doing this because ::exists() doesn't work reliably on Windows via IntelliJ
+            ErrorKind::NotFound => {}
+            _ => {
+                return Err(error.into());
+            }
+        },
+    };
+
+    if JSON_RESPONSE.load(Ordering::SeqCst) {
+        let message = SocketMessage::from_str(std::str::from_utf8(bytes)?)?;
+        write_message(&*SocketMessage::WithResponse(Box::new(message)).as_bytes()?)?;
+    } else {
+        write_message(bytes)?;
+    }
+
+    let listener = UnixListener::bind(&socket)?;
+    match listener.accept() {
+        Ok(incoming) => {
+            let stream = BufReader::new(incoming.0);
+            for line in stream.lines() {
+                println!("{}", line?);
+            }
+
+            Ok(())
+        }
+        Err(error) => {
+            panic!("{}", error);
+        }
+    }
+}
+
 #[allow(clippy::too_many_lines)]
 fn main() -> Result<()> {
     let opts: Opts = Opts::parse();
+    JSON_RESPONSE.store(opts.json, Ordering::SeqCst);
 
     match opts.subcmd {
         SubCommand::AhkLibrary => {
@@ -604,18 +1408,38 @@ fn main() -> Result<()> {
                 println!("{}", line?);
             }
         }
+        SubCommand::LogLevel(arg) => {
+            send_message(&*SocketMessage::SetLogLevel(arg.directives).as_bytes()?)?;
+        }
+        SubCommand::PerformanceCounters(arg) => {
+            send_message(
+                &*SocketMessage::PerformanceCounters(arg.boolean_state.into()).as_bytes()?,
+            )?;
+        }
+        SubCommand::ResetPerformanceCounters => {
+            send_message(&*SocketMessage::ResetPerformanceCounters.as_bytes()?)?;
+        }
         SubCommand::Focus(arg) => {
             send_message(&*SocketMessage::FocusWindow(arg.operation_direction).as_bytes()?)?;
         }
         SubCommand::Promote => {
             send_message(&*SocketMessage::Promote.as_bytes()?)?;
         }
+        SubCommand::BalanceWorkspace => {
+            send_message(&*SocketMessage::BalanceWorkspace.as_bytes()?)?;
+        }
+        SubCommand::FocusPrimaryZone => {
+            send_message(&*SocketMessage::FocusPrimaryZone.as_bytes()?)?;
+        }
         SubCommand::TogglePause => {
-            send_message(&*SocketMessage::TogglePause.as_bytes()?)?;
+            send_priority_message(&*SocketMessage::TogglePause.as_bytes()?)?;
         }
         SubCommand::Retile => {
             send_message(&*SocketMessage::Retile.as_bytes()?)?;
         }
+        SubCommand::Reconcile => {
+            send_message(&*SocketMessage::Reconcile.as_bytes()?)?;
+        }
         SubCommand::Move(arg) => {
             send_message(&*SocketMessage::MoveWindow(arg.operation_direction).as_bytes()?)?;
         }
@@ -631,6 +1455,15 @@ fn main() -> Result<()> {
         SubCommand::MoveToWorkspace(arg) => {
             send_message(&*SocketMessage::MoveContainerToWorkspaceNumber(arg.target).as_bytes()?)?;
         }
+        SubCommand::MoveToWorkspaceAndFollow(arg) => {
+            send_message(
+                &*SocketMessage::MoveContainerToWorkspaceNumberAndFollow(
+                    arg.target,
+                    arg.follow.into(),
+                )
+                .as_bytes()?,
+            )?;
+        }
         SubCommand::SendToMonitor(arg) => {
             send_message(&*SocketMessage::SendContainerToMonitorNumber(arg.target).as_bytes()?)?;
         }
@@ -640,6 +1473,9 @@ fn main() -> Result<()> {
         SubCommand::MoveWorkspaceToMonitor(arg) => {
             send_message(&*SocketMessage::MoveWorkspaceToMonitorNumber(arg.target).as_bytes()?)?;
         }
+        SubCommand::MoveWorkspaceToIndex(arg) => {
+            send_message(&*SocketMessage::MoveWorkspaceToIndex(arg.target).as_bytes()?)?;
+        }
         SubCommand::InvisibleBorders(arg) => {
             send_message(
                 &*SocketMessage::InvisibleBorders(Rect {
@@ -668,6 +1504,12 @@ fn main() -> Result<()> {
                     .as_bytes()?,
             )?;
         }
+        SubCommand::ContainerPaddingScale(arg) => {
+            send_message(
+                &*SocketMessage::ContainerPaddingScale(arg.monitor, arg.workspace, arg.sizes)
+                    .as_bytes()?,
+            )?;
+        }
         SubCommand::WorkspacePadding(arg) => {
             send_message(
                 &*SocketMessage::WorkspacePadding(arg.monitor, arg.workspace, arg.size)
@@ -679,6 +1521,22 @@ fn main() -> Result<()> {
                 &*SocketMessage::AdjustWorkspacePadding(arg.sizing, arg.adjustment).as_bytes()?,
             )?;
         }
+        SubCommand::InnerGap(arg) => {
+            send_message(
+                &*SocketMessage::InnerGap(arg.monitor, arg.workspace, arg.size).as_bytes()?,
+            )?;
+        }
+        SubCommand::OuterGap(arg) => {
+            send_message(
+                &*SocketMessage::OuterGap(arg.monitor, arg.workspace, arg.size).as_bytes()?,
+            )?;
+        }
+        SubCommand::AdjustInnerGap(arg) => {
+            send_message(&*SocketMessage::AdjustInnerGap(arg.sizing, arg.adjustment).as_bytes()?)?;
+        }
+        SubCommand::AdjustOuterGap(arg) => {
+            send_message(&*SocketMessage::AdjustOuterGap(arg.sizing, arg.adjustment).as_bytes()?)?;
+        }
         SubCommand::AdjustContainerPadding(arg) => {
             send_message(
                 &*SocketMessage::AdjustContainerPadding(arg.sizing, arg.adjustment).as_bytes()?,
@@ -693,12 +1551,33 @@ fn main() -> Result<()> {
         SubCommand::ToggleFloat => {
             send_message(&*SocketMessage::ToggleFloat.as_bytes()?)?;
         }
+        SubCommand::TileFloat(arg) => {
+            send_message(&*SocketMessage::TileFloat(arg.operation_direction).as_bytes()?)?;
+        }
         SubCommand::ToggleMonocle => {
             send_message(&*SocketMessage::ToggleMonocle.as_bytes()?)?;
         }
+        SubCommand::ToggleMonoclePip => {
+            send_message(&*SocketMessage::ToggleMonoclePip.as_bytes()?)?;
+        }
+        SubCommand::RaiseWindow => {
+            send_message(&*SocketMessage::RaiseWindow.as_bytes()?)?;
+        }
+        SubCommand::LowerWindow => {
+            send_message(&*SocketMessage::LowerWindow.as_bytes()?)?;
+        }
+        SubCommand::ToggleWindowOnTop => {
+            send_message(&*SocketMessage::ToggleWindowOnTop.as_bytes()?)?;
+        }
+        SubCommand::ToggleFocusMode => {
+            send_message(&*SocketMessage::ToggleFocusMode.as_bytes()?)?;
+        }
         SubCommand::ToggleMaximize => {
             send_message(&*SocketMessage::ToggleMaximize.as_bytes()?)?;
         }
+        SubCommand::ToggleContainerMaximize => {
+            send_message(&*SocketMessage::ToggleContainerMaximize.as_bytes()?)?;
+        }
         SubCommand::WorkspaceLayout(arg) => {
             send_message(
                 &*SocketMessage::WorkspaceLayout(arg.monitor, arg.workspace, arg.value)
@@ -715,12 +1594,101 @@ fn main() -> Result<()> {
                 .as_bytes()?,
             )?;
         }
+        SubCommand::WorkspaceLayoutRotation(arg) => {
+            send_message(
+                &*SocketMessage::WorkspaceLayoutRotation(arg.monitor, arg.workspace, arg.layouts)
+                    .as_bytes()?,
+            )?;
+        }
+        SubCommand::WorkspaceLayoutCycleInterval(arg) => {
+            send_message(
+                &*SocketMessage::WorkspaceLayoutCycleInterval(
+                    arg.monitor,
+                    arg.workspace,
+                    arg.interval,
+                    arg.layouts,
+                )
+                .as_bytes()?,
+            )?;
+        }
+        SubCommand::WorkspaceMultiStack(arg) => {
+            send_message(
+                &*SocketMessage::WorkspaceMultiStack(arg.monitor, arg.workspace, arg.regions)
+                    .as_bytes()?,
+            )?;
+        }
+        SubCommand::CloneWorkspaceLayoutToMonitor(arg) => {
+            send_message(
+                &*SocketMessage::CloneWorkspaceLayoutToMonitor(
+                    arg.monitor,
+                    arg.workspace,
+                    arg.target_monitor,
+                )
+                .as_bytes()?,
+            )?;
+        }
+        SubCommand::WorkspaceWallpaper(arg) => {
+            send_message(
+                &*SocketMessage::WorkspaceWallpaper(
+                    arg.monitor,
+                    arg.workspace,
+                    resolve_windows_path(&arg.path)?,
+                )
+                .as_bytes()?,
+            )?;
+        }
+        SubCommand::ContainerResizeDimensions(arg) => {
+            send_message(
+                &*SocketMessage::SetContainerResizeDimensions(
+                    arg.monitor,
+                    arg.workspace,
+                    arg.container,
+                    Rect {
+                        left: arg.left,
+                        top: arg.top,
+                        right: arg.right,
+                        bottom: arg.bottom,
+                    },
+                )
+                .as_bytes()?,
+            )?;
+        }
         SubCommand::WorkspaceTiling(arg) => {
             send_message(
                 &*SocketMessage::WorkspaceTiling(arg.monitor, arg.workspace, arg.value.into())
                     .as_bytes()?,
             )?;
         }
+        SubCommand::WorkspaceFocusFollowsMouse(arg) => {
+            send_message(
+                &*SocketMessage::WorkspaceFocusFollowsMouse(
+                    arg.monitor,
+                    arg.workspace,
+                    arg.value.into(),
+                )
+                .as_bytes()?,
+            )?;
+        }
+        SubCommand::WorkspaceLogicalPadding(arg) => {
+            send_message(
+                &*SocketMessage::WorkspaceLogicalPadding(
+                    arg.monitor,
+                    arg.workspace,
+                    arg.value.into(),
+                )
+                .as_bytes()?,
+            )?;
+        }
+        SubCommand::WorkspaceTaskbarHidden(arg) => {
+            send_message(
+                &*SocketMessage::WorkspaceTaskbarHidden(
+                    arg.monitor,
+                    arg.workspace,
+                    arg.value.into(),
+                )
+                .as_bytes()?,
+            )?;
+        }
         SubCommand::Start(arg) => {
             let mut buf: PathBuf;
 
@@ -775,20 +1743,87 @@ fn main() -> Result<()> {
             }
         }
         SubCommand::Stop => {
-            send_message(&*SocketMessage::Stop.as_bytes()?)?;
+            send_priority_message(&*SocketMessage::Stop.as_bytes()?)?;
+        }
+        SubCommand::CancelPendingOperations => {
+            send_message(&*SocketMessage::CancelPendingOperations.as_bytes()?)?;
+        }
+        SubCommand::Restart => {
+            send_message(&*SocketMessage::Restart.as_bytes()?)?;
         }
         SubCommand::FloatRule(arg) => {
             send_message(&*SocketMessage::FloatRule(arg.identifier, arg.id).as_bytes()?)?;
         }
+        SubCommand::FloatRuleOnce(arg) => {
+            send_message(&*SocketMessage::FloatRuleOnce(arg.identifier, arg.id).as_bytes()?)?;
+        }
         SubCommand::ManageRule(arg) => {
             send_message(&*SocketMessage::ManageRule(arg.identifier, arg.id).as_bytes()?)?;
         }
+        SubCommand::FollowRule(arg) => {
+            send_message(&*SocketMessage::FollowRule(arg.identifier, arg.id).as_bytes()?)?;
+        }
+        SubCommand::SwallowRule(arg) => {
+            send_message(&*SocketMessage::SwallowRule(arg.identifier, arg.id).as_bytes()?)?;
+        }
+        SubCommand::AppGroupRule(arg) => {
+            send_message(&*SocketMessage::AppGroupRule(arg.identifier, arg.id).as_bytes()?)?;
+        }
         SubCommand::WorkspaceRule(arg) => {
             send_message(
                 &*SocketMessage::WorkspaceRule(arg.identifier, arg.id, arg.monitor, arg.workspace)
                     .as_bytes()?,
             )?;
         }
+        SubCommand::ApplyWorkspaceRule(arg) => {
+            send_message(
+                &*SocketMessage::ApplyWorkspaceRule(
+                    arg.identifier,
+                    arg.id,
+                    arg.monitor,
+                    arg.workspace,
+                )
+                .as_bytes()?,
+            )?;
+        }
+        SubCommand::BoundWorkspaceRule(arg) => {
+            send_message(
+                &*SocketMessage::BoundWorkspaceRule(arg.identifier, arg.id, arg.workspace)
+                    .as_bytes()?,
+            )?;
+        }
+        SubCommand::WorkspaceRuleOnce(arg) => {
+            send_message(
+                &*SocketMessage::WorkspaceRuleOnce(
+                    arg.identifier,
+                    arg.id,
+                    arg.monitor,
+                    arg.workspace,
+                )
+                .as_bytes()?,
+            )?;
+        }
+        SubCommand::ImportConfig(arg) => {
+            let imported = ImportedConfig::load(&resolve_windows_path(&arg.path)?)?;
+
+            for rule in &imported.rules {
+                send_message(&*rule.socket_message(arg.monitor).as_bytes()?)?;
+                println!("{}", rule.komorebic_command(arg.monitor));
+            }
+
+            for skipped in &imported.skipped {
+                println!("skipped: {}", skipped);
+            }
+        }
+        SubCommand::ApplyWorkspaceRules => {
+            send_message(&*SocketMessage::ApplyWorkspaceRules.as_bytes()?)?;
+        }
+        SubCommand::WorkspaceRulesOnTitleChange(arg) => {
+            send_message(
+                &*SocketMessage::WorkspaceRulesOnTitleChange(arg.boolean_state.into())
+                    .as_bytes()?,
+            )?;
+        }
         SubCommand::Stack(arg) => {
             send_message(&*SocketMessage::StackWindow(arg.operation_direction).as_bytes()?)?;
         }
@@ -798,23 +1833,57 @@ fn main() -> Result<()> {
         SubCommand::CycleStack(arg) => {
             send_message(&*SocketMessage::CycleStack(arg.cycle_direction).as_bytes()?)?;
         }
+        SubCommand::NestContainer(arg) => {
+            send_message(&*SocketMessage::NestContainer(arg.axis).as_bytes()?)?;
+        }
+        SubCommand::UnnestContainer => {
+            send_message(&*SocketMessage::UnnestContainer.as_bytes()?)?;
+        }
+        SubCommand::DissolveContainer => {
+            send_message(&*SocketMessage::DissolveContainer.as_bytes()?)?;
+        }
+        SubCommand::NextWindowDirection(arg) => {
+            send_message(
+                &*SocketMessage::NextWindowDirection(arg.operation_direction).as_bytes()?,
+            )?;
+        }
         SubCommand::ChangeLayout(arg) => {
             send_message(&*SocketMessage::ChangeLayout(arg.default_layout).as_bytes()?)?;
         }
+        SubCommand::CycleLayout(arg) => {
+            send_message(&*SocketMessage::CycleLayout(arg.cycle_direction).as_bytes()?)?;
+        }
         SubCommand::LoadCustomLayout(arg) => {
             send_message(
                 &*SocketMessage::ChangeLayoutCustom(resolve_windows_path(&arg.path)?).as_bytes()?,
             )?;
         }
+        SubCommand::GenerateCustomLayout(arg) => {
+            send_message(
+                &*SocketMessage::GenerateCustomLayoutFromWorkspace(resolve_windows_path(
+                    &arg.path,
+                )?)
+                .as_bytes()?,
+            )?;
+        }
         SubCommand::FlipLayout(arg) => {
             send_message(&*SocketMessage::FlipLayout(arg.axis).as_bytes()?)?;
         }
+        SubCommand::ToggleSplitOrientation => {
+            send_message(&*SocketMessage::ToggleSplitOrientation.as_bytes()?)?;
+        }
         SubCommand::FocusMonitor(arg) => {
             send_message(&*SocketMessage::FocusMonitorNumber(arg.target).as_bytes()?)?;
         }
         SubCommand::FocusWorkspace(arg) => {
             send_message(&*SocketMessage::FocusWorkspaceNumber(arg.target).as_bytes()?)?;
         }
+        SubCommand::PeekWorkspace(arg) => {
+            send_message(&*SocketMessage::PeekWorkspace(arg.target).as_bytes()?)?;
+        }
+        SubCommand::EndPeek => {
+            send_message(&*SocketMessage::EndPeek.as_bytes()?)?;
+        }
         SubCommand::FocusMonitorWorkspace(arg) => {
             send_message(
                 &*SocketMessage::FocusMonitorWorkspaceNumber(
@@ -824,6 +1893,34 @@ fn main() -> Result<()> {
                 .as_bytes()?,
             )?;
         }
+        SubCommand::FocusWorkspaceBack => {
+            send_message(&*SocketMessage::FocusWorkspaceBack.as_bytes()?)?;
+        }
+        SubCommand::FocusWorkspaceForward => {
+            send_message(&*SocketMessage::FocusWorkspaceForward.as_bytes()?)?;
+        }
+        SubCommand::FocusContainer(arg) => {
+            send_message(&*SocketMessage::FocusContainerById(arg.id).as_bytes()?)?;
+        }
+        SubCommand::FocusWindowByHwnd(arg) => {
+            send_message(&*SocketMessage::FocusWindowByHwnd(arg.hwnd).as_bytes()?)?;
+        }
+        SubCommand::ShowContainerHints => {
+            send_message(&*SocketMessage::ShowContainerHints.as_bytes()?)?;
+        }
+        SubCommand::FocusHint(arg) => {
+            send_message(&*SocketMessage::FocusHint(arg.label).as_bytes()?)?;
+        }
+        SubCommand::MoveContainer(arg) => {
+            send_message(
+                &*SocketMessage::MoveContainerById(
+                    arg.id,
+                    arg.target_monitor,
+                    arg.target_workspace,
+                )
+                .as_bytes()?,
+            )?;
+        }
         SubCommand::CycleMonitor(arg) => {
             send_message(&*SocketMessage::CycleFocusMonitor(arg.cycle_direction).as_bytes()?)?;
         }
@@ -845,73 +1942,83 @@ fn main() -> Result<()> {
                     .as_bytes()?,
             )?;
         }
+        SubCommand::MonitorGroup(arg) => {
+            send_message(
+                &*SocketMessage::MonitorGroup(arg.primary, arg.secondary, arg.bezel_gap)
+                    .as_bytes()?,
+            )?;
+        }
+        SubCommand::RemoveMonitorGroup(arg) => {
+            send_message(&*SocketMessage::RemoveMonitorGroup(arg.monitor).as_bytes()?)?;
+        }
+        SubCommand::ToggleWorkspaceSync => {
+            send_message(&*SocketMessage::ToggleWorkspaceSync.as_bytes()?)?;
+        }
+        SubCommand::AddExclusionZone(arg) => {
+            send_message(
+                &*SocketMessage::AddExclusionZone(
+                    arg.monitor,
+                    Rect {
+                        left: arg.left,
+                        top: arg.top,
+                        right: arg.width,
+                        bottom: arg.height,
+                    },
+                )
+                .as_bytes()?,
+            )?;
+        }
+        SubCommand::ClearExclusionZones(arg) => {
+            send_message(&*SocketMessage::ClearExclusionZones(arg.monitor).as_bytes()?)?;
+        }
+        SubCommand::MonitorWorkAreaSize(arg) => {
+            send_message(
+                &*SocketMessage::MonitorWorkAreaSize(
+                    arg.monitor,
+                    Rect {
+                        left: arg.left,
+                        top: arg.top,
+                        right: arg.width,
+                        bottom: arg.height,
+                    },
+                )
+                .as_bytes()?,
+            )?;
+        }
+        SubCommand::ClearMonitorWorkAreaSize(arg) => {
+            send_message(&*SocketMessage::ClearMonitorWorkAreaSize(arg.monitor).as_bytes()?)?;
+        }
+        SubCommand::MonitorWorkspaceSync(arg) => {
+            send_message(
+                &*SocketMessage::MonitorWorkspaceSync(arg.monitor, arg.value.into()).as_bytes()?,
+            )?;
+        }
         SubCommand::State => {
-            let home = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
-            let mut socket = home;
-            socket.push("komorebic.sock");
-            let socket = socket.as_path();
-
-            match std::fs::remove_file(&socket) {
-                Ok(_) => {}
-                Err(error) => match error.kind() {
-                    // Doing this because ::exists() doesn't work reliably on Windows via IntelliJ
-                    ErrorKind::NotFound => {}
-                    _ => {
-                        return Err(error.into());
-                    }
-                },
-            };
-
-            send_message(&*SocketMessage::State.as_bytes()?)?;
-
-            let listener = UnixListener::bind(&socket)?;
-            match listener.accept() {
-                Ok(incoming) => {
-                    let stream = BufReader::new(incoming.0);
-                    for line in stream.lines() {
-                        println!("{}", line?);
-                    }
-
-                    return Ok(());
-                }
-                Err(error) => {
-                    panic!("{}", error);
-                }
-            }
+            send_query(&*SocketMessage::State.as_bytes()?)?;
         }
         SubCommand::Query(arg) => {
-            let home = dirs::home_dir().ok_or_else(|| anyhow!("there is no home directory"))?;
-            let mut socket = home;
-            socket.push("komorebic.sock");
-            let socket = socket.as_path();
-
-            match std::fs::remove_file(&socket) {
-                Ok(_) => {}
-                Err(error) => match error.kind() {
-                    // Doing this because ::exists() doesn't work reliably on Windows via IntelliJ
-                    ErrorKind::NotFound => {}
-                    _ => {
-                        return Err(error.into());
-                    }
-                },
-            };
-
-            send_message(&*SocketMessage::Query(arg.state_query).as_bytes()?)?;
-
-            let listener = UnixListener::bind(&socket)?;
-            match listener.accept() {
-                Ok(incoming) => {
-                    let stream = BufReader::new(incoming.0);
-                    for line in stream.lines() {
-                        println!("{}", line?);
-                    }
-
-                    return Ok(());
-                }
-                Err(error) => {
-                    panic!("{}", error);
-                }
-            }
+            send_query(&*SocketMessage::Query(arg.state_query).as_bytes()?)?;
+        }
+        SubCommand::Health => {
+            send_query(&*SocketMessage::QueryHealth.as_bytes()?)?;
+        }
+        SubCommand::WindowList => {
+            send_query(&*SocketMessage::QueryWindowList.as_bytes()?)?;
+        }
+        SubCommand::MruWindowList => {
+            send_query(&*SocketMessage::QueryMruWindowList.as_bytes()?)?;
+        }
+        SubCommand::LearningMode(arg) => {
+            send_message(&*SocketMessage::LearningMode(arg.seconds).as_bytes()?)?;
+        }
+        SubCommand::RuleSuggestions => {
+            send_query(&*SocketMessage::QueryRuleSuggestions.as_bytes()?)?;
+        }
+        SubCommand::ConfigurationDiff => {
+            send_query(&*SocketMessage::QueryConfigurationDiff.as_bytes()?)?;
+        }
+        SubCommand::ProtocolVersion => {
+            send_query(&*SocketMessage::ProtocolVersion.as_bytes()?)?;
         }
         SubCommand::RestoreWindows => {
             let mut hwnd_json =
@@ -934,6 +2041,9 @@ fn main() -> Result<()> {
         SubCommand::ResizeAxis(arg) => {
             send_message(&*SocketMessage::ResizeWindowAxis(arg.axis, arg.sizing).as_bytes()?)?;
         }
+        SubCommand::CycleSplitRatio(arg) => {
+            send_message(&*SocketMessage::CycleSplitRatio(arg.cycle_direction).as_bytes()?)?;
+        }
         SubCommand::FocusFollowsMouse(arg) => {
             send_message(
                 &*SocketMessage::FocusFollowsMouse(arg.implementation, arg.boolean_state.into())
@@ -959,6 +2069,38 @@ fn main() -> Result<()> {
                 &*SocketMessage::IdentifyBorderOverflow(target.identifier, target.id).as_bytes()?,
             )?;
         }
+        SubCommand::IdentifyNoBorderOverflow(target) => {
+            send_message(
+                &*SocketMessage::IdentifyNoBorderOverflow(target.identifier, target.id)
+                    .as_bytes()?,
+            )?;
+        }
+        SubCommand::AddClassBlocklistPattern(arg) => {
+            send_message(&*SocketMessage::AddClassBlocklistPattern(arg.pattern).as_bytes()?)?;
+        }
+        SubCommand::RemoveClassBlocklistPattern(arg) => {
+            send_message(&*SocketMessage::RemoveClassBlocklistPattern(arg.pattern).as_bytes()?)?;
+        }
+        SubCommand::SizeConstraintRule(arg) => {
+            send_message(
+                &*SocketMessage::SizeConstraintRule(
+                    arg.identifier,
+                    arg.id,
+                    arg.constraint,
+                    arg.value,
+                )
+                .as_bytes()?,
+            )?;
+        }
+        SubCommand::AddRuleGroup(arg) => {
+            send_message(&*SocketMessage::AddRuleGroup(arg.name).as_bytes()?)?;
+        }
+        SubCommand::EndRuleGroup => {
+            send_message(&*SocketMessage::EndRuleGroup.as_bytes()?)?;
+        }
+        SubCommand::ToggleRuleGroup(arg) => {
+            send_message(&*SocketMessage::ToggleRuleGroup(arg.name).as_bytes()?)?;
+        }
         SubCommand::Manage => {
             send_message(&*SocketMessage::ManageFocusedWindow.as_bytes()?)?;
         }
@@ -977,27 +2119,123 @@ fn main() -> Result<()> {
         SubCommand::LoadResize(arg) => {
             send_message(&*SocketMessage::Load(resolve_windows_path(&arg.path)?).as_bytes()?)?;
         }
+        SubCommand::SaveWorkspaceSnapshot(arg) => {
+            send_message(
+                &*SocketMessage::SaveWorkspaceSnapshot(resolve_windows_path(&arg.path)?)
+                    .as_bytes()?,
+            )?;
+        }
+        SubCommand::LoadWorkspaceSnapshot(arg) => {
+            send_message(
+                &*SocketMessage::LoadWorkspaceSnapshot(resolve_windows_path(&arg.path)?)
+                    .as_bytes()?,
+            )?;
+        }
+        SubCommand::SpawnIntoWorkspace(arg) => {
+            send_message(
+                &*SocketMessage::SpawnIntoWorkspace(arg.command, arg.monitor, arg.workspace)
+                    .as_bytes()?,
+            )?;
+        }
         SubCommand::Subscribe(arg) => {
             send_message(&*SocketMessage::AddSubscriber(arg.named_pipe).as_bytes()?)?;
         }
         SubCommand::Unsubscribe(arg) => {
             send_message(&*SocketMessage::RemoveSubscriber(arg.named_pipe).as_bytes()?)?;
         }
+        SubCommand::WebsocketServer(arg) => {
+            send_message(&*SocketMessage::WebSocketServer(arg.port, arg.secret).as_bytes()?)?;
+        }
+        SubCommand::WebsocketAllowOrigin(arg) => {
+            send_message(&*SocketMessage::WebSocketAllowOrigin(arg.origin).as_bytes()?)?;
+        }
+        SubCommand::MirrorListen(arg) => {
+            send_message(&*SocketMessage::MirrorListen(arg.port, arg.secret).as_bytes()?)?;
+        }
+        SubCommand::MirrorConnect(arg) => {
+            send_message(&*SocketMessage::MirrorConnect(arg.address, arg.secret).as_bytes()?)?;
+        }
+        SubCommand::SendToPeer(arg) => {
+            let message = SocketMessage::from_str(&arg.message)?;
+            send_message(&*SocketMessage::PeerMessage(Box::new(message)).as_bytes()?)?;
+        }
+        SubCommand::PeerState => {
+            send_query(&*SocketMessage::QueryPeerState.as_bytes()?)?;
+        }
+        SubCommand::DefineAlias(arg) => {
+            let messages = arg
+                .messages
+                .iter()
+                .map(String::as_str)
+                .map(SocketMessage::from_str)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            send_message(&*SocketMessage::DefineAlias(arg.name, messages).as_bytes()?)?;
+        }
+        SubCommand::InvokeAlias(arg) => {
+            send_message(&*SocketMessage::InvokeAlias(arg.name).as_bytes()?)?;
+        }
         SubCommand::ToggleMouseFollowsFocus => {
             send_message(&*SocketMessage::ToggleMouseFollowsFocus.as_bytes()?)?;
         }
+        SubCommand::MouseFollowsFocusPlacement(arg) => {
+            send_message(
+                &*SocketMessage::MouseFollowsFocusPlacement(arg.placement, arg.offset)
+                    .as_bytes()?,
+            )?;
+        }
         SubCommand::MouseFollowsFocus(arg) => {
             send_message(&*SocketMessage::MouseFollowsFocus(arg.boolean_state.into()).as_bytes()?)?;
         }
+        SubCommand::StackIndicator(arg) => {
+            send_message(&*SocketMessage::StackIndicator(arg.boolean_state.into()).as_bytes()?)?;
+        }
+        SubCommand::FloatElevatedWindows(arg) => {
+            send_message(
+                &*SocketMessage::FloatElevatedWindows(arg.boolean_state.into()).as_bytes()?,
+            )?;
+        }
         SubCommand::ResizeDelta(arg) => {
             send_message(&*SocketMessage::ResizeDelta(arg.pixels).as_bytes()?)?;
         }
+        SubCommand::WorkspaceResizeDelta(arg) => {
+            send_message(
+                &*SocketMessage::WorkspaceResizeDelta(arg.monitor, arg.workspace, arg.value)
+                    .as_bytes()?,
+            )?;
+        }
+        SubCommand::FocusModeWidth(arg) => {
+            send_message(&*SocketMessage::FocusModeWidth(arg.percentage).as_bytes()?)?;
+        }
+        SubCommand::ThrottleRetiles(arg) => {
+            send_message(&*SocketMessage::ThrottleRetiles(arg.boolean_state.into()).as_bytes()?)?;
+        }
+        SubCommand::RetileFpsCap(arg) => {
+            send_message(&*SocketMessage::RetileFpsCap(arg.fps).as_bytes()?)?;
+        }
+        SubCommand::RetileBatchSize(arg) => {
+            send_message(&*SocketMessage::RetileBatchSize(arg.windows).as_bytes()?)?;
+        }
+        SubCommand::MessageRateLimit(arg) => {
+            send_message(&*SocketMessage::MessageRateLimit(arg.per_second, arg.burst).as_bytes()?)?;
+        }
         SubCommand::ToggleWindowContainerBehaviour => {
             send_message(&*SocketMessage::ToggleWindowContainerBehaviour.as_bytes()?)?;
         }
         SubCommand::WindowHidingBehaviour(arg) => {
             send_message(&*SocketMessage::WindowHidingBehaviour(arg.hiding_behaviour).as_bytes()?)?;
         }
+        SubCommand::OnEmptyWorkspace(arg) => {
+            send_message(
+                &*SocketMessage::OnEmptyWorkspace(arg.empty_workspace_behaviour).as_bytes()?,
+            )?;
+        }
+        SubCommand::HidingBehaviourRule(arg) => {
+            send_message(
+                &*SocketMessage::HidingBehaviourRule(arg.identifier, arg.id, arg.hiding_behaviour)
+                    .as_bytes()?,
+            )?;
+        }
     }
 
     Ok(())